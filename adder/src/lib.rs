@@ -231,6 +231,7 @@
     // さて、can_hold関数をバグらせたらどうなるでしょう
     // 比較演算子を反転させてやりましょう
     // やっぱ名前 can_hold_ii関数にします
+    //✔落ちたときに2つの形を絵で見たいなら ../../src/draw.rs の assert_can_hold! を使う(../../tests/can_hold_pictures.rs)
     
     impl Rectangle {
         fn can_hold_ii(&self, other: &Rectangle) -> bool {
@@ -405,6 +406,7 @@
     pub fn greeting_ii(name: &str) -> String {
         String::from("こんちゃ!")
     }
+    //✔2つをまとめて、名前が空なら確保しない版は ../../src/text.rs の greeting にある
     
     #[cfg(test)]
     mod tests_vi {
@@ -0,0 +1,113 @@
+// 四分木と全部を順に比べるやり方(線形走査)の速さ比べ
+// 外部クレートを使わないので std::time で測る(`cargo bench --bench spatial_index`)
+// 10万個の長方形に対して範囲検索と最近傍検索を繰り返す
+
+use rust_etude_dev::geometry::{Point, Rect};
+use rust_etude_dev::spatial::{EntryId, QuadTree};
+use rust_etude_dev::text;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITEMS: usize = 100_000;
+const QUERIES: usize = 1_000;
+const WORLD: f64 = 10_000.0;
+
+// 再現できるように種固定の xorshift
+struct Rng(u64);
+
+impl Rng {
+    fn unit(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn rect(&mut self, max_side: f64) -> Rect<f64> {
+        Rect::new(
+            self.unit() * WORLD,
+            self.unit() * WORLD,
+            self.unit() * max_side,
+            self.unit() * max_side,
+        )
+    }
+}
+
+fn time<T>(label: &str, f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let value = black_box(f());
+    let elapsed = start.elapsed();
+    // 全角の見出しでも揃うように表示幅で詰める
+    let pad = 20usize.saturating_sub(text::display_width(label));
+    println!(
+        "{}{} {:>10}",
+        label,
+        " ".repeat(pad),
+        format!("{:.2?}", elapsed)
+    );
+    (value, elapsed)
+}
+
+fn linear_query(rects: &[Rect<f64>], region: &Rect<f64>) -> Vec<EntryId> {
+    (0..rects.len())
+        .filter(|&i| rects[i].intersects(region))
+        .map(EntryId)
+        .collect()
+}
+
+fn linear_nearest(rects: &[Rect<f64>], p: Point<f64>) -> Option<(EntryId, f64)> {
+    let mut best: Option<(EntryId, f64)> = None;
+    for (i, r) in rects.iter().enumerate() {
+        let d = r.distance_squared_to(p);
+        if best.is_none_or(|(_, b)| d < b) {
+            best = Some((EntryId(i), d));
+        }
+    }
+    best
+}
+
+fn main() {
+    let mut rng = Rng(0x5eed);
+    let rects: Vec<Rect<f64>> = (0..ITEMS).map(|_| rng.rect(20.0)).collect();
+    let regions: Vec<Rect<f64>> = (0..QUERIES).map(|_| rng.rect(200.0)).collect();
+    let points: Vec<Point<f64>> = (0..QUERIES)
+        .map(|_| Point::new(rng.unit() * WORLD, rng.unit() * WORLD))
+        .collect();
+
+    println!("{} 個の長方形, {} 回の検索", ITEMS, QUERIES);
+    let (tree, _) = time("四分木 構築", || {
+        let mut t = QuadTree::new(Rect::new(0.0, 0.0, WORLD, WORLD));
+        for (i, r) in rects.iter().enumerate() {
+            t.insert(*r, i);
+        }
+        t
+    });
+
+    let (tree_hits, tree_range) = time("四分木 範囲検索", || {
+        regions.iter().map(|r| tree.query(r)).collect::<Vec<_>>()
+    });
+    let (scan_hits, scan_range) = time("線形走査 範囲検索", || {
+        regions
+            .iter()
+            .map(|r| linear_query(&rects, r))
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(tree_hits, scan_hits, "四分木と線形走査で結果が違う");
+
+    let (tree_near, tree_nearest) = time("四分木 最近傍", || {
+        points.iter().map(|&p| tree.nearest(p)).collect::<Vec<_>>()
+    });
+    let (scan_near, scan_nearest) = time("線形走査 最近傍", || {
+        points
+            .iter()
+            .map(|&p| linear_nearest(&rects, p))
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(tree_near, scan_near, "四分木と線形走査で結果が違う");
+
+    println!(
+        "範囲検索 {:.0}倍, 最近傍 {:.0}倍 速い",
+        scan_range.as_secs_f64() / tree_range.as_secs_f64(),
+        scan_nearest.as_secs_f64() / tree_nearest.as_secs_f64()
+    );
+}
@@ -0,0 +1,92 @@
+// statistics の mode と median を板書の版(./src/others/collections.rs)と比べる速さ比べ
+// 外部クレートを使わないので std::time で測る(`cargo bench --bench statistics`)
+// どれも 1000万個の値で測り、答えが板書の版と同じことも確かめる
+
+#[path = "../tests/notebook_statistics/mod.rs"]
+mod notebook;
+
+use rust_etude_dev::statistics;
+use rust_etude_dev::text;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const N: usize = 10_000_000;
+
+// 再現できるように種固定の xorshift
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // lo 以上 hi 未満
+    fn range(&mut self, lo: i32, hi: i32) -> i32 {
+        lo + (self.next() % (hi - lo) as u64) as i32
+    }
+}
+
+fn time<T>(label: &str, f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let value = black_box(f());
+    let elapsed = start.elapsed();
+    let pad = 28usize.saturating_sub(text::display_width(label));
+    println!(
+        "{}{} {:>10}",
+        label,
+        " ".repeat(pad),
+        format!("{:.2?}", elapsed)
+    );
+    (value, elapsed)
+}
+
+fn speedup(old: Duration, new: Duration) -> String {
+    format!("→ {:.1}倍 速い", old.as_secs_f64() / new.as_secs_f64())
+}
+
+fn main() {
+    let mut rng = Rng(0x5eed);
+    let small: Vec<i32> = (0..N).map(|_| rng.range(0, 1000)).collect();
+    let wide: Vec<i32> = (0..N)
+        .map(|_| rng.range(-100_000_000, 100_000_000))
+        .collect();
+
+    println!("{} 個の値", N);
+
+    println!("mode (値は 0〜999)");
+    let (old, old_t) = time("  板書(文字列の鍵)", || notebook::mode(&small));
+    let (new, new_t) = time("  statistics(整数の鍵)", || statistics::mode(&small));
+    assert_eq!(old, new, "mode の答えが違う");
+    println!("  {}", speedup(old_t, new_t));
+
+    // 板書の版は鍵の数だけ最大値を探し直すので、値がばらけると終わらない
+    println!("mode (値はほとんど重ならない, 板書の版は遅すぎるので省く)");
+    time("  statistics(整数の鍵)", || statistics::mode(&wide));
+
+    for (name, xs) in &[("0〜999", &small), ("±1億", &wide)] {
+        println!("median (値は {})", name);
+        let (old, old_t) = time("  板書(複製して並べ替え)", || {
+            notebook::median(&mut xs.to_vec())
+        });
+        let (new, new_t) = time("  statistics(選択)", || statistics::median(xs));
+        assert_eq!(Some(old), new, "median の答えが違う");
+        println!("  {}", speedup(old_t, new_t));
+    }
+
+    let mut sorted = wide.clone();
+    sorted.sort_unstable();
+    println!("median (整列済み)");
+    let (new, _) = time("  statistics(選択)", || statistics::median(&sorted));
+    assert_eq!(Some(notebook::median(&mut sorted)), new);
+
+    // 板書の mean は i32 で足すので、合計があふれないように正負に散らす
+    let signed: Vec<i32> = (0..N).map(|_| rng.range(-1000, 1000)).collect();
+    println!("mean (値は ±1000)");
+    let (old, old_t) = time("  板書(HashMap に合計)", || notebook::mean(&signed));
+    let (new, new_t) = time("  statistics", || statistics::mean(&signed));
+    assert_eq!(Some(old), new, "mean の答えが違う");
+    println!("  {}", speedup(old_t, new_t));
+}
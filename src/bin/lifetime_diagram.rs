@@ -0,0 +1,69 @@
+// ライフタイム図の生成・検査ツール
+//
+// 標準入力のブロックに図を描いて標準出力へ:
+//   $ cargo run --bin lifetime_diagram < block.rs
+// ファイル中の図が最新か検査(古ければ終了コード1):
+//   $ cargo run --bin lifetime_diagram -- --check src/others/lifetime.rs
+
+use rust_etude_dev::lifetime_diagram;
+use std::io::{self, Read};
+use std::process;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => generate(),
+        [flag, path] if flag == "--check" => check(path),
+        _ => {
+            eprintln!("使い方: lifetime_diagram [--check <ファイル>]");
+            process::exit(2);
+        }
+    }
+}
+
+fn generate() {
+    let mut src = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut src) {
+        eprintln!("標準入力を読めませんでした: {}", e);
+        process::exit(2);
+    }
+    let diagram = match lifetime_diagram::parse(&src) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    print!("{}", diagram.render());
+    for d in diagram.dangling() {
+        eprintln!(
+            "警告: {}行目で {} が借用した {} は死んでいるのに {}行目で使われています",
+            d.borrow_line + 1,
+            d.holder,
+            d.referent,
+            d.use_line + 1
+        );
+    }
+}
+
+fn check(path: &str) {
+    let source = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} を読めませんでした: {}", path, e);
+            process::exit(2);
+        }
+    };
+    let lines: Vec<&str> = source.lines().collect();
+    let mut stale = 0;
+    for block in lifetime_diagram::find_blocks(&source) {
+        let start = block.start;
+        if let Err(e) = lifetime_diagram::check(&lines[block].join("\n")) {
+            println!("{}:{}からの図: {}", path, start + 1, e);
+            stale += 1;
+        }
+    }
+    if stale > 0 {
+        process::exit(1);
+    }
+}
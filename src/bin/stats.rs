@@ -0,0 +1,295 @@
+// 2つの列の相関と回帰を出すツール
+//
+// CSV の2つの列を x, y の組にして、平均, 共分散, 相関係数(ピアソン, スピアマン), 回帰直線を表にする:
+//   $ cargo run --bin stats -- data.csv
+// 標準入力から読む(ファイルを渡さないか - のとき), 列は番号(1 から)か見出しの名前で選ぶ(省くと1列目と2列目):
+//   $ cargo run --bin stats -- --columns 身長,体重 < data.csv
+// 区切り文字を変える, 各行の予測値と残差も出す:
+//   $ cargo run --bin stats -- data.tsv --delimiter '\t' --residuals
+//
+// 1行目は、選んだ2つの列のどちらかが数でなければ見出しとして扱う(列を名前で選んだときは必ず見出し)
+// ほかの列(名前のラベルなど)が文字でもデータの行として読む。空の行は飛ばす
+
+use rust_etude_dev::chart;
+use rust_etude_dev::csv::{Dialect, Row};
+use rust_etude_dev::sheet::SpreadsheetCell;
+use rust_etude_dev::statistics::{self, StatsError};
+use rust_etude_dev::text;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
+
+struct Options {
+    path: Option<String>,
+    columns: (String, String),
+    delimiter: char,
+    residuals: bool,
+}
+
+fn usage() -> ! {
+    eprintln!("使い方: stats [<ファイル>] [--columns <x>,<y>] [--delimiter <文字>] [--residuals]");
+    process::exit(2);
+}
+
+fn fail(e: &dyn std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    process::exit(1);
+}
+
+fn options() -> Options {
+    let mut o = Options {
+        path: None,
+        columns: (String::from("1"), String::from("2")),
+        delimiter: ',',
+        residuals: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--columns" => {
+                let c = args.next().unwrap_or_else(|| usage());
+                let mut parts = c.splitn(2, ',');
+                match (parts.next(), parts.next()) {
+                    (Some(x), Some(y)) if !x.is_empty() && !y.is_empty() => {
+                        o.columns = (x.to_string(), y.to_string())
+                    }
+                    _ => usage(),
+                }
+            }
+            "--delimiter" => {
+                let d = args.next().unwrap_or_else(|| usage());
+                let d = if d == "\\t" { String::from("\t") } else { d };
+                let mut chars = d.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if !matches!(c, '"' | '\r' | '\n') => o.delimiter = c,
+                    _ => usage(),
+                }
+            }
+            "--residuals" => o.residuals = true,
+            "-" if o.path.is_none() => o.path = Some(arg),
+            s if s.starts_with("--") => usage(),
+            _ if o.path.is_none() => o.path = Some(arg),
+            _ => usage(),
+        }
+    }
+    o
+}
+
+fn main() {
+    let o = options();
+    let input: Box<dyn BufRead> = match o.path.as_deref() {
+        None | Some("-") => Box::new(BufReader::new(io::stdin())),
+        Some(path) => match File::open(path) {
+            Ok(f) => Box::new(BufReader::new(f)),
+            Err(e) => {
+                eprintln!("{} を読めませんでした: {}", path, e);
+                process::exit(2);
+            }
+        },
+    };
+
+    let (xs, ys, names) = read_pairs(input, &o);
+    print!("{}", report(&xs, &ys, &names));
+    if o.residuals {
+        println!();
+        print!("{}", residuals(&xs, &ys, &names));
+    }
+}
+
+// 選んだ2つの列を x, y の組にして、列の名前と一緒に返す
+fn read_pairs<R: BufRead>(input: R, o: &Options) -> (Vec<f64>, Vec<f64>, (String, String)) {
+    let mut reader = Dialect::new().delimiter(o.delimiter).reader(input);
+    let mut header: Option<Row> = None;
+    let mut picked: Option<(usize, usize)> = None;
+    let (mut xs, mut ys) = (Vec::new(), Vec::new());
+    while let Some(row) = reader.next() {
+        let row = row.unwrap_or_else(|e| fail(&e));
+        let line = reader.line();
+        if row.is_empty() {
+            continue;
+        }
+        if picked.is_none() {
+            // 最初の行: 選んだ列のどちらかが数でなければ見出し
+            let numbered = |spec: &str| spec.parse::<usize>().ok().map(|_| column(spec, None));
+            let text_at = |c: usize| matches!(row.get(c), Some(SpreadsheetCell::Text(_)));
+            let is_header = match (numbered(&o.columns.0), numbered(&o.columns.1)) {
+                (Some(x), Some(y)) => text_at(x) || text_at(y),
+                _ => true,
+            };
+            if is_header {
+                header = Some(row.clone());
+            }
+            picked = Some((
+                column(&o.columns.0, header.as_ref()),
+                column(&o.columns.1, header.as_ref()),
+            ));
+            if is_header {
+                continue;
+            }
+        }
+        let (x, y) = picked.unwrap();
+        xs.push(number(&row, x, line));
+        ys.push(number(&row, y, line));
+    }
+    let (x, y) = picked.unwrap_or_else(|| fail(&StatsError::Empty));
+    let names = (name(x, header.as_ref()), name(y, header.as_ref()));
+    (xs, ys, names)
+}
+
+// 番号(1 から)か見出しの名前から 0 始まりの列番号に
+fn column(spec: &str, header: Option<&Row>) -> usize {
+    if let Ok(n) = spec.parse::<usize>() {
+        if n == 0 {
+            fail(&"列の番号は 1 から数えます");
+        }
+        return n - 1;
+    }
+    let found = header.and_then(|h| {
+        h.iter()
+            .position(|c| matches!(c, SpreadsheetCell::Text(t) if t == spec))
+    });
+    found.unwrap_or_else(|| fail(&format!("{} という列の見出しがありません", spec)))
+}
+
+fn name(c: usize, header: Option<&Row>) -> String {
+    match header.and_then(|h| h.get(c)) {
+        Some(SpreadsheetCell::Text(t)) if !t.is_empty() => t.clone(),
+        _ => format!("{}列目", c + 1),
+    }
+}
+
+fn number(row: &Row, c: usize, line: usize) -> f64 {
+    match row.get(c) {
+        Some(SpreadsheetCell::Int(n)) => *n as f64,
+        Some(SpreadsheetCell::Float(x)) => *x,
+        Some(SpreadsheetCell::Text(t)) => fail(&format!(
+            "{}行{}列目: {:?} は数ではありません",
+            line,
+            c + 1,
+            t
+        )),
+        None => fail(&format!("{}行目: {}列目がありません", line, c + 1)),
+    }
+}
+
+// 決まらないもの(値が全部同じなど)は - にして理由を下に書く
+fn report(xs: &[f64], ys: &[f64], names: &(String, String)) -> String {
+    let mut notes = Vec::new();
+    let mut or_dash = |r: Result<f64, StatsError>| match r {
+        Ok(v) => chart::number(v),
+        Err(e) => {
+            let e = e.to_string();
+            if !notes.contains(&e) {
+                notes.push(e);
+            }
+            String::from("-")
+        }
+    };
+    let fit = statistics::linear_regression(xs, ys);
+    let mut rows = vec![
+        vec![String::from("項目"), String::from("値")],
+        vec![String::from("組の数"), xs.len().to_string()],
+        vec![
+            format!("{} の平均", names.0),
+            or_dash(statistics::mean(xs).ok_or(StatsError::Empty)),
+        ],
+        vec![
+            format!("{} の平均", names.1),
+            or_dash(statistics::mean(ys).ok_or(StatsError::Empty)),
+        ],
+        vec![
+            String::from("共分散"),
+            or_dash(statistics::covariance(xs, ys)),
+        ],
+        vec![
+            String::from("ピアソンの相関係数"),
+            or_dash(statistics::pearson(xs, ys)),
+        ],
+        vec![
+            String::from("スピアマンの順位相関係数"),
+            or_dash(statistics::spearman(xs, ys)),
+        ],
+        vec![String::from("傾き"), or_dash(fit.map(|r| r.slope))],
+        vec![String::from("切片"), or_dash(fit.map(|r| r.intercept))],
+        vec![
+            String::from("決定係数 R²"),
+            or_dash(fit.map(|r| r.r_squared)),
+        ],
+    ];
+    if let Ok(r) = fit {
+        let sign = if r.intercept < 0.0 { '-' } else { '+' };
+        rows.push(vec![
+            String::from("回帰直線"),
+            format!(
+                "{} = {} × {} {} {}",
+                names.1,
+                chart::number(r.slope),
+                names.0,
+                sign,
+                chart::number(r.intercept.abs())
+            ),
+        ]);
+    }
+    let mut out = text::table(&rows);
+    for n in notes {
+        out.push_str(&format!("- は {}\n", n));
+    }
+    out
+}
+
+fn residuals(xs: &[f64], ys: &[f64], names: &(String, String)) -> String {
+    let fit = match statistics::linear_regression(xs, ys) {
+        Ok(r) => r,
+        Err(e) => return format!("残差は出せません: {}\n", e),
+    };
+    let predicted = fit.predictions(xs);
+    let residuals = fit.residuals(xs, ys).unwrap_or_else(|e| fail(&e));
+    let mut rows = vec![vec![
+        names.0.clone(),
+        names.1.clone(),
+        String::from("予測値"),
+        String::from("残差"),
+    ]];
+    for i in 0..xs.len() {
+        rows.push(vec![
+            chart::number(xs[i]),
+            chart::number(ys[i]),
+            chart::number(predicted[i]),
+            chart::number(residuals[i]),
+        ]);
+    }
+    text::table(&rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(csv: &str, x: &str, y: &str) -> (Vec<f64>, Vec<f64>, (String, String)) {
+        let o = Options {
+            path: None,
+            columns: (x.to_string(), y.to_string()),
+            delimiter: ',',
+            residuals: false,
+        };
+        read_pairs(csv.as_bytes(), &o)
+    }
+
+    #[test]
+    fn label_column_does_not_make_a_header() {
+        let (xs, ys, names) = read("alice,1,2\nbob,2,4\ncarol,3,5\n", "2", "3");
+        assert_eq!((xs, ys), (vec![1.0, 2.0, 3.0], vec![2.0, 4.0, 5.0]));
+        assert_eq!(names, (String::from("2列目"), String::from("3列目")));
+    }
+
+    #[test]
+    fn header_is_read_from_the_selected_columns() {
+        let csv = "名前,身長,体重\nalice,160,50\nbob,170,62\n";
+        let (xs, _, names) = read(csv, "2", "3");
+        assert_eq!(xs, vec![160.0, 170.0]);
+        assert_eq!(names, (String::from("身長"), String::from("体重")));
+        let (_, ys, names) = read(csv, "身長", "体重");
+        assert_eq!(ys, vec![50.0, 62.0]);
+        assert_eq!(names.1, "体重");
+    }
+}
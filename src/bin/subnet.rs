@@ -0,0 +1,145 @@
+// サブネット計算のツール
+//
+// CIDR ごとにネットワーク, マスク, ブロードキャスト, ホストの範囲と数を表にする:
+//   $ cargo run --bin subnet -- 192.168.1.10/24 10.0.0.0/31 2001:db8::/48
+// 分割(各 CIDR を /26 に分けた一覧):
+//   $ cargo run --bin subnet -- 192.168.0.0/24 --split 26
+// 集約(渡したものをまとめた一番少ない CIDR の並び):
+//   $ cargo run --bin subnet -- 10.0.0.0/24 10.0.1.0/24 --aggregate
+// 所属(どの CIDR にアドレスが入っているか):
+//   $ cargo run --bin subnet -- 10.0.0.0/8 192.168.0.0/16 --contains 10.1.2.3
+//
+// 種類は範囲の全部のアドレスについて見る(0.0.0.0/0 のように種類の違う区域をまたぐものは 混在)
+
+use rust_etude_dev::ip::IpAddress;
+use rust_etude_dev::numerals::{self, Style};
+use rust_etude_dev::subnet::{self, IpNet};
+use rust_etude_dev::text;
+use std::process;
+
+// --split で1つの CIDR から出す行の上限
+const SPLIT_LIMIT: usize = 256;
+
+enum Mode {
+    Summary,
+    Split(u8),
+    Aggregate,
+    Contains(IpAddress),
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "使い方: subnet <CIDR>... [--split <接頭辞長> | --aggregate | --contains <アドレス>]"
+    );
+    process::exit(2);
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut nets = Vec::new();
+    let mut mode = Mode::Summary;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--split" => {
+                let n = args.next().unwrap_or_else(|| usage());
+                let prefix = n
+                    .trim_start_matches('/')
+                    .parse()
+                    .unwrap_or_else(|_| usage());
+                mode = Mode::Split(prefix);
+            }
+            "--aggregate" => mode = Mode::Aggregate,
+            "--contains" => {
+                let a = args.next().unwrap_or_else(|| usage());
+                match IpAddress::parse(&a) {
+                    Ok(a) => mode = Mode::Contains(a),
+                    Err(e) => fail(&e),
+                }
+            }
+            s if s.starts_with("--") => usage(),
+            s => match IpNet::parse(s) {
+                Ok(n) => nets.push(n),
+                Err(e) => fail(&e),
+            },
+        }
+    }
+    if nets.is_empty() {
+        usage();
+    }
+
+    match mode {
+        Mode::Summary => print!("{}", summary(&nets)),
+        Mode::Split(prefix) => {
+            for n in &nets {
+                let parts = match n.subnets(prefix) {
+                    Ok(p) => p,
+                    Err(e) => fail(&e),
+                };
+                println!("{} を /{} に分割", n.trunc(), prefix);
+                let parts: Vec<IpNet> = parts.take(SPLIT_LIMIT + 1).collect();
+                print!("{}", summary(&parts[..parts.len().min(SPLIT_LIMIT)]));
+                if parts.len() > SPLIT_LIMIT {
+                    println!("(先頭の {} 個だけ表示)", SPLIT_LIMIT);
+                }
+            }
+        }
+        Mode::Aggregate => print!("{}", summary(&subnet::aggregate(&nets))),
+        Mode::Contains(addr) => {
+            let mut rows = vec![vec![String::from("CIDR"), format!("{} を含む", addr)]];
+            for n in &nets {
+                let yes = if n.contains(&addr) {
+                    "はい"
+                } else {
+                    "いいえ"
+                };
+                rows.push(vec![n.to_string(), String::from(yes)]);
+            }
+            print!("{}", text::table(&rows));
+            if !nets.iter().any(|n| n.contains(&addr)) {
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn fail(e: &dyn std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    process::exit(1);
+}
+
+fn summary(nets: &[IpNet]) -> String {
+    let mut rows = vec![[
+        "CIDR",
+        "ネットワーク",
+        "ネットマスク",
+        "ブロードキャスト",
+        "ホスト範囲",
+        "ホスト数",
+        "種類",
+    ]
+    .iter()
+    .map(|s| String::from(*s))
+    .collect::<Vec<_>>()];
+    for n in nets {
+        let (first, last) = n.hosts();
+        rows.push(vec![
+            n.to_string(),
+            n.network().to_string(),
+            n.netmask().to_string(),
+            n.broadcast().map_or(String::from("-"), |b| b.to_string()),
+            format!("{} - {}", first, last),
+            count(n),
+            n.scope()
+                .map_or(String::from("混在"), |s| format!("{:?}", s)),
+        ]);
+    }
+    text::table(&rows)
+}
+
+// ホスト数を3桁区切りで(IPv6 の /0 だけは u128 に収まらないので 2^128 と書く)
+fn count(n: &IpNet) -> String {
+    if !n.is_ipv4() && n.prefix() == 0 {
+        return String::from("2^128");
+    }
+    numerals::format_unsigned(n.host_count(), Style::Thousands)
+}
@@ -0,0 +1,75 @@
+// 文字列の変換ツール
+//
+// 標準入力を1行ずつ変換して標準出力へ(1行ずつ流すので、どんなに大きなファイルでも1行分しかメモリに載せない):
+//   $ cargo run --bin transform -- --with pig-latin,rot13 < input.txt
+// 使える変換の一覧:
+//   $ cargo run --bin transform -- --list
+//
+// 行の終わり(LF か CRLF)はそのまま残し、変換は行の中身だけにかける
+
+use rust_etude_dev::text;
+use rust_etude_dev::transform::{Pipeline, Transformer, BUILTINS};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+
+fn usage() -> ! {
+    eprintln!("使い方: transform --with <変換>[,<変換>...] | --list");
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let pipeline = match args.as_slice() {
+        [flag] if flag == "--list" => return list(),
+        [flag, names] if flag == "--with" => Pipeline::parse(names),
+        _ => usage(),
+    };
+    let pipeline = pipeline.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    if let Err(e) = stream(&pipeline) {
+        // head などに渡して途中で閉じられたのは失敗ではない
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            return;
+        }
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn list() {
+    let mut rows = vec![vec!["名前", "説明"]];
+    rows.extend(BUILTINS.iter().map(|(name, about)| vec![*name, *about]));
+    print!("{}", text::table(&rows));
+}
+
+fn stream(pipeline: &Pipeline) -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut input = stdin.lock();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut buf = Vec::new();
+    let mut line = 0;
+    loop {
+        buf.clear();
+        if input.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        line += 1;
+        let body = buf.len()
+            - [&b"\r\n"[..], b"\n"]
+                .iter()
+                .find(|end| buf.ends_with(end))
+                .map_or(0, |end| end.len());
+        let s = std::str::from_utf8(&buf[..body]).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}行目が UTF-8 ではありません", line),
+            )
+        })?;
+        out.write_all(pipeline.apply(s).as_bytes())?;
+        out.write_all(&buf[body..])?;
+    }
+    out.flush()
+}
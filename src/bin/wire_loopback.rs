@@ -0,0 +1,197 @@
+// wire の見本: 127.0.0.1 で2つのスレッドが Message をやりとりする
+//
+// 送り手は Message をバイナリ(--text ならテキスト)で送り、
+// 受け手は届いたものを Dispatcher に当てて、その後の状態を Write にして送り返す:
+//   $ cargo run --bin wire_loopback
+//   $ cargo run --bin wire_loopback -- --text
+// 標準入力にテキストの行(MOVE 3 -4 など)を渡すと、見本の代わりにそれを送る:
+//   $ printf 'MOVE 1 2\nWRITE "hi"\nQUIT\n' | cargo run --bin wire_loopback -- --stdin
+//
+// 受け手は1件ごとに返事を書くので、送り手は書くのと読むのを別のスレッドでする
+// (全部書いてから読むと、長い台本ではどちらのバッファも詰まって両方止まる)
+
+use rust_etude_dev::message::{Dispatcher, Message};
+use rust_etude_dev::wire::{self, BinaryDecoder, TextDecoder, WireError};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::thread;
+
+#[derive(Clone, Copy)]
+enum Format {
+    Binary,
+    Text,
+}
+
+// 2つの書き方を同じように扱うための包み
+enum Decoder {
+    Binary(BinaryDecoder),
+    Text(TextDecoder),
+}
+
+impl Decoder {
+    fn new(format: Format) -> Decoder {
+        match format {
+            Format::Binary => Decoder::Binary(BinaryDecoder::new()),
+            Format::Text => Decoder::Text(TextDecoder::new()),
+        }
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        match self {
+            Decoder::Binary(d) => d.feed(bytes),
+            Decoder::Text(d) => d.feed(bytes),
+        }
+    }
+
+    fn next_message(&mut self) -> Result<Option<Message>, WireError> {
+        match self {
+            Decoder::Binary(d) => d.next_message(),
+            Decoder::Text(d) => d.next_message(),
+        }
+    }
+}
+
+fn encode(format: Format, m: &Message) -> Result<Vec<u8>, WireError> {
+    match format {
+        Format::Binary => wire::encode_binary(m),
+        Format::Text => Ok(wire::encode_text(m).into_bytes()),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("使い方: wire_loopback [--text] [--stdin]");
+    process::exit(2);
+}
+
+fn main() {
+    let mut format = Format::Binary;
+    let mut from_stdin = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--text" => format = Format::Text,
+            "--stdin" => from_stdin = true,
+            _ => usage(),
+        }
+    }
+    let script = if from_stdin {
+        read_script()
+    } else {
+        vec![
+            Message::Move { x: 3, y: -4 },
+            Message::Write(String::from("こんにちは")),
+            Message::ChangeColor(255, 128, 0),
+            Message::ChangeColor(0, 300, 0),
+            Message::Write(String::from(", 世界")),
+            Message::Quit,
+        ]
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap_or_else(|e| fail(&e));
+    let addr = listener.local_addr().unwrap_or_else(|e| fail(&e));
+    println!("{} で待ち受け", addr);
+    let server = thread::spawn(move || -> io::Result<()> {
+        let (stream, peer) = listener.accept()?;
+        println!("[受け手] {} とつながった", peer);
+        serve(stream, format)
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap_or_else(|e| fail(&e));
+    let mut out = stream.try_clone().unwrap_or_else(|e| fail(&e));
+    let sender = thread::spawn(move || -> io::Result<()> {
+        if let Format::Text = format {
+            out.write_all(wire::text_header().as_bytes())?;
+        }
+        for m in &script {
+            match encode(format, m) {
+                Ok(bytes) => {
+                    println!("[送り手] → {:?}", m);
+                    out.write_all(&bytes)?;
+                }
+                Err(e) => println!("[送り手] 送れません: {}", e),
+            }
+        }
+        // 送り終わったことを知らせる(Quit が無くても受け手が止まるように)
+        out.shutdown(std::net::Shutdown::Write)
+    });
+
+    let mut d = Decoder::new(format);
+    let mut buf = [0; 64];
+    loop {
+        let n = stream.read(&mut buf).unwrap_or_else(|e| fail(&e));
+        if n == 0 {
+            break;
+        }
+        d.feed(&buf[..n]);
+        loop {
+            match d.next_message() {
+                Ok(Some(Message::Write(reply))) => println!("[送り手] ← {}", reply),
+                Ok(Some(m)) => println!("[送り手] ← {:?}", m),
+                Ok(None) => break,
+                Err(e) => println!("[送り手] 読めない返事: {}", e),
+            }
+        }
+    }
+    for (who, handle) in [("送り手", sender), ("受け手", server)] {
+        match handle.join() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => fail(&e),
+            Err(_) => fail(&format!("{}のスレッドがパニックしました", who)),
+        }
+    }
+}
+
+// 届いた Message を当てて、状態かエラーを Write で返す
+fn serve(mut stream: TcpStream, format: Format) -> io::Result<()> {
+    let mut dispatcher = Dispatcher::new();
+    let mut d = Decoder::new(format);
+    let mut buf = [0; 64];
+    loop {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        d.feed(&buf[..n]);
+        loop {
+            let reply = match d.next_message() {
+                Ok(Some(m)) => match dispatcher.dispatch(m) {
+                    Ok(state) => state.to_string(),
+                    Err(e) => format!("エラー: {}", e),
+                },
+                Ok(None) => break,
+                Err(e) => format!("読めません: {}", e),
+            };
+            // 状態が長すぎてフレームに入らなければ、そのことを返事にする
+            let bytes = encode(format, &Message::Write(reply))
+                .or_else(|e| encode(format, &Message::Write(format!("返事を送れません: {}", e))));
+            stream.write_all(&bytes.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?)?;
+        }
+    }
+}
+
+fn read_script() -> Vec<Message> {
+    let mut src = String::new();
+    io::stdin()
+        .read_to_string(&mut src)
+        .unwrap_or_else(|e| fail(&e));
+    let mut d = TextDecoder::new();
+    d.feed(src.as_bytes());
+    let mut script = Vec::new();
+    loop {
+        match d.next_message() {
+            Ok(Some(m)) => script.push(m),
+            Ok(None) => break,
+            Err(e) => fail(&e),
+        }
+    }
+    match d.finish() {
+        Ok(last) => script.extend(last),
+        Err(e) => fail(&e),
+    }
+    script
+}
+
+fn fail(e: &dyn std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    process::exit(1);
+}
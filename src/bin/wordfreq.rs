@@ -0,0 +1,200 @@
+// 単語の出現回数を数えるツール
+//
+// ファイルやディレクトリ(中のファイルを全部, 名前の順)の単語を数えて、多い順に表にする(何も渡さなければ標準入力):
+//   $ cargo run --bin wordfreq -- README.md src --top 20
+// 大文字小文字を分ける, ストップワードを除く(en は組み込みの英語, それ以外はファイル: 1行1語で # から後は無視), 2語ずつ数える:
+//   $ cargo run --bin wordfreq -- book.txt --keep-case --stopwords en --stopwords stop.txt --ngram 2
+// CSV や JSON で書き出して、別々に数えた集計を後で足し合わせる(足すのは --top で切っていない集計):
+//   $ cargo run --bin wordfreq -- part1/ --format csv > a.csv
+//   $ cargo run --bin wordfreq -- part2/ --format json > b.json
+//   $ cargo run --bin wordfreq -- --merge a.csv b.json --top 10
+//
+// ファイルは1行ずつ読むので大きくてもよい。ディレクトリの中の . で始まるもの(.git など)は見ない
+// ディレクトリの中のシンボリックリンクはたどらない(自分の親を指すリンクで止まらなくならないように)
+// UTF-8 でないファイル(画像など)は警告を出して飛ばす
+
+use rust_etude_dev::wordfreq::{Counter, Counts, Format, ENGLISH_STOPWORDS};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process;
+
+struct Options {
+    paths: Vec<PathBuf>,
+    merge: bool,
+    top: Option<usize>,
+    format: Format,
+    counter: Counter,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "使い方: wordfreq [<ファイルかディレクトリ>...] [--top <k>] [--ngram <n>] [--keep-case] \
+         [--stopwords en|<ファイル>]... [--format table|csv|json]\n       \
+         wordfreq --merge <集計ファイル>... [--top <k>] [--format table|csv|json]"
+    );
+    process::exit(2);
+}
+
+fn fail(e: &dyn std::fmt::Display) -> ! {
+    eprintln!("{}", e);
+    process::exit(1);
+}
+
+fn options() -> Options {
+    let mut o = Options {
+        paths: Vec::new(),
+        merge: false,
+        top: None,
+        format: Format::Table,
+        counter: Counter::new(),
+    };
+    let mut args = std::env::args().skip(1);
+    // 設定は fold_case → stopwords → ngram の順で組む(ストップワードはどの順でも大文字小文字を問わない)
+    let (mut keep_case, mut n, mut stopwords) = (false, 1, Vec::new());
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--merge" => o.merge = true,
+            "--keep-case" => keep_case = true,
+            "--top" => o.top = Some(value().parse().unwrap_or_else(|_| usage())),
+            "--ngram" => match value().parse() {
+                Ok(k) if k >= 1 => n = k,
+                _ => usage(),
+            },
+            "--format" => o.format = value().parse().unwrap_or_else(|e: String| fail(&e)),
+            "--stopwords" => match value().as_str() {
+                "en" => stopwords.extend(ENGLISH_STOPWORDS.iter().map(|w| w.to_string())),
+                path => stopwords.extend(stopword_file(path)),
+            },
+            s if s.starts_with("--") => usage(),
+            _ => o.paths.push(PathBuf::from(arg)),
+        }
+    }
+    if o.merge && o.paths.is_empty() {
+        usage();
+    }
+    o.counter = Counter::new()
+        .fold_case(!keep_case)
+        .stopwords(stopwords)
+        .ngram(n);
+    o
+}
+
+// 1行1語(# から後と前後の空白は無視)
+fn stopword_file(path: &str) -> Vec<String> {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|e| fail(&format!("{} を読めませんでした: {}", path, e)));
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim().to_string())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn main() {
+    let mut o = options();
+    let counts = if o.merge {
+        merge(&o.paths)
+    } else if o.paths.is_empty() {
+        let stdin = io::stdin();
+        count_reader(&mut o.counter, stdin.lock())
+            .unwrap_or_else(|e| fail(&format!("標準入力: {}", e)))
+    } else {
+        let mut files = Vec::new();
+        for path in &o.paths {
+            collect_files(path, &mut files);
+        }
+        let mut all = Counts::new();
+        for file in &files {
+            let counted =
+                File::open(file).and_then(|f| count_reader(&mut o.counter, BufReader::new(f)));
+            match counted {
+                Ok(c) => all.merge(c).unwrap_or_else(|e| fail(&e)),
+                Err(e) => eprintln!("警告: {} を飛ばします: {}", file.display(), e),
+            }
+        }
+        all
+    };
+    print!("{}", counts.render(o.format, o.top));
+}
+
+// ディレクトリなら中を名前の順にたどって、ファイルだけ集める
+// 引数で渡されたリンクはたどるが、中で見つけたディレクトリへのリンクは飛ばす
+fn collect_files(path: &Path, out: &mut Vec<PathBuf>) {
+    if !path.is_dir() {
+        out.push(path.to_path_buf());
+        return;
+    }
+    collect_dir(path, out);
+}
+
+fn collect_dir(path: &Path, out: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(path)
+        .unwrap_or_else(|e| fail(&format!("{} を読めませんでした: {}", path.display(), e)));
+    let mut children: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            !p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'))
+        })
+        .collect();
+    children.sort();
+    for child in children {
+        match fs::symlink_metadata(&child) {
+            Ok(m) if m.is_dir() => collect_dir(&child, out),
+            Ok(m) if m.file_type().is_symlink() && child.is_dir() => {
+                eprintln!(
+                    "警告: {} はディレクトリへのリンクなので飛ばします",
+                    child.display()
+                )
+            }
+            Ok(_) => out.push(child),
+            Err(e) => eprintln!("警告: {} を飛ばします: {}", child.display(), e),
+        }
+    }
+}
+
+// 1つの文書として数える(途中で読めなくなったらそこまでの分も捨てる)
+fn count_reader<R: BufRead>(counter: &mut Counter, mut input: R) -> io::Result<Counts> {
+    let mut buf = Vec::new();
+    let mut line = 0;
+    loop {
+        buf.clear();
+        let read = input.read_until(b'\n', &mut buf);
+        let s = match read {
+            Ok(0) => break,
+            Ok(_) => std::str::from_utf8(&buf).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}行目が UTF-8 ではありません", line + 1),
+                )
+            }),
+            Err(e) => Err(e),
+        };
+        match s {
+            Ok(s) => counter.add(s),
+            Err(e) => {
+                counter.take();
+                return Err(e);
+            }
+        }
+        line += 1;
+    }
+    Ok(counter.take())
+}
+
+fn merge(paths: &[PathBuf]) -> Counts {
+    let mut all = Counts::new();
+    for path in paths {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|e| fail(&format!("{} を読めませんでした: {}", path.display(), e)));
+        match Counts::parse(&text) {
+            Ok(c) => all
+                .merge(c)
+                .unwrap_or_else(|e| fail(&format!("{}: {}", path.display(), e))),
+            Err(e) => fail(&format!("{}: {}", path.display(), e)),
+        }
+    }
+    all
+}
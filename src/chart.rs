@@ -0,0 +1,623 @@
+// 分布を端末で見るための図
+// statistics の mean, median, mode の数字だけでは分布の形が分からないので、
+// ヒストグラム(区間ごとの個数)に分けて、横棒グラフ, スパークライン, 箱ひげ図 にして描く
+// 線はブロック要素(█ ▏ ▁ ░ など)と罫線で描き、平均, 中央値, 最頻値 に印を付ける
+//
+//     印   + 平均   ┃ 中央値   ^ 最頻値   * 印が重なったところ
+//
+//     横棒グラフ(collections.rs の使用例の値)    箱ひげ図(同じ値)
+//     [40, 60)   ████████  3  ← 最頻値             ├──────^░░┃░+░░░───────────┤
+//     [60, 80)   █████▍    2  ← 平均, 中央値       43                        98
+//     [80, 100]  █████▍    2                       + 平均 66.5714  ┃ 中央値 63  ^ 最頻値 57  ░ 57〜74
+//
+// NaN と ±inf は数えない(描く前に取り除く)
+
+use crate::statistics::{self, Interpolation, Number, StatsError};
+use crate::text;
+use std::fmt;
+
+/// 印に使う文字
+pub const MEAN_MARK: char = '+';
+pub const MEDIAN_MARK: char = '┃';
+pub const MODE_MARK: char = '^';
+const CLASH_MARK: char = '*';
+
+// 本数の上限(幅を細かくしすぎて Vec が膨らまないように)
+const MAX_BINS: usize = 10_000;
+
+/// ヒストグラムの区間の決め方
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binning {
+    Width(f64),       // 幅を決める(区間の端は幅の倍数にそろえる)
+    Count(usize),     // 本数を決めて、最小値から最大値までを等分する
+    Sturges,          // log2(n) + 1 本(ceil)
+    FreedmanDiaconis, // 幅 2 * IQR / n^(1/3)。IQR が 0 なら Sturges
+}
+
+/// 区間1本([lo, hi) で、最後の1本だけ [lo, hi])
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bin {
+    pub lo: f64,
+    pub hi: f64,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    start: f64,
+    width: f64,
+    // 右端. start + width * 本数 は端数の誤差で最大値に届かないことがあるので別に持つ
+    end: f64,
+    counts: Vec<usize>,
+}
+
+// NaN と ±inf を除いて f64 に(inf が残ると区間の幅が inf になる)
+fn finite<T: Number>(xs: &[T]) -> Vec<f64> {
+    xs.iter()
+        .map(|x| x.to_f64())
+        .filter(|x| x.is_finite())
+        .collect()
+}
+
+impl Histogram {
+    pub fn new<T: Number>(xs: &[T], binning: Binning) -> Result<Histogram, StatsError> {
+        let xs = finite(xs);
+        let (min, max) = match (statistics::min(&xs), statistics::max(&xs)) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return Err(StatsError::Empty),
+        };
+        let span = max - min;
+        let sturges = (xs.len() as f64).log2().ceil() as usize + 1;
+        let (start, width, bins) = match binning {
+            Binning::Width(w) if !(w > 0.0 && w.is_finite()) => {
+                return Err(StatsError::BinWidth(w))
+            }
+            Binning::Width(w) => {
+                let start = ((min / w).floor() * w).min(min);
+                let bins = ((max - start) / w).floor() + 1.0;
+                if bins > MAX_BINS as f64 {
+                    return Err(StatsError::BinWidth(w));
+                }
+                (start, w, bins as usize)
+            }
+            Binning::Count(n) if n == 0 || n > MAX_BINS => return Err(StatsError::BinCount(n)),
+            Binning::Count(n) => (min, span / n as f64, n),
+            Binning::Sturges => (min, span / sturges as f64, sturges),
+            Binning::FreedmanDiaconis => {
+                let iqr = statistics::iqr(&xs).unwrap_or(0.0);
+                if iqr > 0.0 {
+                    let w = 2.0 * iqr / (xs.len() as f64).cbrt();
+                    let bins = ((span / w).ceil() as usize).clamp(1, MAX_BINS);
+                    (min, span / bins as f64, bins)
+                } else {
+                    (min, span / sturges as f64, sturges)
+                }
+            }
+        };
+        // 全部同じ値なら幅 1 (か決めた幅)の1本にまとめる
+        let (width, bins) = if span == 0.0 {
+            match binning {
+                Binning::Width(w) => (w, 1),
+                _ => (1.0, 1),
+            }
+        } else {
+            (width, bins)
+        };
+        let end = (start + width * bins as f64).max(max);
+        let mut h = Histogram {
+            start,
+            width,
+            end,
+            counts: vec![0; bins],
+        };
+        for &x in &xs {
+            let i = h.index_of(x).expect("最小値から最大値までは区間に入る");
+            h.counts[i] += 1;
+        }
+        Ok(h)
+    }
+
+    /// x が入る区間の番号
+    pub fn index_of(&self, x: f64) -> Option<usize> {
+        let last = self.counts.len() - 1;
+        if x.is_nan() || x < self.start || x > self.edge(last + 1) {
+            return None;
+        }
+        // 端数の誤差で1本ずれることがあるので、区間の端と比べて直す
+        let mut i = (((x - self.start) / self.width).floor() as usize).min(last);
+        if i > 0 && x < self.edge(i) {
+            i -= 1;
+        } else if i < last && x >= self.edge(i + 1) {
+            i += 1;
+        }
+        Some(i)
+    }
+
+    // i 本目の左端(counts.len() なら右端)
+    fn edge(&self, i: usize) -> f64 {
+        if i == self.counts.len() {
+            self.end
+        } else {
+            self.start + self.width * i as f64
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// 数えた値の数
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn bins(&self) -> Vec<Bin> {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| Bin {
+                lo: self.edge(i),
+                hi: self.edge(i + 1),
+                count,
+            })
+            .collect()
+    }
+
+    /// 横棒グラフ(1行に1本, 一番長い棒が width 文字)
+    /// 平均, 中央値, 最頻値 の入る区間の行には ← で書き添える
+    pub fn bars(&self, marks: Option<&Marks>, width: usize) -> String {
+        let most = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let last = self.counts.len() - 1;
+        let labels: Vec<String> = self
+            .bins()
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let close = if i == last { ']' } else { ')' };
+                format!("[{}, {}{}", number(b.lo), number(b.hi), close)
+            })
+            .collect();
+        let label_width = labels
+            .iter()
+            .map(|l| text::display_width(l))
+            .max()
+            .unwrap_or(0);
+        let count_width = most.to_string().len();
+        let mut out = String::new();
+        for (i, label) in labels.iter().enumerate() {
+            let count = self.counts[i];
+            let bar = bar(count, most, width);
+            out.push_str(&format!(
+                "{}{}  {}{}  {:>cw$}",
+                label,
+                " ".repeat(label_width - text::display_width(label)),
+                bar,
+                " ".repeat(width - bar.chars().count()),
+                count,
+                cw = count_width
+            ));
+            if let Some(m) = marks {
+                let names: Vec<&str> = m
+                    .named()
+                    .into_iter()
+                    .filter(|&(_, x)| self.index_of(x) == Some(i))
+                    .map(|(name, _)| name)
+                    .collect();
+                if !names.is_empty() {
+                    out.push_str("  ← ");
+                    out.push_str(&names.join(", "));
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// 1本を1文字にしたスパークライン(0 個の区間は空白)
+    /// marks を渡すと、下の行に印を、その下に凡例を書く
+    pub fn sparkline(&self, marks: Option<&Marks>) -> String {
+        let most = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut out: String = self
+            .counts
+            .iter()
+            .map(|&c| {
+                if c == 0 {
+                    ' '
+                } else {
+                    // 1 個でも一番低い ▁ は出す
+                    LEVELS[(c * 7).div_ceil(most).min(7)]
+                }
+            })
+            .collect();
+        if let Some(m) = marks {
+            let mut line = vec![' '; self.counts.len()];
+            for (mark, x) in m.marked() {
+                if let Some(i) = self.index_of(x) {
+                    put(&mut line, i, mark);
+                }
+            }
+            out.push('\n');
+            out.push_str(line.iter().collect::<String>().trim_end());
+            out.push('\n');
+            out.push_str(&m.legend());
+        }
+        out.push('\n');
+        out
+    }
+}
+
+// スパークラインの高さ(低い順)
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// 横棒の端数(1/8 刻み)
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+fn bar(count: usize, most: usize, width: usize) -> String {
+    let eighths = (count * width * 8 + most / 2) / most;
+    let mut s = "█".repeat(eighths / 8);
+    let rest = eighths % 8;
+    if rest > 0 {
+        s.push(EIGHTHS[rest]);
+    }
+    s
+}
+
+// 印を置く(もう印があれば * にする)
+fn put(line: &mut [char], col: usize, mark: char) {
+    let marks = [MEAN_MARK, MEDIAN_MARK, MODE_MARK, CLASH_MARK];
+    line[col] = if marks.contains(&line[col]) {
+        CLASH_MARK
+    } else {
+        mark
+    };
+}
+
+/// 目盛りや凡例の数(小数は4桁まで, 末尾の 0 は削る)
+pub fn number(x: f64) -> String {
+    let s = format!("{:.4}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// 図に付ける印の位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marks {
+    pub mean: f64,
+    pub median: f64,
+    pub modes: Vec<f64>, // どの値も1回ずつなら最頻値は無しにする
+}
+
+impl Marks {
+    pub fn of<T: Number>(xs: &[T]) -> Option<Marks> {
+        let xs = finite(xs);
+        let modes = statistics::mode(&xs);
+        let repeated = modes
+            .first()
+            .is_some_and(|&m| xs.iter().filter(|&&x| x == m).count() > 1);
+        Some(Marks {
+            mean: statistics::mean(&xs)?,
+            median: statistics::median(&xs)?,
+            modes: if repeated { modes } else { Vec::new() },
+        })
+    }
+
+    // 書く順(後の方が上に重なる)
+    fn marked(&self) -> Vec<(char, f64)> {
+        let mut v: Vec<(char, f64)> = self.modes.iter().map(|&m| (MODE_MARK, m)).collect();
+        v.push((MEAN_MARK, self.mean));
+        v.push((MEDIAN_MARK, self.median));
+        v
+    }
+
+    fn named(&self) -> Vec<(&'static str, f64)> {
+        let mut v = vec![("平均", self.mean), ("中央値", self.median)];
+        v.extend(self.modes.iter().map(|&m| ("最頻値", m)));
+        v
+    }
+
+    /// + 平均 66.5714  ┃ 中央値 63  ^ 最頻値 57
+    pub fn legend(&self) -> String {
+        let mut parts = vec![
+            format!("{} 平均 {}", MEAN_MARK, number(self.mean)),
+            format!("{} 中央値 {}", MEDIAN_MARK, number(self.median)),
+        ];
+        if !self.modes.is_empty() {
+            let modes: Vec<String> = self.modes.iter().map(|&m| number(m)).collect();
+            parts.push(format!("{} 最頻値 {}", MODE_MARK, modes.join(", ")));
+        }
+        parts.join("  ")
+    }
+}
+
+impl fmt::Display for Marks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.legend())
+    }
+}
+
+/// 値の並び(時系列など)を1つ1文字のスパークラインにする。最小値が ▁ で最大値が █
+/// 全部同じ値なら真ん中の高さ
+pub fn sparkline<T: Number>(series: &[T]) -> String {
+    let xs = finite(series);
+    let (min, max) = match (statistics::min(&xs), statistics::max(&xs)) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return String::new(),
+    };
+    xs.iter()
+        .map(|&x| {
+            if max == min {
+                LEVELS[3]
+            } else {
+                LEVELS[(((x - min) / (max - min)) * 7.0).round() as usize]
+            }
+        })
+        .collect()
+}
+
+/// 横向きの箱ひげ図(width 文字, 8 未満なら 8)
+/// 箱(░)は第1〜第3四分位数, ひげ(├─┤)は箱から 1.5×IQR 以内で一番遠い値まで, その外は外れ値(·)
+/// 下の行に最小値と最大値の目盛り, その下に凡例
+pub fn box_plot<T: Number>(xs: &[T], width: usize) -> Option<String> {
+    let mut xs = finite(xs);
+    xs.sort_unstable_by(f64::total_cmp);
+    let q = statistics::quantiles(&xs, &[0.25, 0.75], Interpolation::Linear).ok()?;
+    let (q1, q3) = (q[0], q[1]);
+    let marks = Marks::of(&xs)?;
+    let iqr = q3 - q1;
+    let (fence_lo, fence_hi) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+    let (min, max) = (xs[0], xs[xs.len() - 1]);
+    let whisker_lo = xs.iter().copied().find(|&x| x >= fence_lo).unwrap_or(min);
+    let whisker_hi = xs
+        .iter()
+        .rev()
+        .copied()
+        .find(|&x| x <= fence_hi)
+        .unwrap_or(max);
+
+    let width = width.max(8);
+    let col = |x: f64| {
+        if max == min {
+            width / 2
+        } else {
+            (((x - min) / (max - min)) * (width - 1) as f64).round() as usize
+        }
+    };
+    let mut line = vec![' '; width];
+    for &x in &xs {
+        if x < whisker_lo || x > whisker_hi {
+            line[col(x)] = '·';
+        }
+    }
+    for c in line
+        .iter_mut()
+        .take(col(whisker_hi) + 1)
+        .skip(col(whisker_lo))
+    {
+        *c = '─';
+    }
+    line[col(whisker_lo)] = '├';
+    line[col(whisker_hi)] = '┤';
+    for c in line.iter_mut().take(col(q3) + 1).skip(col(q1)) {
+        *c = '░';
+    }
+    for (mark, x) in marks.marked() {
+        put(&mut line, col(x), mark);
+    }
+
+    let (lo, hi) = (number(min), number(max));
+    let gap = width.saturating_sub(lo.len() + hi.len()).max(1);
+    Some(format!(
+        "{}\n{}{}{}\n{}  ░ {}〜{}\n",
+        line.iter().collect::<String>().trim_end(),
+        lo,
+        " ".repeat(gap),
+        hi,
+        marks.legend(),
+        number(q1),
+        number(q3)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // collections.rs の使用例の値
+    const SAMPLE: [i32; 7] = [43, 57, 57, 63, 66, 82, 98];
+
+    #[test]
+    fn binning_rules() {
+        let xs: Vec<i32> = (0..100).collect();
+        // ceil(log2 100) + 1 = 8 本
+        let h = Histogram::new(&xs, Binning::Sturges).unwrap();
+        assert_eq!(h.counts().len(), 8);
+        assert_eq!(h.total(), 100);
+        // IQR 49.5 なので幅 99 / 4.64 → 22 くらいで 5 本
+        let h = Histogram::new(&xs, Binning::FreedmanDiaconis).unwrap();
+        assert_eq!(h.counts().len(), 5);
+        assert_eq!(h.total(), 100);
+
+        let h = Histogram::new(&SAMPLE, Binning::Width(10.0)).unwrap();
+        assert_eq!(h.counts(), &[1, 2, 2, 0, 1, 1]);
+        let bins = h.bins();
+        assert_eq!((bins[0].lo, bins[0].hi), (40.0, 50.0));
+        assert_eq!(bins[5].hi, 100.0); // 98 は最後の [90, 100] に入る
+
+        let h = Histogram::new(&[0.0, 0.5, 1.0], Binning::Count(2)).unwrap();
+        assert_eq!(h.counts(), &[1, 2]); // 最後の区間は右端も含む
+        assert_eq!(h.index_of(1.5), None);
+    }
+
+    #[test]
+    fn degenerate_and_bad_input() {
+        let h = Histogram::new(&[5, 5, 5], Binning::Sturges).unwrap();
+        assert_eq!(h.counts(), &[3]);
+        let empty: [f64; 0] = [];
+        assert_eq!(
+            Histogram::new(&empty, Binning::Sturges),
+            Err(StatsError::Empty)
+        );
+        assert_eq!(
+            Histogram::new(&[f64::NAN, f64::INFINITY], Binning::Sturges),
+            Err(StatsError::Empty)
+        );
+        assert_eq!(
+            Histogram::new(&[1, 2], Binning::Width(0.0)),
+            Err(StatsError::BinWidth(0.0))
+        );
+        assert_eq!(
+            Histogram::new(&[0.0, 1e9], Binning::Width(1.0)),
+            Err(StatsError::BinWidth(1.0))
+        );
+        assert_eq!(
+            Histogram::new(&[1, 2], Binning::Count(0)),
+            Err(StatsError::BinCount(0))
+        );
+        // FD は IQR が 0 なら Sturges
+        let h = Histogram::new(&[1, 1, 1, 1, 9], Binning::FreedmanDiaconis).unwrap();
+        assert_eq!(h.counts().len(), 4);
+    }
+
+    #[test]
+    fn infinities_are_dropped() {
+        let xs = [f64::NEG_INFINITY, 1.0, 2.0, f64::NAN, 3.0, f64::INFINITY];
+        let h = Histogram::new(&xs, Binning::Count(3)).unwrap();
+        assert_eq!(h.counts(), &[1, 1, 1]);
+        assert_eq!(h.total(), 3);
+        assert_eq!(Marks::of(&xs).unwrap().mean, 2.0);
+        assert_eq!(sparkline(&xs), "▁▅█");
+        assert_eq!(
+            box_plot(&xs, 30).unwrap(),
+            box_plot(&[1.0, 2.0, 3.0], 30).unwrap()
+        );
+    }
+
+    #[test]
+    fn every_value_lands_in_its_bin() {
+        // 桁の違う値の組と、どの区間の決め方でも、全部の値がちょうど1回ずつ自分の [lo, hi) に入る
+        let sets: Vec<Vec<f64>> = vec![
+            vec![0.0011, 0.0017, -0.004, 0.0029, 0.0011],
+            SAMPLE.iter().map(|&x| x as f64).collect(),
+            (0..250)
+                .map(|i| (i * 37 % 101) as f64 * 3.75 - 120.0)
+                .collect(),
+            (0..90).map(|i| 1e6 * (i as f64).sqrt() - 5e7).collect(),
+            vec![-7.0],
+        ];
+        for xs in &sets {
+            let span = statistics::max(xs).unwrap() - statistics::min(xs).unwrap();
+            let hows = [
+                Binning::Sturges,
+                Binning::FreedmanDiaconis,
+                Binning::Count(1),
+                Binning::Count(7),
+                Binning::Count(39),
+                Binning::Width((span / 13.0).max(1e-3)),
+                Binning::Width((span / 3.0).max(1e-3)),
+            ];
+            for &how in &hows {
+                let h = Histogram::new(xs, how).unwrap();
+                assert_eq!(h.total(), xs.len(), "{:?}", how);
+                let bins = h.bins();
+                let last = bins.len() - 1;
+                for &x in xs {
+                    let i = h.index_of(x).unwrap();
+                    let b = bins[i];
+                    assert!(
+                        b.lo <= x && (x < b.hi || (i == last && x <= b.hi)),
+                        "{:?}: {} が {:?} に入った",
+                        how,
+                        x,
+                        b
+                    );
+                }
+                // 図はどれも区間の数だけ行(文字)がある
+                assert_eq!(h.bars(None, 10).lines().count(), bins.len());
+                assert_eq!(
+                    h.sparkline(None).trim_end_matches('\n').chars().count(),
+                    bins.len()
+                );
+            }
+            assert_eq!(box_plot(xs, 30).unwrap().lines().count(), 3);
+        }
+    }
+
+    #[test]
+    fn marks_of_sample() {
+        let m = Marks::of(&SAMPLE).unwrap();
+        assert_eq!(m.median, 63.0);
+        assert_eq!(m.modes, vec![57.0]);
+        assert_eq!(m.to_string(), "+ 平均 66.5714  ┃ 中央値 63  ^ 最頻値 57");
+        // どれも1回なら最頻値は無し
+        assert_eq!(Marks::of(&[1, 2, 3]).unwrap().modes, Vec::<f64>::new());
+        assert_eq!(Marks::of::<i32>(&[]), None);
+    }
+
+    #[test]
+    fn bar_chart() {
+        let h = Histogram::new(&SAMPLE, Binning::Width(20.0)).unwrap();
+        let m = Marks::of(&SAMPLE).unwrap();
+        assert_eq!(
+            h.bars(Some(&m), 8),
+            "[40, 60)   ████████  3  ← 最頻値\n\
+             [60, 80)   █████▍    2  ← 平均, 中央値\n\
+             [80, 100]  █████▍    2\n"
+        );
+        assert_eq!(
+            h.bars(None, 3),
+            "[40, 60)   ███  3\n[60, 80)   ██   2\n[80, 100]  ██   2\n"
+        );
+    }
+
+    #[test]
+    fn sparklines() {
+        assert_eq!(sparkline(&[1, 2, 3, 4, 5, 6, 7, 8]), "▁▂▃▄▅▆▇█");
+        assert_eq!(sparkline(&[0.0, 1.0, 0.5]), "▁█▅");
+        assert_eq!(sparkline(&[3, 3]), "▄▄");
+        assert_eq!(sparkline::<i32>(&[]), "");
+
+        let h = Histogram::new(&SAMPLE, Binning::Width(10.0)).unwrap();
+        assert_eq!(h.sparkline(None), "▅██ ▅▅\n");
+        let m = Marks::of(&SAMPLE).unwrap();
+        assert_eq!(
+            h.sparkline(Some(&m)),
+            "▅██ ▅▅\n ^*\n+ 平均 66.5714  ┃ 中央値 63  ^ 最頻値 57\n"
+        );
+    }
+
+    #[test]
+    fn box_plot_of_sample() {
+        let plot = box_plot(&SAMPLE, 28).unwrap();
+        let lines: Vec<&str> = plot.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], format!("43{}98", " ".repeat(24)));
+        assert_eq!(
+            lines[2],
+            "+ 平均 66.5714  ┃ 中央値 63  ^ 最頻値 57  ░ 57〜74"
+        );
+        // 外れ値は無いので左端と右端がひげの端
+        assert!(lines[0].starts_with('├'), "{}", lines[0]);
+        assert!(lines[0].ends_with('┤'), "{}", lines[0]);
+        for mark in &[MEAN_MARK, MEDIAN_MARK, MODE_MARK, '░'] {
+            assert!(lines[0].contains(*mark), "{} {}", mark, lines[0]);
+        }
+        assert_eq!(box_plot::<i32>(&[], 20), None);
+    }
+
+    #[test]
+    fn box_plot_outliers() {
+        let plot = box_plot(&[1, 10, 11, 12, 13, 14, 40], 40).unwrap();
+        let first = plot.lines().next().unwrap();
+        assert!(first.starts_with('·'), "{}", first);
+        assert!(first.ends_with('·'), "{}", first);
+        assert_eq!(first.matches('·').count(), 2);
+    }
+}
@@ -0,0 +1,633 @@
+// 色
+// ./others/structure.rs の タプル構造体 RGB(f64, f64, f64) を育てたもの
+// RGB, HSL, HSV, 16進表記(#rrggbb) の相互変換, CSS の色名の読み込み,
+// リニア空間での混色, WCAG のコントラスト比, 端末用の ANSI エスケープ(トゥルーカラー/256色) を扱う
+// 板書の出力と webdev のフロントで同じ色を使えるように Palette も置いておく
+
+use std::fmt;
+
+/// RGB の色。各成分は 0.0 ~ 1.0 の sRGB 値(RGB(1.0, 0.5, 0.3) と同じ)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb(pub f64, pub f64, pub f64);
+
+/// HSL。色相は度(0 ~ 360)、彩度と輝度は 0.0 ~ 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl(pub f64, pub f64, pub f64);
+
+/// HSV。色相は度(0 ~ 360)、彩度と明度は 0.0 ~ 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv(pub f64, pub f64, pub f64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColourError {
+    InvalidHex(String),
+    UnknownName(String),
+}
+
+impl fmt::Display for ColourError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColourError::InvalidHex(s) => write!(f, "`{}` は #rrggbb 形式ではありません", s),
+            ColourError::UnknownName(s) => write!(f, "`{}` という色名は知りません", s),
+        }
+    }
+}
+
+impl std::error::Error for ColourError {}
+
+impl Rgb {
+    /// 0 ~ 255 の8bit値から作る
+    pub fn from_u8(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb(r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0)
+    }
+
+    /// 8bit値にする(範囲外は 0 ~ 255 に収める)
+    pub fn to_u8(self) -> (u8, u8, u8) {
+        let c = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        (c(self.0), c(self.1), c(self.2))
+    }
+
+    /// `#rrggbb` か `#rgb`(# は省略可)を読む
+    pub fn from_hex(s: &str) -> Result<Rgb, ColourError> {
+        let err = || ColourError::InvalidHex(String::from(s));
+        let hex = s.trim().strip_prefix('#').unwrap_or(s.trim());
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(err());
+        }
+        let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).map_err(|_| err());
+        match hex.len() {
+            3 => Ok(Rgb::from_u8(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+            6 => {
+                let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err());
+                Ok(Rgb::from_u8(byte(0)?, byte(2)?, byte(4)?))
+            }
+            _ => Err(err()),
+        }
+    }
+
+    /// `#rrggbb`(小文字)にする
+    pub fn to_hex(self) -> String {
+        let (r, g, b) = self.to_u8();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
+    /// 16進表記か CSS の色名(大文字小文字は区別しない)を読む
+    pub fn parse(s: &str) -> Result<Rgb, ColourError> {
+        let s = s.trim();
+        if s.starts_with('#') {
+            return Rgb::from_hex(s);
+        }
+        named(s).ok_or_else(|| ColourError::UnknownName(String::from(s)))
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        let Rgb(r, g, b) = self.clamped();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+        let d = max - min;
+        if d == 0.0 {
+            return Hsl(0.0, 0.0, l);
+        }
+        let s = d / (1.0 - (2.0 * l - 1.0).abs());
+        Hsl(hue(r, g, b, max, d), s, l)
+    }
+
+    pub fn to_hsv(self) -> Hsv {
+        let Rgb(r, g, b) = self.clamped();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let d = max - min;
+        if d == 0.0 {
+            return Hsv(0.0, 0.0, max);
+        }
+        Hsv(hue(r, g, b, max, d), d / max, max)
+    }
+
+    /// sRGB の値をリニア(光の強さに比例する値)にする
+    pub fn to_linear(self) -> (f64, f64, f64) {
+        let Rgb(r, g, b) = self.clamped();
+        (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b))
+    }
+
+    pub fn from_linear(r: f64, g: f64, b: f64) -> Rgb {
+        Rgb(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+    }
+
+    /// other と t(0.0 で self, 1.0 で other)の割合で混ぜる
+    /// sRGB の値のまま混ぜると中間色が暗くなるので、リニア空間で混ぜてから戻す
+    pub fn blend(self, other: Rgb, t: f64) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let (r1, g1, b1) = self.to_linear();
+        let (r2, g2, b2) = other.to_linear();
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+        Rgb::from_linear(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+    }
+
+    /// WCAG 2 の相対輝度(0.0 = 黒, 1.0 = 白)
+    pub fn relative_luminance(self) -> f64 {
+        let (r, g, b) = self.to_linear();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// WCAG 2 のコントラスト比(1.0 ~ 21.0)
+    pub fn contrast_ratio(self, other: Rgb) -> f64 {
+        let a = self.relative_luminance();
+        let b = other.relative_luminance();
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// 通常の文字で WCAG AA(4.5:1 以上)を満たすか
+    pub fn is_readable_on(self, background: Rgb) -> bool {
+        self.contrast_ratio(background) >= 4.5
+    }
+
+    /// 前景色の ANSI エスケープ
+    pub fn ansi_fg(self, mode: AnsiMode) -> String {
+        self.ansi(38, mode)
+    }
+
+    /// 背景色の ANSI エスケープ
+    pub fn ansi_bg(self, mode: AnsiMode) -> String {
+        self.ansi(48, mode)
+    }
+
+    /// text をこの色で塗って最後に色を戻す
+    pub fn paint(self, text: &str, mode: AnsiMode) -> String {
+        format!("{}{}{}", self.ansi_fg(mode), text, ANSI_RESET)
+    }
+
+    /// xterm の256色で一番近い色の番号(16 ~ 255 の色立方体と灰色の帯から選ぶ)
+    pub fn to_ansi256(self) -> u8 {
+        let (r, g, b) = self.to_u8();
+        // 6x6x6 の色立方体の各段の値
+        const STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        let nearest_step = |v: u8| {
+            (0..6)
+                .min_by_key(|&i| (STEPS[i] as i32 - v as i32).abs())
+                .unwrap_or(0)
+        };
+        let (ri, gi, bi) = (nearest_step(r), nearest_step(g), nearest_step(b));
+        let cube = (STEPS[ri], STEPS[gi], STEPS[bi]);
+        let cube_index = 16 + 36 * ri + 6 * gi + bi;
+        // 灰色の帯は 8, 18, ..., 238 の24段
+        let avg = (r as u32 + g as u32 + b as u32) / 3;
+        let gray_i = ((avg as i32 - 8 + 5) / 10).clamp(0, 23) as u8;
+        let gray = 8 + 10 * gray_i;
+        let dist = |(x, y, z): (u8, u8, u8)| {
+            let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+            d(x, r) + d(y, g) + d(z, b)
+        };
+        if dist((gray, gray, gray)) < dist(cube) {
+            232 + gray_i
+        } else {
+            cube_index as u8
+        }
+    }
+
+    fn ansi(self, layer: u8, mode: AnsiMode) -> String {
+        match mode {
+            AnsiMode::TrueColor => {
+                let (r, g, b) = self.to_u8();
+                format!("\x1b[{};2;{};{};{}m", layer, r, g, b)
+            }
+            AnsiMode::Ansi256 => format!("\x1b[{};5;{}m", layer, self.to_ansi256()),
+        }
+    }
+
+    fn clamped(self) -> Rgb {
+        Rgb(
+            self.0.clamp(0.0, 1.0),
+            self.1.clamp(0.0, 1.0),
+            self.2.clamp(0.0, 1.0),
+        )
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Hsl {
+    pub fn to_rgb(self) -> Rgb {
+        let Hsl(h, s, l) = self;
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        from_chroma(h, c, l - c / 2.0)
+    }
+}
+
+impl Hsv {
+    pub fn to_rgb(self) -> Rgb {
+        let Hsv(h, s, v) = self;
+        let c = v * s;
+        from_chroma(h, c, v - c)
+    }
+}
+
+impl From<Hsl> for Rgb {
+    fn from(hsl: Hsl) -> Rgb {
+        hsl.to_rgb()
+    }
+}
+
+impl From<Hsv> for Rgb {
+    fn from(hsv: Hsv) -> Rgb {
+        hsv.to_rgb()
+    }
+}
+
+// 色相(度)を求める。max と d は r, g, b の最大値と (最大値 - 最小値)
+fn hue(r: f64, g: f64, b: f64, max: f64, d: f64) -> f64 {
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    h * 60.0
+}
+
+// 色相, 彩度の幅 c, 底上げ m から RGB に戻す(HSL と HSV で共通の部分)
+fn from_chroma(h: f64, c: f64, m: f64) -> Rgb {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    Rgb(r + m, g + m, b + m)
+}
+
+fn srgb_to_linear(v: f64) -> f64 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f64) -> f64 {
+    let v = v.clamp(0.0, 1.0);
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// 端末の色の出し方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiMode {
+    TrueColor, // 24bit(\x1b[38;2;r;g;bm)
+    Ansi256,   // xterm の256色(\x1b[38;5;nm)
+}
+
+impl AnsiMode {
+    /// 環境変数 COLORTERM が truecolor か 24bit ならトゥルーカラー、そうでなければ256色
+    pub fn detect() -> AnsiMode {
+        match std::env::var("COLORTERM") {
+            Ok(v) if v == "truecolor" || v == "24bit" => AnsiMode::TrueColor,
+            _ => AnsiMode::Ansi256,
+        }
+    }
+}
+
+/// 色を元に戻すエスケープ
+pub const ANSI_RESET: &str = "\x1b[0m";
+
+/// CSS の色名から色を引く(大文字小文字は区別しない)
+pub fn named(name: &str) -> Option<Rgb> {
+    let name = name.to_ascii_lowercase();
+    CSS_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, hex)| Rgb::from_u8((hex >> 16) as u8, (hex >> 8) as u8, hex as u8))
+}
+
+/// 名前付きの色の組
+/// 板書の出力(端末)と webdev のフロント(CSS)で同じ色を使うためのもの
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Palette {
+    entries: Vec<(String, Rgb)>,
+}
+
+impl Palette {
+    /// 空の色の組
+    pub fn new() -> Palette {
+        Palette::default()
+    }
+
+    /// この板書で使う色
+    pub fn etude() -> Palette {
+        let mut p = Palette::new();
+        p.set("accent", Rgb::from_u8(0xde, 0xa5, 0x84)); // Rust のロゴっぽい色
+        p.set("text", Rgb::from_u8(0x1e, 0x1e, 0x1e));
+        p.set("background", Rgb::from_u8(0xfa, 0xfa, 0xf7));
+        p.set("ok", Rgb::from_u8(0x2e, 0x7d, 0x32));
+        p.set("warn", Rgb::from_u8(0xb2, 0x6a, 0x00));
+        p.set("error", Rgb::from_u8(0xc6, 0x28, 0x28));
+        p.set("muted", Rgb::from_u8(0x75, 0x75, 0x75));
+        p
+    }
+
+    /// 色を登録する(同じ名前なら上書き)
+    pub fn set(&mut self, name: &str, colour: Rgb) -> &mut Palette {
+        match self.entries.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = colour,
+            None => self.entries.push((String::from(name), colour)),
+        }
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rgb> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|&(_, c)| c)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Rgb)> {
+        self.entries.iter().map(|(n, c)| (n.as_str(), *c))
+    }
+
+    /// CSS のカスタムプロパティとして書き出す(:root { --accent: #dea584; ... })
+    pub fn to_css(&self) -> String {
+        let mut out = String::from(":root {\n");
+        for (name, colour) in self.iter() {
+            out.push_str(&format!("  --{}: {};\n", name, colour.to_hex()));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// text を name の色で塗る。知らない名前ならそのまま返す
+    pub fn paint(&self, name: &str, text: &str, mode: AnsiMode) -> String {
+        match self.get(name) {
+            Some(c) => c.paint(text, mode),
+            None => String::from(text),
+        }
+    }
+}
+
+// CSS Color Module Level 4 の色名
+const CSS_NAMES: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        assert_eq!(Rgb::from_hex("#ff8000").unwrap().to_u8(), (255, 128, 0));
+        assert_eq!(Rgb::from_hex("0af").unwrap().to_hex(), "#00aaff");
+        assert_eq!(Rgb(1.0, 0.5, 0.3).to_hex(), "#ff804d");
+        assert!(Rgb::from_hex("#12345").is_err());
+        assert!(Rgb::from_hex("#gg0000").is_err());
+    }
+
+    #[test]
+    fn css_names() {
+        assert_eq!(Rgb::parse("RebeccaPurple").unwrap().to_hex(), "#663399");
+        assert_eq!(Rgb::parse("#fff").unwrap(), Rgb::parse("white").unwrap());
+        assert_eq!(
+            Rgb::parse("notacolour"),
+            Err(ColourError::UnknownName(String::from("notacolour")))
+        );
+    }
+
+    #[test]
+    fn hsl_and_hsv() {
+        let orange = Rgb::from_hex("#ff8000").unwrap();
+        let Hsl(h, s, l) = orange.to_hsl();
+        assert!((h - 30.1).abs() < 0.1 && close(s, 1.0) && (l - 0.5).abs() < 0.01);
+        let Hsv(h, s, v) = Rgb::from_hex("#336699").unwrap().to_hsv();
+        assert!(close(h, 210.0) && (s - 0.667).abs() < 0.001 && close(v, 0.6));
+        assert_eq!(Hsl(120.0, 1.0, 0.25).to_rgb().to_hex(), "#008000");
+        assert_eq!(Hsv(0.0, 0.0, 0.5).to_rgb().to_hex(), "#808080");
+        assert_eq!(Rgb::from(Hsl(-120.0, 1.0, 0.5)).to_hex(), "#0000ff");
+    }
+
+    #[test]
+    fn conversions_round_trip_for_every_css_colour() {
+        for &(name, _) in CSS_NAMES.iter() {
+            let c = named(name).unwrap();
+            assert_eq!(c.to_hsl().to_rgb().to_hex(), c.to_hex(), "{} (HSL)", name);
+            assert_eq!(c.to_hsv().to_rgb().to_hex(), c.to_hex(), "{} (HSV)", name);
+        }
+    }
+
+    #[test]
+    fn linear_blend_is_brighter_than_naive() {
+        let black = Rgb(0.0, 0.0, 0.0);
+        let white = Rgb(1.0, 1.0, 1.0);
+        let mid = black.blend(white, 0.5);
+        // リニア空間での半分は sRGB で 0.735 くらい(#bcbcbc)
+        assert_eq!(mid.to_hex(), "#bcbcbc");
+        assert_eq!(black.blend(white, 0.0), black);
+        assert_eq!(black.blend(white, 1.0).to_hex(), "#ffffff");
+    }
+
+    #[test]
+    fn wcag_contrast() {
+        let black = Rgb(0.0, 0.0, 0.0);
+        let white = Rgb(1.0, 1.0, 1.0);
+        assert!(close(black.contrast_ratio(white), 21.0));
+        assert!(close(white.contrast_ratio(white), 1.0));
+        let grey = Rgb::from_hex("#767676").unwrap();
+        assert!(grey.is_readable_on(white)); // 4.54:1
+        assert!(!Rgb::from_hex("#777777").unwrap().is_readable_on(white)); // 4.48:1
+    }
+
+    #[test]
+    fn ansi_escapes() {
+        let c = Rgb::from_hex("#ff8000").unwrap();
+        assert_eq!(c.ansi_fg(AnsiMode::TrueColor), "\x1b[38;2;255;128;0m");
+        assert_eq!(c.ansi_bg(AnsiMode::TrueColor), "\x1b[48;2;255;128;0m");
+        assert_eq!(Rgb::parse("red").unwrap().to_ansi256(), 196);
+        assert_eq!(Rgb::parse("white").unwrap().to_ansi256(), 231);
+        assert_eq!(Rgb::parse("black").unwrap().to_ansi256(), 16);
+        assert_eq!(Rgb::from_hex("#808080").unwrap().to_ansi256(), 244);
+        assert_eq!(
+            c.paint("hi", AnsiMode::Ansi256),
+            format!("\x1b[38;5;{}mhi\x1b[0m", c.to_ansi256())
+        );
+    }
+
+    #[test]
+    fn palette_is_shared_between_css_and_terminal() {
+        let p = Palette::etude();
+        let css = p.to_css();
+        assert!(css.starts_with(":root {\n  --accent: #dea584;\n"));
+        assert!(p
+            .paint("accent", "x", AnsiMode::TrueColor)
+            .contains("38;2;222;165;132"));
+        assert_eq!(p.paint("nope", "x", AnsiMode::TrueColor), "x");
+        assert_eq!(Palette::default(), Palette::new());
+        assert_eq!(Palette::new().iter().count(), 0);
+        // 板書の文字色は背景の上で読める
+        assert!(p
+            .get("text")
+            .unwrap()
+            .is_readable_on(p.get("background").unwrap()));
+    }
+}
@@ -0,0 +1,476 @@
+// CSV の読み書き(RFC 4180)
+// ./others/collections.rs の row のような Vec<SpreadsheetCell> を1行として、CSV の文字列やファイルと行き来する
+//
+//     1,blue,10.12          → [Int(1), Text("blue"), Float(10.12)]
+//     "a,b","say ""hi"""    → [Text("a,b"), Text("say \"hi\"")]
+//
+// " で囲んだ欄には区切り文字や " (2つ重ねて書く)や改行をそのまま入れられる
+// 囲んでいない欄だけ型を推測する(" で囲んだ "007" は Text のまま)
+// 行の終わりは CRLF でも LF でも読めて、書くときは RFC のとおり CRLF にする
+// 大きなファイルは Reader で1行(1レコード)ずつ読めば全体をメモリに載せなくてよい
+
+use crate::sheet::SpreadsheetCell;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// 1行分
+pub type Row = Vec<SpreadsheetCell>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvError {
+    UnclosedQuote { line: usize, column: usize }, // 開いた " の位置
+    StrayQuote { line: usize, column: usize },    // 囲んでいない欄の途中の "
+    AfterQuote { line: usize, column: usize },    // 閉じた " の後に区切りでも改行でもない文字
+    InvalidUtf8 { line: usize },
+    Io(String),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsvError::UnclosedQuote { line, column } => {
+                write!(
+                    f,
+                    "{}行{}列: \" が閉じられないままファイルが終わりました",
+                    line, column
+                )
+            }
+            CsvError::StrayQuote { line, column } => write!(
+                f,
+                "{}行{}列: \" で囲んでいない欄の途中に \" があります",
+                line, column
+            ),
+            CsvError::AfterQuote { line, column } => write!(
+                f,
+                "{}行{}列: 閉じた \" の後に区切り文字か改行がありません",
+                line, column
+            ),
+            CsvError::InvalidUtf8 { line } => write!(f, "{}行: UTF-8 として読めません", line),
+            CsvError::Io(e) => write!(f, "読み込みに失敗しました: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// 書き方の違い(区切り文字と型の推測)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    delimiter: char,
+    infer_types: bool,
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect {
+            delimiter: ',',
+            infer_types: true,
+        }
+    }
+}
+
+impl Dialect {
+    /// , 区切りで型を推測する
+    pub fn new() -> Dialect {
+        Dialect::default()
+    }
+
+    /// 区切り文字を変える(タブ区切りなら '\t', ; 区切りなら ';')
+    /// " と改行は区切りにできない
+    pub fn delimiter(self, delimiter: char) -> Dialect {
+        assert!(
+            !matches!(delimiter, '"' | '\r' | '\n'),
+            "{:?} は区切り文字にできません",
+            delimiter
+        );
+        Dialect { delimiter, ..self }
+    }
+
+    /// false にすると欄は全部 Text になる
+    pub fn infer_types(self, infer_types: bool) -> Dialect {
+        Dialect {
+            infer_types,
+            ..self
+        }
+    }
+
+    /// 少しずつ読む
+    pub fn reader<R: BufRead>(self, input: R) -> Reader<R> {
+        Reader {
+            input,
+            dialect: self,
+            line: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    /// 文字列を全部読む(最初のエラーで止まる)
+    pub fn parse(self, s: &str) -> Result<Vec<Row>, CsvError> {
+        self.reader(s.as_bytes()).collect()
+    }
+
+    /// 1行書く(最後に CRLF も書く)
+    pub fn write_row<W: Write>(self, out: &mut W, row: &[SpreadsheetCell]) -> io::Result<()> {
+        let mut line = String::new();
+        self.push_row(&mut line, row);
+        out.write_all(line.as_bytes())
+    }
+
+    /// 全部の行を CSV の文字列にする
+    pub fn format(self, rows: &[Row]) -> String {
+        let mut out = String::new();
+        for row in rows {
+            self.push_row(&mut out, row);
+        }
+        out
+    }
+
+    fn push_row(self, out: &mut String, row: &[SpreadsheetCell]) {
+        for (i, cell) in row.iter().enumerate() {
+            if i > 0 {
+                out.push(self.delimiter);
+            }
+            match cell {
+                SpreadsheetCell::Int(n) => out.push_str(&n.to_string()),
+                // {:?} なら 3.0 の .0 が残るので読み直しても Float になる(NaN や inf は Text として読まれる)
+                SpreadsheetCell::Float(x) => out.push_str(&format!("{:?}", x)),
+                SpreadsheetCell::Text(s) => {
+                    // 1欄だけの空の行は、囲まないと空行(欄が0個)と区別できない
+                    let must_quote = s
+                        .contains(|c: char| c == self.delimiter || "\"\r\n".contains(c))
+                        || (self.infer_types
+                            && !matches!(SpreadsheetCell::infer(s), SpreadsheetCell::Text(_)))
+                        || (s.is_empty() && row.len() == 1);
+                    if must_quote {
+                        out.push('"');
+                        out.push_str(&s.replace('"', "\"\""));
+                        out.push('"');
+                    } else {
+                        out.push_str(s);
+                    }
+                }
+            }
+        }
+        out.push_str("\r\n");
+    }
+
+    fn cell(self, field: String, quoted: bool) -> SpreadsheetCell {
+        if quoted || !self.infer_types {
+            SpreadsheetCell::Text(field)
+        } else {
+            SpreadsheetCell::infer(&field)
+        }
+    }
+}
+
+/// 区切り , で型を推測して全部読む
+pub fn parse(s: &str) -> Result<Vec<Row>, CsvError> {
+    Dialect::new().parse(s)
+}
+
+/// 区切り , で全部書く
+pub fn format(rows: &[Row]) -> String {
+    Dialect::new().format(rows)
+}
+
+/// 1行(1レコード)ずつ読む Iterator
+/// " の中の改行をまたぐレコードは何行分でも読み進める
+/// エラーになったレコードはその物理行の終わりまで捨てて、次の行から読み続ける
+pub struct Reader<R> {
+    input: R,
+    dialect: Dialect,
+    line: usize, // 最後に読んだ物理行(1 始まり)
+    buf: Vec<u8>,
+}
+
+// 欄のどこにいるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,  // 欄の始め
+    Plain,  // 囲んでいない欄の途中
+    Quoted, // " の中
+    Closed, // 閉じた " の後
+}
+
+impl<R: BufRead> Reader<R> {
+    /// 最後に読んだ物理行の番号
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    // 改行も含めて1行読む
+    fn read_line(&mut self) -> Result<Option<Vec<char>>, CsvError> {
+        self.buf.clear();
+        let n = self
+            .input
+            .read_until(b'\n', &mut self.buf)
+            .map_err(|e| CsvError::Io(e.to_string()))?;
+        if n == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        match std::str::from_utf8(&self.buf) {
+            Ok(s) => Ok(Some(s.chars().collect())),
+            Err(_) => Err(CsvError::InvalidUtf8 { line: self.line }),
+        }
+    }
+
+    fn record(&mut self) -> Option<Result<Row, CsvError>> {
+        let mut chars = match self.read_line() {
+            Ok(Some(chars)) => chars,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        // 空行は欄が0個の行
+        if chars == ['\n'] || chars == ['\r', '\n'] {
+            return Some(Ok(Vec::new()));
+        }
+        let delimiter = self.dialect.delimiter;
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut state = State::Start;
+        let mut open = (0, 0);
+        let mut i = 0;
+        loop {
+            if i == chars.len() {
+                if state != State::Quoted {
+                    // 改行の無い最後の行
+                    row.push(self.dialect.cell(field, state == State::Closed));
+                    return Some(Ok(row));
+                }
+                // " の中の改行なので次の行へ続く
+                match self.read_line() {
+                    Ok(Some(next)) => {
+                        chars = next;
+                        i = 0;
+                        continue;
+                    }
+                    Ok(None) => {
+                        let (line, column) = open;
+                        return Some(Err(CsvError::UnclosedQuote { line, column }));
+                    }
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            let c = chars[i];
+            let column = i + 1;
+            i += 1;
+            let line_end = c == '\n' || (c == '\r' && chars.get(i) == Some(&'\n'));
+            match state {
+                State::Quoted => {
+                    if c != '"' {
+                        field.push(c);
+                    } else if chars.get(i) == Some(&'"') {
+                        field.push('"');
+                        i += 1;
+                    } else {
+                        state = State::Closed;
+                    }
+                }
+                _ if line_end || c == delimiter => {
+                    let quoted = state == State::Closed;
+                    row.push(self.dialect.cell(std::mem::take(&mut field), quoted));
+                    if line_end {
+                        return Some(Ok(row));
+                    }
+                    state = State::Start;
+                }
+                State::Start if c == '"' => {
+                    state = State::Quoted;
+                    open = (self.line, column);
+                }
+                State::Plain if c == '"' => {
+                    let line = self.line;
+                    return Some(Err(CsvError::StrayQuote { line, column }));
+                }
+                State::Start | State::Plain => {
+                    field.push(c);
+                    state = State::Plain;
+                }
+                State::Closed => {
+                    let line = self.line;
+                    return Some(Err(CsvError::AfterQuote { line, column }));
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Row, CsvError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.record()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sheet::SpreadsheetCell::{Float, Int, Text};
+
+    fn text(s: &str) -> SpreadsheetCell {
+        Text(s.to_string())
+    }
+
+    #[test]
+    fn infers_types() {
+        // collections.rs の row と同じもの
+        assert_eq!(
+            parse("3,blue,10.12\n").unwrap(),
+            vec![vec![Int(3), text("blue"), Float(10.12)]]
+        );
+        assert_eq!(
+            parse("-7,+2,1e3,007,\"12\", 5,inf\r\n").unwrap(),
+            vec![vec![
+                Int(-7),
+                Int(2),
+                Float(1000.0),
+                Int(7),
+                text("12"),
+                text(" 5"),
+                text("inf"),
+            ]]
+        );
+        assert_eq!(
+            Dialect::new().infer_types(false).parse("1,2.5").unwrap(),
+            vec![vec![text("1"), text("2.5")]]
+        );
+    }
+
+    #[test]
+    fn quoted_fields() {
+        let rows =
+            parse("\"a,b\",\"say \"\"hi\"\"\",\"two\r\nlines\",\"\"\r\nx,,\r\n\n\"\"\r\n").unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    text("a,b"),
+                    text("say \"hi\""),
+                    text("two\r\nlines"),
+                    text("")
+                ],
+                vec![text("x"), text(""), text("")],
+                vec![],
+                vec![text("")],
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_delimiters() {
+        let tsv = Dialect::new().delimiter('\t');
+        assert_eq!(
+            tsv.parse("a,b\t1\n").unwrap(),
+            vec![vec![text("a,b"), Int(1)]]
+        );
+        let semi = Dialect::new().delimiter(';');
+        let rows = vec![vec![text("1;2"), Float(0.5), text("x,y")]];
+        assert_eq!(semi.format(&rows), "\"1;2\";0.5;x,y\r\n");
+        assert_eq!(semi.parse(&semi.format(&rows)).unwrap(), rows);
+    }
+
+    #[test]
+    fn writes_rfc4180() {
+        let rows = vec![
+            vec![Int(3), text("blue"), Float(10.0)],
+            vec![text("12"), text("a\"b"), text("line\nbreak"), text("")],
+            vec![],
+            vec![text("")],
+        ];
+        let s = format(&rows);
+        assert_eq!(
+            s,
+            "3,blue,10.0\r\n\"12\",\"a\"\"b\",\"line\nbreak\",\r\n\r\n\"\"\r\n"
+        );
+        assert_eq!(parse(&s).unwrap(), rows);
+        let mut out = Vec::new();
+        Dialect::new().write_row(&mut out, &rows[0]).unwrap();
+        assert_eq!(out, b"3,blue,10.0\r\n");
+    }
+
+    #[test]
+    fn errors_report_line_and_column() {
+        assert_eq!(
+            parse("a,b\nc,d\"e\n"),
+            Err(CsvError::StrayQuote { line: 2, column: 4 })
+        );
+        assert_eq!(
+            parse("a\n\"ok\"x\n"),
+            Err(CsvError::AfterQuote { line: 2, column: 5 })
+        );
+        assert_eq!(
+            parse("a\nb,\"open\nstill\nopen"),
+            Err(CsvError::UnclosedQuote { line: 2, column: 3 })
+        );
+        assert_eq!(
+            Dialect::new().reader(&b"ok\n\xff\n"[..]).nth(1),
+            Some(Err(CsvError::InvalidUtf8 { line: 2 }))
+        );
+        assert_eq!(
+            CsvError::StrayQuote { line: 2, column: 4 }.to_string(),
+            "2行4列: \" で囲んでいない欄の途中に \" があります"
+        );
+    }
+
+    #[test]
+    fn reader_skips_bad_records() {
+        let mut reader = Dialect::new().reader("1,2\nx\"y\n\"multi\nline\",3\n4\n".as_bytes());
+        assert_eq!(reader.next(), Some(Ok(vec![Int(1), Int(2)])));
+        assert!(reader.next().unwrap().is_err());
+        assert_eq!(reader.next(), Some(Ok(vec![text("multi\nline"), Int(3)])));
+        assert_eq!(reader.line(), 4);
+        assert_eq!(reader.next(), Some(Ok(vec![Int(4)])));
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn errors_point_past_multiline_fields() {
+        // 複数行の欄の後でも、行番号は物理行で数える
+        let s = "\"one\ntwo\nthree\",1\nok,\"bad\"x\n";
+        let results: Vec<_> = Dialect::new().reader(s.as_bytes()).collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1], Err(CsvError::AfterQuote { line: 4, column: 9 }));
+    }
+
+    #[test]
+    fn written_rows_read_back_through_small_buffers() {
+        // 区切り文字, ", 改行, 数に見える Text の入った行を書いて読み直す
+        let rows = vec![
+            vec![Int(-1_000_000), Float(-0.125), text(""), text("a,b;c\td|e")],
+            vec![],
+            vec![
+                text("say \"hi\""),
+                text("two\r\nlines\n"),
+                text("12"),
+                text("1.5"),
+            ],
+            vec![text(" "), text("-"), text("e"), text("日本"), Int(0)],
+            vec![text("")],
+        ];
+        for &delimiter in &[',', ';', '\t', '|'] {
+            let dialect = Dialect::new().delimiter(delimiter);
+            let s = dialect.format(&rows);
+            // 区切りの位置に関係なくバッファが切れるように
+            for capacity in 1..8 {
+                let input = io::BufReader::with_capacity(capacity, s.as_bytes());
+                let back: Result<Vec<Row>, CsvError> = dialect.reader(input).collect();
+                assert_eq!(back.as_ref(), Ok(&rows), "{:?} {}", s, capacity);
+            }
+        }
+        // 欄の中に改行が無ければ、行の終わりを LF にしても同じ
+        let rows: Vec<Row> = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|c| match c {
+                        Text(s) => Text(s.replace(['\r', '\n'], "")),
+                        c => c,
+                    })
+                    .collect()
+            })
+            .collect();
+        let lf = Dialect::new().format(&rows).replace("\r\n", "\n");
+        assert_eq!(Dialect::new().parse(&lf), Ok(rows), "{:?}", lf);
+    }
+}
@@ -0,0 +1,362 @@
+// 長方形の絵
+// RectDebug は {:#?} で数字を出すだけなので、長方形を罫線の文字で端末に描いたり SVG にしたりする
+// can_hold のテストが落ちたときに2つの形を絵で見られるよう assert_can_hold! も置く
+
+use crate::colour;
+use crate::geometry::{Coord, Rect};
+use crate::text::char_width;
+
+// 罫線の向き(その升目から線が出ている方向)
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const UP: u8 = 4;
+const DOWN: u8 = 8;
+
+// 升目から出ている線の向きの組み合わせを罫線の文字にする
+fn box_char(mask: u8) -> char {
+    match mask {
+        0 => ' ',
+        m if m == RIGHT | DOWN => '┌',
+        m if m == LEFT | DOWN => '┐',
+        m if m == RIGHT | UP => '└',
+        m if m == LEFT | UP => '┘',
+        m if m == LEFT | RIGHT | DOWN => '┬',
+        m if m == LEFT | RIGHT | UP => '┴',
+        m if m == UP | DOWN | RIGHT => '├',
+        m if m == UP | DOWN | LEFT => '┤',
+        m if m == LEFT | RIGHT | UP | DOWN => '┼',
+        m if m & (UP | DOWN) == 0 => '─',
+        _ => '│',
+    }
+}
+
+// SVG の塗りに順番に使う色(CSS の色名)
+const SVG_COLOURS: [&str; 6] = [
+    "steelblue",
+    "tomato",
+    "seagreen",
+    "goldenrod",
+    "orchid",
+    "slategray",
+];
+
+/// 名前付きの長方形を集めた絵
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drawing<T> {
+    shapes: Vec<(Rect<T>, String)>,
+}
+
+impl<T: Coord> Drawing<T> {
+    pub fn new() -> Drawing<T> {
+        Drawing { shapes: Vec::new() }
+    }
+
+    /// 長方形を足す。label は空でもよい
+    pub fn add(&mut self, rect: Rect<T>, label: &str) -> &mut Drawing<T> {
+        self.shapes.push((rect, String::from(label)));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// 全部を囲む範囲 (x, y, 幅, 高さ)
+    fn extent(&self) -> Option<(f64, f64, f64, f64)> {
+        let rects: Vec<Rect<T>> = self.shapes.iter().map(|(r, _)| *r).collect();
+        Rect::bounding_box(&rects).map(|b| {
+            (
+                b.x.to_f64(),
+                b.y.to_f64(),
+                b.width.to_f64(),
+                b.height.to_f64(),
+            )
+        })
+    }
+
+    /// 罫線の文字で描く。幅は max_columns 桁に収まるように縮める
+    /// 端末の文字は縦長なので、1単位を横2桁, 縦1行にするのが基本(それより大きくはしない)
+    /// 升目に丸めるので、小さすぎる長方形は線1本や点になる
+    pub fn to_ascii(&self, max_columns: usize) -> String {
+        let (x0, y0, w, h) = match self.extent() {
+            Some(e) => e,
+            None => return String::new(),
+        };
+        let sx = if w > 0.0 {
+            (max_columns.saturating_sub(1) as f64 / w).min(2.0)
+        } else {
+            2.0
+        };
+        let sy = sx / 2.0;
+        let cols = (w * sx).round() as usize + 1;
+        let rows = (h * sy).round() as usize + 1;
+        let mut lines = vec![0u8; cols * rows];
+        let mut text: Vec<Option<char>> = vec![None; cols * rows];
+        let cell = |c: usize, r: usize| r * cols + c;
+
+        let corners = |rect: &Rect<T>| {
+            let c0 = ((rect.x.to_f64() - x0) * sx).round() as usize;
+            let r0 = ((rect.y.to_f64() - y0) * sy).round() as usize;
+            let c1 = ((rect.right().to_f64() - x0) * sx).round() as usize;
+            let r1 = ((rect.bottom().to_f64() - y0) * sy).round() as usize;
+            (c0, r0, c1, r1)
+        };
+        for (rect, _) in &self.shapes {
+            let (c0, r0, c1, r1) = corners(rect);
+            for &r in &[r0, r1] {
+                for c in c0..c1 {
+                    lines[cell(c, r)] |= RIGHT;
+                    lines[cell(c + 1, r)] |= LEFT;
+                }
+            }
+            for &c in &[c0, c1] {
+                for r in r0..r1 {
+                    lines[cell(c, r)] |= DOWN;
+                    lines[cell(c, r + 1)] |= UP;
+                }
+            }
+            if c0 == c1 && r0 == r1 {
+                text[cell(c0, r0)] = Some('·');
+            }
+        }
+        // 名前は中の左上に書く。他の線や名前とぶつかるなら下の行へずらし、中に空きがなければ上の辺に重ねる
+        for (rect, label) in &self.shapes {
+            let (c0, r0, c1, r1) = corners(rect);
+            let room = c1.saturating_sub(c0 + 1);
+            let mut chars = Vec::new();
+            let mut used = 0;
+            for ch in label.chars() {
+                let wide = char_width(ch);
+                if used + wide > room.max(1) {
+                    break;
+                }
+                chars.push((ch, wide));
+                used += wide;
+            }
+            let free = |r: usize| {
+                (c0 + 1..c0 + 1 + used)
+                    .all(|c| c < cols && lines[cell(c, r)] == 0 && text[cell(c, r)].is_none())
+            };
+            let row = (r0 + 1..r1).find(|&r| free(r)).unwrap_or(r0);
+            let mut c = c0 + 1;
+            for (ch, wide) in chars {
+                if c + wide > cols {
+                    break;
+                }
+                text[cell(c, row)] = Some(ch);
+                if wide == 2 {
+                    // 全角の文字が2桁ぶん使うので次の升目は出力しない
+                    text[cell(c + 1, row)] = Some('\0');
+                }
+                c += wide;
+            }
+        }
+
+        let mut out = String::new();
+        for r in 0..rows {
+            let mut line = String::new();
+            for c in 0..cols {
+                match text[cell(c, r)] {
+                    Some('\0') => {}
+                    Some(ch) => line.push(ch),
+                    None => line.push(box_char(lines[cell(c, r)])),
+                }
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// SVG の文書にする。1単位を scale ピクセルにして、周りに少し余白を付ける
+    pub fn to_svg(&self, scale: f64) -> String {
+        let (x0, y0, w, h) = self.extent().unwrap_or((0.0, 0.0, 0.0, 0.0));
+        let margin = 4.0;
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">\n",
+            num(w * scale + margin * 2.0),
+            num(h * scale + margin * 2.0),
+            num(x0 * scale - margin),
+            num(y0 * scale - margin),
+            num(w * scale + margin * 2.0),
+            num(h * scale + margin * 2.0),
+        );
+        for (i, (rect, label)) in self.shapes.iter().enumerate() {
+            let fill = colour::named(SVG_COLOURS[i % SVG_COLOURS.len()])
+                .expect("SVG_COLOURS は CSS の色名")
+                .to_hex();
+            let (x, y) = (rect.x.to_f64() * scale, rect.y.to_f64() * scale);
+            out.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.3\" stroke=\"{}\"/>\n",
+                num(x),
+                num(y),
+                num(rect.width.to_f64() * scale),
+                num(rect.height.to_f64() * scale),
+                fill,
+                fill
+            ));
+            if !label.is_empty() {
+                out.push_str(&format!(
+                    "  <text x=\"{}\" y=\"{}\" font-size=\"12\" dominant-baseline=\"hanging\">{}</text>\n",
+                    num(x + 2.0),
+                    num(y + 2.0),
+                    escape_xml(label)
+                ));
+            }
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+impl<T: Coord> Default for Drawing<T> {
+    fn default() -> Drawing<T> {
+        Drawing::new()
+    }
+}
+
+// SVG の数値は余計な 0 を付けない(12.0 は 12, 0.50 は 0.5)
+fn num(v: f64) -> String {
+    let s = format!("{:.3}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        String::from("0")
+    } else {
+        String::from(s)
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// can_hold の2つの長方形を左上を揃えて重ねた絵
+/// 位置は関係なく大きさだけを比べるので、どちらも原点に置き直して描く
+pub fn can_hold_picture<T: Coord + std::fmt::Display>(outer: &Rect<T>, inner: &Rect<T>) -> String {
+    let mut d = Drawing::new();
+    d.add(
+        Rect::with_size(outer.width, outer.height),
+        &format!("self {}x{}", outer.width, outer.height),
+    );
+    d.add(
+        Rect::with_size(inner.width, inner.height),
+        &format!("other {}x{}", inner.width, inner.height),
+    );
+    d.to_ascii(60)
+}
+
+/// outer.can_hold(&inner, rotation) を確かめる。落ちたら2つの形の絵をメッセージに付ける
+/// rotation を省くと Rotation::Fixed
+#[macro_export]
+macro_rules! assert_can_hold {
+    ($outer:expr, $inner:expr) => {
+        $crate::assert_can_hold!($outer, $inner, $crate::geometry::Rotation::Fixed)
+    };
+    ($outer:expr, $inner:expr, $rotation:expr) => {{
+        let (outer, inner) = (&$outer, &$inner);
+        assert!(
+            outer.can_hold(inner, $rotation),
+            "{} は {} を収められません\n{}",
+            outer,
+            inner,
+            $crate::draw::can_hold_picture(outer, inner)
+        );
+    }};
+}
+
+/// assert_can_hold! の逆で、収まらないことを確かめる
+#[macro_export]
+macro_rules! assert_cannot_hold {
+    ($outer:expr, $inner:expr) => {
+        $crate::assert_cannot_hold!($outer, $inner, $crate::geometry::Rotation::Fixed)
+    };
+    ($outer:expr, $inner:expr, $rotation:expr) => {{
+        let (outer, inner) = (&$outer, &$inner);
+        assert!(
+            !outer.can_hold(inner, $rotation),
+            "{} に {} が収まってしまいます\n{}",
+            outer,
+            inner,
+            $crate::draw::can_hold_picture(outer, inner)
+        );
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_box() {
+        let mut d = Drawing::new();
+        d.add(Rect::with_size(4, 2), "a");
+        assert_eq!(d.to_ascii(80), "┌───────┐\n│a      │\n└───────┘\n");
+    }
+
+    #[test]
+    fn overlapping_boxes_merge_lines() {
+        let mut d = Drawing::new();
+        d.add(Rect::new(0, 0, 3, 2), "");
+        d.add(Rect::new(1, 1, 3, 2), "");
+        assert_eq!(d.to_ascii(80), "┌─────┐\n│ ┌───┼─┐\n└─┼───┘ │\n  └─────┘\n");
+    }
+
+    #[test]
+    fn labels_fit_inside_and_handle_wide_chars() {
+        let mut d = Drawing::new();
+        d.add(Rect::with_size(5, 2), "家畜台帳");
+        d.add(Rect::new(0, 3, 5, 1), "long label that is cut");
+        assert_eq!(
+            d.to_ascii(80),
+            "┌─────────┐\n│家畜台帳 │\n└─────────┘\n┌long labe┐\n└─────────┘\n"
+        );
+    }
+
+    #[test]
+    fn wide_drawings_are_scaled_down() {
+        let mut d = Drawing::new();
+        d.add(Rect::with_size(100, 40), "");
+        let art = d.to_ascii(21);
+        assert!(art.lines().all(|l| l.chars().count() <= 21));
+        assert_eq!(art.lines().count(), 5);
+        assert_eq!(Drawing::<i32>::new().to_ascii(80), "");
+    }
+
+    #[test]
+    fn svg_document() {
+        let mut d = Drawing::new();
+        d.add(Rect::new(0.0, 0.0, 2.5, 1.0), "a<b");
+        let svg = d.to_svg(10.0);
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"33\" height=\"18\" viewBox=\"-4 -4 33 18\">\n"
+        ));
+        assert!(svg.contains(
+            "<rect x=\"0\" y=\"0\" width=\"25\" height=\"10\" fill=\"#4682b4\" fill-opacity=\"0.3\" stroke=\"#4682b4\"/>"
+        ));
+        assert!(svg.contains(">a&lt;b</text>"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn can_hold_picture_shows_both() {
+        let pic = can_hold_picture(&Rect::with_size(8, 3), &Rect::new(50, 50, 5, 1));
+        assert_eq!(
+            pic,
+            "┌other 5x1┬─────┐\n├─────────┘     │\n│self 8x3       │\n└───────────────┘\n"
+        );
+    }
+}
@@ -0,0 +1,355 @@
+// 長方形の幾何
+// ./others/structure.rs の Rect, RectDebug, MthRect と adder の Rectangle は
+// どれも「幅と高さを持つ長方形」を別々に作っていたので、位置付きの長方形としてここにまとめる
+// 座標は整数でも浮動小数点数でも使えるようにジェネリックにしている
+// 座標系は左上が原点で、x は右へ、y は下へ増える
+
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+
+/// 座標に使える数値型
+/// 整数と浮動小数点数の両方を扱うために必要な演算だけをまとめたもの
+pub trait Coord:
+    Copy + PartialOrd + fmt::Debug + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    fn zero() -> Self;
+    fn two() -> Self;
+    /// 距離の比較などで使う f64 への変換
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_coord {
+    ($($t:ty => $zero:expr, $two:expr;)*) => {
+        $(
+            impl Coord for $t {
+                fn zero() -> Self { $zero }
+                fn two() -> Self { $two }
+                fn to_f64(self) -> f64 { self as f64 }
+            }
+        )*
+    };
+}
+
+impl_coord! {
+    i32 => 0, 2;
+    i64 => 0, 2;
+    u32 => 0, 2;
+    u64 => 0, 2;
+    f32 => 0.0, 2.0;
+    f64 => 0.0, 2.0;
+}
+
+// PartialOrd しかない型(浮動小数点数)でも使える min, max
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Coord> Point<T> {
+    pub fn new(x: T, y: T) -> Point<T> {
+        Point { x, y }
+    }
+}
+
+/// 収まるかを調べるときに 90°回転を許すか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Fixed,
+    Allowed,
+}
+
+/// 位置付きの長方形
+/// (x, y) が左上の角で、幅と高さは 0 以上
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect<T> {
+    pub x: T,
+    pub y: T,
+    pub width: T,
+    pub height: T,
+}
+
+impl<T: Coord> Rect<T> {
+    /// 幅か高さが負ならパニック
+    pub fn new(x: T, y: T, width: T, height: T) -> Rect<T> {
+        assert!(
+            width >= T::zero() && height >= T::zero(),
+            "長方形の幅と高さは 0 以上でなければなりません: {:?} x {:?}",
+            width,
+            height
+        );
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// 原点に置いた 幅 x 高さ の長方形(MthRect::square と同じもの)
+    pub fn with_size(width: T, height: T) -> Rect<T> {
+        Rect::new(T::zero(), T::zero(), width, height)
+    }
+
+    /// 対角の2点から作る(どちらの角を先に渡してもよい)
+    pub fn from_corners(a: Point<T>, b: Point<T>) -> Rect<T> {
+        let (left, right) = (min(a.x, b.x), max(a.x, b.x));
+        let (top, bottom) = (min(a.y, b.y), max(a.y, b.y));
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    pub fn left(&self) -> T {
+        self.x
+    }
+
+    pub fn top(&self) -> T {
+        self.y
+    }
+
+    pub fn right(&self) -> T {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> T {
+        self.y + self.height
+    }
+
+    pub fn top_left(&self) -> Point<T> {
+        Point::new(self.x, self.y)
+    }
+
+    pub fn bottom_right(&self) -> Point<T> {
+        Point::new(self.right(), self.bottom())
+    }
+
+    /// 位置を変えずに幅と高さを入れ替える
+    pub fn rotated(&self) -> Rect<T> {
+        Rect::new(self.x, self.y, self.height, self.width)
+    }
+
+    /// 大きさを変えずに (x, y) へ動かす
+    pub fn moved_to(&self, x: T, y: T) -> Rect<T> {
+        Rect::new(x, y, self.width, self.height)
+    }
+
+    pub fn area(&self) -> T {
+        self.width * self.height
+    }
+
+    pub fn perimeter(&self) -> T {
+        T::two() * (self.width + self.height)
+    }
+
+    /// 面積が 0 か
+    pub fn is_empty(&self) -> bool {
+        self.width == T::zero() || self.height == T::zero()
+    }
+
+    /// 点が長方形の中にあるか
+    /// 左と上の辺は含み、右と下の辺は含まない(隣り合う長方形で点の取り合いが起きないように)
+    pub fn contains_point(&self, p: Point<T>) -> bool {
+        self.x <= p.x && p.x < self.right() && self.y <= p.y && p.y < self.bottom()
+    }
+
+    /// other が(辺が重なるのも含めて)まるごと self の中にあるか
+    pub fn contains(&self, other: &Rect<T>) -> bool {
+        self.x <= other.x
+            && self.y <= other.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// 重なっている部分。面積が 0 になる(辺で接するだけの)ときは None
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let left = max(self.x, other.x);
+        let top = max(self.y, other.y);
+        let right = min(self.right(), other.right());
+        let bottom = min(self.bottom(), other.bottom());
+        if left < right && top < bottom {
+            Some(Rect::new(left, top, right - left, bottom - top))
+        } else {
+            None
+        }
+    }
+
+    pub fn intersects(&self, other: &Rect<T>) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// 重なっている部分の面積
+    pub fn overlap_area(&self, other: &Rect<T>) -> T {
+        self.intersection(other).map_or(T::zero(), |r| r.area())
+    }
+
+    /// 両方を囲む一番小さい長方形(バウンディングボックス)
+    pub fn union(&self, other: &Rect<T>) -> Rect<T> {
+        Rect::from_corners(
+            Point::new(min(self.x, other.x), min(self.y, other.y)),
+            Point::new(
+                max(self.right(), other.right()),
+                max(self.bottom(), other.bottom()),
+            ),
+        )
+    }
+
+    /// 全部を囲む長方形。空なら None
+    pub fn bounding_box<'a, I>(rects: I) -> Option<Rect<T>>
+    where
+        I: IntoIterator<Item = &'a Rect<T>>,
+        T: 'a,
+    {
+        let mut iter = rects.into_iter();
+        let first = *iter.next()?;
+        Some(iter.fold(first, |acc, r| acc.union(r)))
+    }
+
+    /// other が self に(位置は気にせず)収まるか
+    /// 板書の can_hold と同じく、幅も高さも真に小さいときだけ true
+    /// Rotation::Allowed なら other を 90°回したものも試す
+    pub fn can_hold(&self, other: &Rect<T>, rotation: Rotation) -> bool {
+        let fits = |w: T, h: T| self.width > w && self.height > h;
+        fits(other.width, other.height)
+            || (rotation == Rotation::Allowed && fits(other.height, other.width))
+    }
+
+    /// can_hold と同じだが、ぴったり同じ大きさでも収まるとみなす(詰め込みなどで使う)
+    pub fn fits(&self, other: &Rect<T>, rotation: Rotation) -> bool {
+        let fits = |w: T, h: T| self.width >= w && self.height >= h;
+        fits(other.width, other.height)
+            || (rotation == Rotation::Allowed && fits(other.height, other.width))
+    }
+
+    /// 中心
+    pub fn center(&self) -> (f64, f64) {
+        (
+            self.x.to_f64() + self.width.to_f64() / 2.0,
+            self.y.to_f64() + self.height.to_f64() / 2.0,
+        )
+    }
+
+    /// 点から長方形までの距離の2乗(中にあれば 0)
+    pub fn distance_squared_to(&self, p: Point<T>) -> f64 {
+        let axis = |v: f64, lo: f64, hi: f64| {
+            if v < lo {
+                lo - v
+            } else if v > hi {
+                v - hi
+            } else {
+                0.0
+            }
+        };
+        let dx = axis(p.x.to_f64(), self.x.to_f64(), self.right().to_f64());
+        let dy = axis(p.y.to_f64(), self.y.to_f64(), self.bottom().to_f64());
+        dx * dx + dy * dy
+    }
+}
+
+impl<T: Coord + fmt::Display> fmt::Display for Rect<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} @ ({}, {})",
+            self.width, self.height, self.x, self.y
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chapter_examples() {
+        // structure.rs の板書と同じ値
+        assert_eq!(Rect::with_size(30u32, 50).area(), 1500);
+        assert_eq!(Rect::with_size(28u32, 16).area(), 448);
+        let rect1 = Rect::with_size(30u32, 50);
+        assert!(rect1.can_hold(&Rect::with_size(10, 40), Rotation::Fixed));
+        assert!(!rect1.can_hold(&Rect::with_size(60, 45), Rotation::Fixed));
+        // adder の Rectangle と同じ値
+        let larger = Rect::with_size(8, 7);
+        let smaller = Rect::with_size(5, 1);
+        assert!(larger.can_hold(&smaller, Rotation::Fixed));
+        assert!(!smaller.can_hold(&larger, Rotation::Fixed));
+    }
+
+    #[test]
+    fn rotation() {
+        let slot = Rect::with_size(10, 4);
+        let tall = Rect::with_size(3, 9);
+        assert!(!slot.can_hold(&tall, Rotation::Fixed));
+        assert!(slot.can_hold(&tall, Rotation::Allowed));
+        // ぴったりは can_hold では false、fits では true
+        assert!(!slot.can_hold(&Rect::with_size(4, 10), Rotation::Allowed));
+        assert!(slot.fits(&Rect::with_size(4, 10), Rotation::Allowed));
+        assert!(!slot.fits(&Rect::with_size(4, 10), Rotation::Fixed));
+    }
+
+    #[test]
+    fn intersection_and_union() {
+        let a = Rect::new(0, 0, 10, 10);
+        let b = Rect::new(5, 5, 10, 10);
+        assert_eq!(a.intersection(&b), Some(Rect::new(5, 5, 5, 5)));
+        assert_eq!(a.overlap_area(&b), 25);
+        assert_eq!(a.union(&b), Rect::new(0, 0, 15, 15));
+        // 辺で接するだけなら重なっていない
+        let c = Rect::new(10, 0, 5, 5);
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(a.overlap_area(&c), 0);
+        assert_eq!(
+            Rect::bounding_box(&[a, b, c]),
+            Some(Rect::new(0, 0, 15, 15))
+        );
+        assert_eq!(Rect::<i32>::bounding_box(&[]), None);
+    }
+
+    #[test]
+    fn points_and_containment() {
+        let r = Rect::new(-2, -2, 4, 4);
+        assert!(r.contains_point(Point::new(-2, -2)));
+        assert!(r.contains_point(Point::new(0, 1)));
+        assert!(!r.contains_point(Point::new(2, 0))); // 右の辺は含まない
+        assert!(r.contains(&Rect::new(-2, -2, 4, 4)));
+        assert!(r.contains(&Rect::new(-1, -1, 2, 2)));
+        assert!(!r.contains(&Rect::new(-1, -1, 4, 2)));
+        assert_eq!(r.perimeter(), 16);
+        assert_eq!(r.distance_squared_to(Point::new(5, 6)), 9.0 + 16.0);
+        assert_eq!(r.distance_squared_to(Point::new(0, 0)), 0.0);
+    }
+
+    #[test]
+    fn floats() {
+        let a = Rect::new(0.5, 0.5, 2.0, 1.5);
+        let b = Rect::new(1.5, 1.0, 2.0, 2.0);
+        assert_eq!(a.overlap_area(&b), 1.0);
+        assert_eq!(a.perimeter(), 7.0);
+        assert_eq!(a.center(), (1.5, 1.25));
+        assert_eq!(format!("{}", a), "2x1.5 @ (0.5, 0.5)");
+        assert_eq!(
+            Rect::from_corners(Point::new(3.0, 1.0), Point::new(1.0, 2.0)),
+            Rect::new(1.0, 1.0, 2.0, 1.0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "0 以上")]
+    fn negative_size_panics() {
+        Rect::new(0, 0, -1, 3);
+    }
+}
@@ -0,0 +1,616 @@
+// IP アドレス
+// ./others/enm_mch_iflet.rs の IpAddrII::V4(u8, u8, u8, u8) と V6(String) は形を表すだけで
+// 読み込みも検査もしていなかったので、ちゃんと読んで書けるアドレスの型にしたもの
+// IPv6 は `::` の省略と埋め込みの IPv4 を読み、書くときは RFC 5952 の正規形にする
+// std::net の型とも相互に変換できる(板書でも言っている通り本来はそっちを使えばよい)
+
+use std::fmt;
+use std::net;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpError {
+    InvalidV4 { input: String, reason: &'static str },
+    InvalidV6 { input: String, reason: &'static str },
+}
+
+impl fmt::Display for IpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpError::InvalidV4 { input, reason } => {
+                write!(f, "`{}` は IPv4 アドレスではありません({})", input, reason)
+            }
+            IpError::InvalidV6 { input, reason } => {
+                write!(f, "`{}` は IPv6 アドレスではありません({})", input, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IpError {}
+
+/// アドレスの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Unspecified,   // 0.0.0.0, ::
+    Loopback,      // 127.0.0.0/8, ::1
+    Private,       // 10/8, 172.16/12, 192.168/16, fc00::/7(ユニークローカル)
+    LinkLocal,     // 169.254/16, fe80::/10
+    Multicast,     // 224/4, ff00::/8
+    Broadcast,     // 255.255.255.255
+    Documentation, // 192.0.2/24, 198.51.100/24, 203.0.113/24, 2001:db8::/32
+    Global,        // それ以外
+}
+
+/// IPv4 アドレス(IpAddrII::V4 の4つの u8)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ipv4(pub [u8; 4]);
+
+impl Ipv4 {
+    pub const UNSPECIFIED: Ipv4 = Ipv4([0, 0, 0, 0]);
+    pub const LOCALHOST: Ipv4 = Ipv4([127, 0, 0, 1]);
+    pub const BROADCAST: Ipv4 = Ipv4([255, 255, 255, 255]);
+
+    pub fn new(a: u8, b: u8, c: u8, d: u8) -> Ipv4 {
+        Ipv4([a, b, c, d])
+    }
+
+    pub fn octets(&self) -> [u8; 4] {
+        self.0
+    }
+
+    pub fn to_u32(self) -> u32 {
+        u32::from_be_bytes(self.0)
+    }
+
+    pub fn from_u32(n: u32) -> Ipv4 {
+        Ipv4(n.to_be_bytes())
+    }
+
+    /// 10進の4つ組(a.b.c.d)を読む
+    /// std と同じく、0 以外で 0 から始まる数(8進数と紛らわしい 010 など)は受け付けない
+    pub fn parse(s: &str) -> Result<Ipv4, IpError> {
+        let err = |reason| IpError::InvalidV4 {
+            input: String::from(s),
+            reason,
+        };
+        let mut octets = [0u8; 4];
+        let mut parts = s.split('.');
+        for octet in octets.iter_mut() {
+            let part = parts.next().ok_or_else(|| err("数が4つありません"))?;
+            *octet =
+                parse_octet(part).ok_or_else(|| err("0 ~ 255 の10進数でない部分があります"))?;
+        }
+        if parts.next().is_some() {
+            return Err(err("数が5つ以上あります"));
+        }
+        Ok(Ipv4(octets))
+    }
+
+    /// 先頭の prefix ビットが net と同じか
+    fn in_block(&self, net: [u8; 4], prefix: u32) -> bool {
+        let mask = if prefix == 0 {
+            0
+        } else {
+            u32::MAX << (32 - prefix)
+        };
+        self.to_u32() & mask == u32::from_be_bytes(net) & mask
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        *self == Ipv4::UNSPECIFIED
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.0[0] == 127
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.in_block([10, 0, 0, 0], 8)
+            || self.in_block([172, 16, 0, 0], 12)
+            || self.in_block([192, 168, 0, 0], 16)
+    }
+
+    pub fn is_link_local(&self) -> bool {
+        self.in_block([169, 254, 0, 0], 16)
+    }
+
+    pub fn is_multicast(&self) -> bool {
+        self.in_block([224, 0, 0, 0], 4)
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        *self == Ipv4::BROADCAST
+    }
+
+    pub fn is_documentation(&self) -> bool {
+        self.in_block([192, 0, 2, 0], 24)
+            || self.in_block([198, 51, 100, 0], 24)
+            || self.in_block([203, 0, 113, 0], 24)
+    }
+
+    pub fn scope(&self) -> Scope {
+        if self.is_unspecified() {
+            Scope::Unspecified
+        } else if self.is_loopback() {
+            Scope::Loopback
+        } else if self.is_private() {
+            Scope::Private
+        } else if self.is_link_local() {
+            Scope::LinkLocal
+        } else if self.is_multicast() {
+            Scope::Multicast
+        } else if self.is_broadcast() {
+            Scope::Broadcast
+        } else if self.is_documentation() {
+            Scope::Documentation
+        } else {
+            Scope::Global
+        }
+    }
+
+    /// ::ffff:a.b.c.d の形(IPv4 射影アドレス)にする
+    pub fn to_ipv6_mapped(self) -> Ipv6 {
+        let [a, b, c, d] = self.0;
+        Ipv6([
+            0,
+            0,
+            0,
+            0,
+            0,
+            0xffff,
+            u16::from_be_bytes([a, b]),
+            u16::from_be_bytes([c, d]),
+        ])
+    }
+}
+
+fn parse_octet(part: &str) -> Option<u8> {
+    let valid = !part.is_empty()
+        && part.len() <= 3
+        && part.bytes().all(|b| b.is_ascii_digit())
+        && !(part.len() > 1 && part.starts_with('0'));
+    if valid {
+        part.parse().ok()
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for Ipv4 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b, c, d] = self.0;
+        write!(f, "{}.{}.{}.{}", a, b, c, d)
+    }
+}
+
+impl FromStr for Ipv4 {
+    type Err = IpError;
+
+    fn from_str(s: &str) -> Result<Ipv4, IpError> {
+        Ipv4::parse(s)
+    }
+}
+
+/// IPv6 アドレス(16bit の組が8つ)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ipv6(pub [u16; 8]);
+
+impl Ipv6 {
+    pub const UNSPECIFIED: Ipv6 = Ipv6([0; 8]);
+    pub const LOCALHOST: Ipv6 = Ipv6([0, 0, 0, 0, 0, 0, 0, 1]);
+
+    pub fn segments(&self) -> [u16; 8] {
+        self.0
+    }
+
+    pub fn to_u128(self) -> u128 {
+        self.0.iter().fold(0, |acc, &s| (acc << 16) | s as u128)
+    }
+
+    pub fn from_u128(n: u128) -> Ipv6 {
+        let mut segments = [0u16; 8];
+        for (i, s) in segments.iter_mut().enumerate() {
+            *s = (n >> (112 - 16 * i)) as u16;
+        }
+        Ipv6(segments)
+    }
+
+    /// コロン区切りの16進を読む
+    /// `::` は1回だけ使えて1つ以上の 0 の組を表す。最後の32bit は a.b.c.d でも書ける
+    pub fn parse(s: &str) -> Result<Ipv6, IpError> {
+        let err = |reason| IpError::InvalidV6 {
+            input: String::from(s),
+            reason,
+        };
+        let (head, tail) = match s.find("::") {
+            Some(i) => {
+                if s[i + 2..].contains("::") {
+                    return Err(err("`::` が2回以上あります"));
+                }
+                (&s[..i], Some(&s[i + 2..]))
+            }
+            None => (s, None),
+        };
+        // 片側の組を読む。last なら最後に IPv4 の4つ組があってもよい
+        let groups = |part: &str, last: bool| -> Result<Vec<u16>, IpError> {
+            let mut out = Vec::new();
+            if part.is_empty() {
+                return Ok(out);
+            }
+            let pieces: Vec<&str> = part.split(':').collect();
+            for (i, piece) in pieces.iter().enumerate() {
+                if last && i == pieces.len() - 1 && piece.contains('.') {
+                    let v4 = Ipv4::parse(piece)
+                        .map_err(|_| err("埋め込みの IPv4 が正しくありません"))?;
+                    let [a, b, c, d] = v4.0;
+                    out.push(u16::from_be_bytes([a, b]));
+                    out.push(u16::from_be_bytes([c, d]));
+                } else if piece.is_empty() || piece.len() > 4 {
+                    return Err(err("組は1 ~ 4桁の16進数です"));
+                } else {
+                    out.push(
+                        u16::from_str_radix(piece, 16)
+                            .ok()
+                            .filter(|_| piece.bytes().all(|b| b.is_ascii_hexdigit()))
+                            .ok_or_else(|| err("16進数でない組があります"))?,
+                    );
+                }
+            }
+            Ok(out)
+        };
+        let mut segments = [0u16; 8];
+        match tail {
+            None => {
+                let all = groups(head, true)?;
+                if all.len() != 8 {
+                    return Err(err("`::` なしでは組が8つ必要です"));
+                }
+                segments.copy_from_slice(&all);
+            }
+            Some(tail) => {
+                let front = groups(head, false)?;
+                let back = groups(tail, true)?;
+                if front.len() + back.len() > 7 {
+                    return Err(err("`::` を使うと組は7つまでです"));
+                }
+                segments[..front.len()].copy_from_slice(&front);
+                segments[8 - back.len()..].copy_from_slice(&back);
+            }
+        }
+        Ok(Ipv6(segments))
+    }
+
+    /// 先頭の prefix ビットが net と同じか
+    fn in_block(&self, net: u128, prefix: u32) -> bool {
+        let mask = if prefix == 0 {
+            0
+        } else {
+            u128::MAX << (128 - prefix)
+        };
+        self.to_u128() & mask == net & mask
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        *self == Ipv6::UNSPECIFIED
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        *self == Ipv6::LOCALHOST
+    }
+
+    /// ユニークローカル(fc00::/7)。IPv4 のプライベートアドレスにあたる
+    pub fn is_unique_local(&self) -> bool {
+        self.0[0] & 0xfe00 == 0xfc00
+    }
+
+    pub fn is_link_local(&self) -> bool {
+        self.0[0] & 0xffc0 == 0xfe80
+    }
+
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0xff00 == 0xff00
+    }
+
+    pub fn is_documentation(&self) -> bool {
+        self.in_block(0x2001_0db8 << 96, 32)
+    }
+
+    pub fn scope(&self) -> Scope {
+        if self.is_unspecified() {
+            Scope::Unspecified
+        } else if self.is_loopback() {
+            Scope::Loopback
+        } else if self.is_unique_local() {
+            Scope::Private
+        } else if self.is_link_local() {
+            Scope::LinkLocal
+        } else if self.is_multicast() {
+            Scope::Multicast
+        } else if self.is_documentation() {
+            Scope::Documentation
+        } else {
+            Scope::Global
+        }
+    }
+
+    /// ::ffff:a.b.c.d なら中の IPv4
+    pub fn to_ipv4_mapped(&self) -> Option<Ipv4> {
+        match self.0 {
+            [0, 0, 0, 0, 0, 0xffff, hi, lo] => {
+                let [a, b] = hi.to_be_bytes();
+                let [c, d] = lo.to_be_bytes();
+                Some(Ipv4([a, b, c, d]))
+            }
+            _ => None,
+        }
+    }
+}
+
+// RFC 5952 の正規形
+// 16進は小文字で先頭の 0 を省く。2つ以上続く 0 の組のうち一番長いもの(同じ長さなら前のもの)を `::` にする
+// IPv4 射影アドレスは最後の32bit を a.b.c.d で書く
+impl fmt::Display for Ipv6 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(v4) = self.to_ipv4_mapped() {
+            return write!(f, "::ffff:{}", v4);
+        }
+        // 一番長い 0 の並び (始まり, 長さ)
+        let mut best = (0, 0);
+        let mut i = 0;
+        while i < 8 {
+            if self.0[i] == 0 {
+                let start = i;
+                while i < 8 && self.0[i] == 0 {
+                    i += 1;
+                }
+                if i - start > best.1 {
+                    best = (start, i - start);
+                }
+            } else {
+                i += 1;
+            }
+        }
+        let write_groups = |f: &mut fmt::Formatter, groups: &[u16]| -> fmt::Result {
+            for (i, g) in groups.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(":")?;
+                }
+                write!(f, "{:x}", g)?;
+            }
+            Ok(())
+        };
+        if best.1 < 2 {
+            return write_groups(f, &self.0);
+        }
+        write_groups(f, &self.0[..best.0])?;
+        f.write_str("::")?;
+        write_groups(f, &self.0[best.0 + best.1..])
+    }
+}
+
+impl FromStr for Ipv6 {
+    type Err = IpError;
+
+    fn from_str(s: &str) -> Result<Ipv6, IpError> {
+        Ipv6::parse(s)
+    }
+}
+
+/// IPv4 か IPv6(IpAddrII の置き換え)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IpAddress {
+    V4(Ipv4),
+    V6(Ipv6),
+}
+
+impl IpAddress {
+    /// `:` を含めば IPv6、そうでなければ IPv4 として読む
+    pub fn parse(s: &str) -> Result<IpAddress, IpError> {
+        if s.contains(':') {
+            Ipv6::parse(s).map(IpAddress::V6)
+        } else {
+            Ipv4::parse(s).map(IpAddress::V4)
+        }
+    }
+
+    pub fn scope(&self) -> Scope {
+        match self {
+            IpAddress::V4(a) => a.scope(),
+            IpAddress::V6(a) => a.scope(),
+        }
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.scope() == Scope::Loopback
+    }
+}
+
+impl fmt::Display for IpAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpAddress::V4(a) => a.fmt(f),
+            IpAddress::V6(a) => a.fmt(f),
+        }
+    }
+}
+
+impl FromStr for IpAddress {
+    type Err = IpError;
+
+    fn from_str(s: &str) -> Result<IpAddress, IpError> {
+        IpAddress::parse(s)
+    }
+}
+
+impl From<Ipv4> for IpAddress {
+    fn from(a: Ipv4) -> IpAddress {
+        IpAddress::V4(a)
+    }
+}
+
+impl From<Ipv6> for IpAddress {
+    fn from(a: Ipv6) -> IpAddress {
+        IpAddress::V6(a)
+    }
+}
+
+// std::net との変換
+
+impl From<net::Ipv4Addr> for Ipv4 {
+    fn from(a: net::Ipv4Addr) -> Ipv4 {
+        Ipv4(a.octets())
+    }
+}
+
+impl From<Ipv4> for net::Ipv4Addr {
+    fn from(a: Ipv4) -> net::Ipv4Addr {
+        net::Ipv4Addr::from(a.0)
+    }
+}
+
+impl From<net::Ipv6Addr> for Ipv6 {
+    fn from(a: net::Ipv6Addr) -> Ipv6 {
+        Ipv6(a.segments())
+    }
+}
+
+impl From<Ipv6> for net::Ipv6Addr {
+    fn from(a: Ipv6) -> net::Ipv6Addr {
+        net::Ipv6Addr::from(a.0)
+    }
+}
+
+impl From<net::IpAddr> for IpAddress {
+    fn from(a: net::IpAddr) -> IpAddress {
+        match a {
+            net::IpAddr::V4(a) => IpAddress::V4(a.into()),
+            net::IpAddr::V6(a) => IpAddress::V6(a.into()),
+        }
+    }
+}
+
+impl From<IpAddress> for net::IpAddr {
+    fn from(a: IpAddress) -> net::IpAddr {
+        match a {
+            IpAddress::V4(a) => net::IpAddr::V4(a.into()),
+            IpAddress::V6(a) => net::IpAddr::V6(a.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chapter_examples() {
+        // IpAddrII::V4(127, 0, 0, 1) と IpAddrII::V6(String::from("::1"))
+        let home = IpAddress::V4(Ipv4::new(127, 0, 0, 1));
+        let loopback = IpAddress::parse("::1").unwrap();
+        assert_eq!(home.to_string(), "127.0.0.1");
+        assert_eq!(loopback, IpAddress::V6(Ipv6::LOCALHOST));
+        assert!(home.is_loopback() && loopback.is_loopback());
+    }
+
+    #[test]
+    fn v4_parsing() {
+        assert_eq!(Ipv4::parse("192.168.0.1"), Ok(Ipv4::new(192, 168, 0, 1)));
+        assert_eq!(Ipv4::parse("0.0.0.0"), Ok(Ipv4::UNSPECIFIED));
+        for bad in &[
+            "1.2.3",
+            "1.2.3.4.5",
+            "256.0.0.1",
+            "01.2.3.4",
+            "1..2.3",
+            "+1.2.3.4",
+            "",
+        ] {
+            assert!(Ipv4::parse(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(Ipv4::from_u32(0x7f00_0001), Ipv4::LOCALHOST);
+    }
+
+    #[test]
+    fn v6_rfc5952_output() {
+        let cases = [
+            ("2001:0db8:0000:0000:0000:0000:0002:0001", "2001:db8::2:1"),
+            ("2001:db8:0:1:1:1:1:1", "2001:db8:0:1:1:1:1:1"), // 0 が1つなら省かない
+            ("2001:0:0:1:0:0:0:1", "2001:0:0:1::1"),          // 長い方を省く
+            ("2001:db8:0:0:1:0:0:1", "2001:db8::1:0:0:1"),    // 同じ長さなら前
+            ("2001:DB8::ABCD", "2001:db8::abcd"),
+            ("::", "::"),
+            ("1::", "1::"),
+            ("::ffff:192.0.2.1", "::ffff:192.0.2.1"),
+            ("::ffff:c000:0201", "::ffff:192.0.2.1"),
+            ("::192.0.2.1", "::c000:201"),
+            ("64:ff9b::192.0.2.33", "64:ff9b::c000:221"),
+        ];
+        for &(input, expected) in cases.iter() {
+            assert_eq!(
+                Ipv6::parse(input).unwrap().to_string(),
+                expected,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn v6_rejects() {
+        for bad in &[
+            "1:2:3:4:5:6:7",
+            "1:2:3:4:5:6:7:8:9",
+            "1::2::3",
+            "1:::2",
+            "12345::",
+            "::g",
+            "1:2:3:4:5:6:7:8::",
+            "::1.2.3",
+            "1.2.3.4::",
+            ":1::",
+            "1::2:",
+        ] {
+            assert!(Ipv6::parse(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(
+            Ipv6::parse("1::2::3").unwrap_err().to_string(),
+            "`1::2::3` は IPv6 アドレスではありません(`::` が2回以上あります)"
+        );
+    }
+
+    #[test]
+    fn scopes() {
+        let scope = |s: &str| IpAddress::parse(s).unwrap().scope();
+        assert_eq!(scope("10.1.2.3"), Scope::Private);
+        assert_eq!(scope("172.31.255.255"), Scope::Private);
+        assert_eq!(scope("172.32.0.0"), Scope::Global);
+        assert_eq!(scope("169.254.1.1"), Scope::LinkLocal);
+        assert_eq!(scope("239.0.0.1"), Scope::Multicast);
+        assert_eq!(scope("255.255.255.255"), Scope::Broadcast);
+        assert_eq!(scope("203.0.113.5"), Scope::Documentation);
+        assert_eq!(scope("8.8.8.8"), Scope::Global);
+        assert_eq!(scope("::"), Scope::Unspecified);
+        assert_eq!(scope("fd12::1"), Scope::Private);
+        assert_eq!(scope("fe80::1"), Scope::LinkLocal);
+        assert_eq!(scope("febf::1"), Scope::LinkLocal);
+        assert_eq!(scope("fec0::1"), Scope::Global);
+        assert_eq!(scope("ff02::1"), Scope::Multicast);
+        assert_eq!(scope("2001:db8::1"), Scope::Documentation);
+    }
+
+    #[test]
+    fn std_conversions() {
+        let v4: net::Ipv4Addr = Ipv4::new(1, 2, 3, 4).into();
+        assert_eq!(v4, net::Ipv4Addr::new(1, 2, 3, 4));
+        let v6 = Ipv6::parse("2001:db8::1").unwrap();
+        assert_eq!(Ipv6::from(net::Ipv6Addr::from(v6)), v6);
+        let any: net::IpAddr = IpAddress::V4(Ipv4::LOCALHOST).into();
+        assert!(any.is_loopback());
+        assert_eq!(IpAddress::from(any), IpAddress::V4(Ipv4::LOCALHOST));
+        assert_eq!(
+            Ipv4::new(192, 0, 2, 1).to_ipv6_mapped().to_ipv4_mapped(),
+            Some(Ipv4::new(192, 0, 2, 1))
+        );
+    }
+}
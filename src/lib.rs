@@ -0,0 +1,26 @@
+// 板書で出てきた例を実用できる形に育てたモジュール置き場
+// 板書そのもの(./others/)は main.rs から読まれるのでここには含めない
+
+pub mod chart; // 分布の図(ヒストグラム, 箱ひげ図)
+pub mod colour; // 色の変換, 混色, 端末の色
+pub mod csv; // CSV の読み書き
+pub mod draw; // 長方形の絵(罫線, SVG)
+pub mod geometry; // 位置付きの長方形
+pub mod ip; // IPv4/IPv6 アドレス
+pub mod lifetime_diagram; // ライフタイム図の自動生成
+pub mod livestock; // 家畜台帳
+pub mod maybe; // 自作の Option と Result
+pub mod message; // Message の配送と取り消し
+pub mod money; // 通貨付きの金額
+pub mod numerals; // 万, 億 や漢数字での数の書き方
+pub mod packing; // 長方形の詰め込み
+pub mod pig_latin; // 文のピッグ・ラテン変換と逆変換
+pub mod register; // レジ(おつり, 締め)
+pub mod sheet; // 表計算(式, 再計算)
+pub mod spatial; // 長方形の空間索引(四分木)
+pub mod statistics; // 平均, 分散, 分位数, 相関と回帰
+pub mod subnet; // CIDR のサブネット計算
+pub mod text; // 確保を減らした文字列の小道具
+pub mod transform; // 文字列の変換器とパイプライン
+pub mod wire; // Message の通信用の書き方
+pub mod wordfreq; // 単語の出現回数(n-gram, 日本語の文字 bigram)
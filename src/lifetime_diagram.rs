@@ -0,0 +1,750 @@
+// ライフタイム図の自動生成
+// ./others/lifetime.rs にある `// --------+- 'a` みたいな括弧の図を手で描くのはつらいので、
+// let束縛, ネストしたスコープ, 参照 を含む小さな Rust風ブロックから自動で描く
+//
+// 入力(ライフタイム名は `// 'a` のように注釈できる。省略したら 'a から順に空いてる名前が付く):
+//
+//     let r;         // 'a
+//     {
+//         let x = 5; // 'b
+//         r = &x;
+//     }
+//     println!("{}", r);
+//
+// 出力:
+//
+//     let r;             // --------+- 'a
+//     {                  //         |
+//         let x = 5;     // -+- 'b  |
+//         r = &x;        //  |      |
+//     }                  // -+      |
+//     println!("{}", r); //         |
+//
+// 短命な(内側の)区間ほど左の列に、それを囲む区間ほど右の列に置かれる
+// ブロック内で閉じ括弧に当たらなかった束縛は最後の行まで `|` が伸びる
+// 既に図が描かれたブロックをもう一度読ませると同じ図が出てくるので、check関数で図が最新か検査できる
+
+use crate::text::display_width;
+use std::fmt;
+
+/// 1つの let束縛の生存区間
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub name: String,  // 束縛名(r や x)
+    pub label: String, // ライフタイム名('a など)
+    pub start: usize,  // 誕生した行(0始まり)
+    pub end: usize,    // 死亡した行
+    pub closed: bool,  // ブロック内の閉じ括弧で死んだか(false なら最後の行まで生きている)
+}
+
+/// `r = &x;` のような借用。holder と referent は regions の添字
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Borrow {
+    pub holder: usize,
+    pub referent: usize,
+    pub line: usize,
+}
+
+/// 参照先が死んだ後に参照が使われている箇所(ダングリング参照)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dangling {
+    pub holder: String,
+    pub referent: String,
+    pub borrow_line: usize,
+    pub use_line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagramError {
+    DuplicateLabel { label: String, line: usize }, // 同じライフタイム名が2回注釈された
+    LabelsExhausted,                               // 'a ~ 'z を使い切った
+}
+
+impl fmt::Display for DiagramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagramError::DuplicateLabel { label, line } => {
+                write!(
+                    f,
+                    "{}行目: ライフタイム名 {} が重複しています",
+                    line + 1,
+                    label
+                )
+            }
+            DiagramError::LabelsExhausted => {
+                write!(f, "自動で付けられるライフタイム名が足りません")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiagramError {}
+
+/// 図の検査結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckError {
+    Parse(DiagramError),
+    Mismatch {
+        line: usize,
+        expected: String,
+        found: String,
+    }, // 描き直した図と食い違った最初の行
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Parse(e) => e.fmt(f),
+            CheckError::Mismatch {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}行目の図が古いです\n  期待: {}\n  実際: {}",
+                line + 1,
+                expected,
+                found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+impl From<DiagramError> for CheckError {
+    fn from(e: DiagramError) -> CheckError {
+        CheckError::Parse(e)
+    }
+}
+
+// 入力の1行(コードとコメントに分けたもの)
+#[derive(Debug)]
+struct SourceLine {
+    code: String,         // コメントより前の部分(末尾の空白は除く)
+    note: Option<String>, // 図の後ろ(または図の代わり)に書かれていた普通のコメント
+    idents: Vec<String>,  // 文字列リテラル外の識別子
+}
+
+/// 読み込んだブロックとそこから求めた生存区間, 借用
+#[derive(Debug)]
+pub struct Diagram {
+    lines: Vec<SourceLine>,
+    regions: Vec<Region>,
+    borrows: Vec<Borrow>,
+}
+
+/// ブロックを読み込んで図を描いた文字列を返す
+pub fn render(src: &str) -> Result<String, DiagramError> {
+    Ok(parse(src)?.render())
+}
+
+/// 図の描かれたブロックを描き直して、同じになるか確かめる
+pub fn check(block: &str) -> Result<(), CheckError> {
+    let rendered = parse(block)?.render();
+    let mut expected = rendered.lines();
+    let mut found = block.lines();
+    let mut line = 0;
+    loop {
+        match (expected.next(), found.next()) {
+            (None, None) => return Ok(()),
+            (e, f) => {
+                let e = e.unwrap_or("").trim_end();
+                let f = f.unwrap_or("").trim_end();
+                if e != f {
+                    return Err(CheckError::Mismatch {
+                        line,
+                        expected: e.to_string(),
+                        found: f.to_string(),
+                    });
+                }
+            }
+        }
+        line += 1;
+    }
+}
+
+/// ファイルの中から図が描かれているブロックを探して、その行範囲(0始まり, 終端は含まない)を返す
+/// 図のコメントが付いた行が連続しているところを1つのブロックとみなす
+pub fn find_blocks(source: &str) -> Vec<std::ops::Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut start = None;
+    let mut count = 0;
+    for (i, line) in source.lines().enumerate() {
+        let (_, comment) = split_comment(line);
+        let drawn = comment
+            .map(|c| {
+                let (diagram, _) = split_note(c);
+                diagram.is_some_and(|d| d.contains('+') || d.contains('|'))
+            })
+            .unwrap_or(false);
+        match (drawn, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                blocks.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+        count = i + 1;
+    }
+    if let Some(s) = start {
+        blocks.push(s..count);
+    }
+    blocks
+}
+
+/// ブロックを読み込む
+pub fn parse(src: &str) -> Result<Diagram, DiagramError> {
+    let mut lines = Vec::new();
+    let mut regions: Vec<Region> = Vec::new();
+    let mut borrows = Vec::new();
+    let mut explicit: Vec<Option<String>> = Vec::new(); // regions と同じ並びの注釈されたライフタイム名
+
+    // スコープごとにそこで生まれた束縛の添字を積む
+    // ブロック自体の外側にも暗黙のスコープがあるとみなすので、最初から1段積んでおく
+    let mut scopes: Vec<Vec<usize>> = vec![Vec::new()];
+
+    for (i, raw) in src.lines().enumerate() {
+        let (code, comment) = split_comment(raw);
+        let (label, note) = match comment {
+            Some(c) => match split_note(c) {
+                (Some(d), note) => (diagram_label(d), note),
+                (None, _) => (None, Some(c.trim().to_string())),
+            },
+            None => (None, None),
+        };
+        let code = code.trim_end();
+        let mut idents = Vec::new();
+        let mut let_here = None;
+        let mut expect_binding = false;
+        for token in tokenize(code) {
+            match token {
+                Token::Open => scopes.push(Vec::new()),
+                Token::Close => {
+                    let dying = scopes.pop().unwrap_or_default();
+                    for r in dying {
+                        regions[r].end = i;
+                        regions[r].closed = true;
+                    }
+                    if scopes.is_empty() {
+                        scopes.push(Vec::new());
+                    }
+                }
+                Token::Ident(id) => {
+                    if id == "let" {
+                        expect_binding = true;
+                    } else if expect_binding && id != "mut" {
+                        expect_binding = false;
+                        let_here = Some(regions.len());
+                        regions.push(Region {
+                            name: id.clone(),
+                            label: String::new(),
+                            start: i,
+                            end: usize::MAX,
+                            closed: false,
+                        });
+                        explicit.push(label.clone());
+                        if let Some(scope) = scopes.last_mut() {
+                            scope.push(regions.len() - 1);
+                        }
+                    }
+                    idents.push(id);
+                }
+                Token::Other => expect_binding = false,
+            }
+        }
+
+        // 借用の検出(`let r = &x;` と `r = &x;` の形だけ)
+        if let Some(referent) = borrowed_name(code) {
+            let holder = match let_here {
+                Some(r) => Some(r),
+                None => assigned_name(code).and_then(|name| live(&regions, name, i)),
+            };
+            if let (Some(holder), Some(referent)) = (holder, live(&regions, referent, i)) {
+                if holder != referent {
+                    borrows.push(Borrow {
+                        holder,
+                        referent,
+                        line: i,
+                    });
+                }
+            }
+        }
+
+        lines.push(SourceLine {
+            code: code.to_string(),
+            note,
+            idents,
+        });
+    }
+
+    let last = lines.len().saturating_sub(1);
+    for r in regions.iter_mut() {
+        if !r.closed {
+            r.end = last;
+        }
+    }
+
+    // ライフタイム名を決める。注釈が優先で、残りは 'a から順に空いてるものを割り当てる
+    for (r, label) in explicit.iter().enumerate() {
+        if let Some(label) = label {
+            if regions.iter().any(|other| &other.label == label) {
+                return Err(DiagramError::DuplicateLabel {
+                    label: label.clone(),
+                    line: regions[r].start,
+                });
+            }
+            regions[r].label = label.clone();
+        }
+    }
+    let mut letters = (b'a'..=b'z').map(|c| format!("'{}", c as char));
+    for r in 0..regions.len() {
+        if regions[r].label.is_empty() {
+            regions[r].label = loop {
+                let candidate = letters.next().ok_or(DiagramError::LabelsExhausted)?;
+                if !regions.iter().any(|other| other.label == candidate) {
+                    break candidate;
+                }
+            };
+        }
+    }
+
+    Ok(Diagram {
+        lines,
+        regions,
+        borrows,
+    })
+}
+
+impl Diagram {
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn borrows(&self) -> &[Borrow] {
+        &self.borrows
+    }
+
+    /// 参照先が死んだ後に参照を使っている箇所を探す
+    /// 借用チェッカーの真似事なので、単純な `r = &x;` の形しか見ない
+    pub fn dangling(&self) -> Vec<Dangling> {
+        let mut found = Vec::new();
+        for (n, b) in self.borrows.iter().enumerate() {
+            let holder = &self.regions[b.holder];
+            let referent = &self.regions[b.referent];
+            if !referent.closed {
+                continue;
+            }
+            // 同じ参照に別の借用が入ったらそこで追跡終了
+            let reborrow = self.borrows[n + 1..]
+                .iter()
+                .find(|later| later.holder == b.holder)
+                .map_or(usize::MAX, |later| later.line);
+            let uses = (referent.end + 1..=holder.end)
+                .take_while(|&line| line < reborrow)
+                .find(|&line| self.lines[line].idents.iter().any(|id| id == &holder.name));
+            if let Some(use_line) = uses {
+                found.push(Dangling {
+                    holder: holder.name.clone(),
+                    referent: referent.name.clone(),
+                    borrow_line: b.line,
+                    use_line,
+                });
+            }
+        }
+        found
+    }
+
+    /// 図を描く
+    pub fn render(&self) -> String {
+        let columns = self.columns();
+        let label_width = self
+            .regions
+            .iter()
+            .map(|r| r.label.chars().count() + 2)
+            .max()
+            .unwrap_or(0);
+        let position = |col: usize| 1 + col * (label_width + 3);
+
+        let mut diagrams = Vec::with_capacity(self.lines.len());
+        for i in 0..self.lines.len() {
+            let mut canvas: Vec<char> = Vec::new();
+            let put = |canvas: &mut Vec<char>, at: usize, c: char| {
+                if canvas.len() <= at {
+                    canvas.resize(at + 1, ' ');
+                }
+                canvas[at] = c;
+            };
+            // この行で生まれるか死ぬ区間のうち一番右の列まで `-` を引く
+            let marked = |r: &Region| r.start == i || (r.closed && r.end == i);
+            let reach = self
+                .regions
+                .iter()
+                .zip(&columns)
+                .filter(|(r, _)| marked(r))
+                .map(|(_, &col)| position(col))
+                .max();
+            if let Some(reach) = reach {
+                for at in 0..reach {
+                    put(&mut canvas, at, '-');
+                }
+            }
+            for (r, &col) in self.regions.iter().zip(&columns) {
+                if r.start > i || r.end < i {
+                    continue;
+                }
+                let at = position(col);
+                if marked(r) {
+                    put(&mut canvas, at, '+');
+                    if r.start == i {
+                        for (k, c) in format!("- {}", r.label).chars().enumerate() {
+                            put(&mut canvas, at + 1 + k, c);
+                        }
+                    }
+                } else if reach.is_none_or(|reach| at > reach) {
+                    put(&mut canvas, at, '|');
+                }
+            }
+            let drawn: String = canvas.into_iter().collect();
+            diagrams.push(drawn.trim_end().to_string());
+        }
+
+        let code_width = self
+            .lines
+            .iter()
+            .map(|l| display_width(&l.code))
+            .max()
+            .unwrap_or(0);
+        let diagram_width = diagrams
+            .iter()
+            .map(|d| d.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut out = String::new();
+        for (line, diagram) in self.lines.iter().zip(&diagrams) {
+            let padding = " ".repeat(code_width - display_width(&line.code) + 1);
+            match (diagram.is_empty(), &line.note) {
+                (true, None) => out.push_str(&line.code),
+                (true, Some(note)) => {
+                    out.push_str(&format!("{}{}// {}", line.code, padding, note));
+                }
+                (false, None) => {
+                    out.push_str(&format!("{}{}// {}", line.code, padding, diagram));
+                }
+                (false, Some(note)) => out.push_str(&format!(
+                    "{}{}// {:w$}   // {}",
+                    line.code,
+                    padding,
+                    diagram,
+                    note,
+                    w = diagram_width
+                )),
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    // 各区間を何列目に描くか決める
+    // 自分が囲んでいる区間の列 + 1 に置くので、短命な区間ほど左に来る
+    fn columns(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.regions.len()).collect();
+        order.sort_by(|&a, &b| self.regions[b].start.cmp(&self.regions[a].start));
+        let mut columns = vec![0; self.regions.len()];
+        for &a in &order {
+            let outer = &self.regions[a];
+            columns[a] = self
+                .regions
+                .iter()
+                .enumerate()
+                .filter(|&(b, inner)| b != a && outer.start < inner.start && inner.end <= outer.end)
+                .map(|(b, _)| columns[b] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+        columns
+    }
+}
+
+// 文字列リテラルの外にある最初の `//` でコードとコメントに分ける
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let bytes = line.as_bytes();
+    let mut in_string = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if in_string => i += 1,
+            b'"' => in_string = !in_string,
+            b'/' if !in_string && bytes.get(i + 1) == Some(&b'/') => {
+                return (&line[..i], Some(&line[i + 2..]));
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    (line, None)
+}
+
+// コメントを 図の部分 と 後ろに続く普通のコメント に分ける
+// 図として読めなければ (None, 全体) になる
+fn split_note(comment: &str) -> (Option<&str>, Option<String>) {
+    let (head, tail) = match comment.find("//") {
+        Some(at) => (&comment[..at], Some(comment[at + 2..].trim().to_string())),
+        None => (comment, None),
+    };
+    if is_diagram(head) {
+        (Some(head), tail)
+    } else {
+        (None, Some(comment.trim().to_string()))
+    }
+}
+
+// `-`, `+`, `|`, 空白 と高々1つのライフタイム名だけでできているか
+fn is_diagram(s: &str) -> bool {
+    let mut labels = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '-' | '+' | '|' | ' ' => {}
+            '\'' => {
+                let mut len = 0;
+                while chars
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    chars.next();
+                    len += 1;
+                }
+                labels += 1;
+                if len == 0 || labels > 1 {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn diagram_label(s: &str) -> Option<String> {
+    let at = s.find('\'')?;
+    let label: String = s[at..]
+        .chars()
+        .enumerate()
+        .take_while(|&(k, c)| k == 0 || c.is_ascii_alphanumeric() || c == '_')
+        .map(|(_, c)| c)
+        .collect();
+    Some(label)
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Ident(String),
+    Other,
+}
+
+// 文字列, 文字リテラルの外の 波括弧 と 識別子 を拾う
+fn tokenize(code: &str) -> Vec<Token> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            tokens.push(Token::Other);
+        } else if c == '\'' && chars.get(i + 2) == Some(&'\'') {
+            i += 2; // 'x' のような文字リテラル
+            tokens.push(Token::Other);
+        } else if c == '{' {
+            tokens.push(Token::Open);
+        } else if c == '}' {
+            tokens.push(Token::Close);
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i + 1 < chars.len() && (chars[i + 1].is_alphanumeric() || chars[i + 1] == '_') {
+                i += 1;
+            }
+            let id: String = chars[start..=i].iter().collect();
+            // ライフタイム('a)は識別子に数えない
+            if start > 0 && chars[start - 1] == '\'' {
+                tokens.push(Token::Other);
+            } else {
+                tokens.push(Token::Ident(id));
+            }
+        } else if !c.is_whitespace() && c != '&' && c != '=' {
+            tokens.push(Token::Other);
+        }
+        i += 1;
+    }
+    tokens
+}
+
+// `= &x` や `= &mut x` の x
+fn borrowed_name(code: &str) -> Option<&str> {
+    let at = code.find('=')?;
+    let rest = code[at + 1..].trim_start().strip_prefix('&')?;
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix("mut ").unwrap_or(rest).trim_start();
+    leading_ident(rest)
+}
+
+// `r = ...` の r (`==` は除く)
+fn assigned_name(code: &str) -> Option<&str> {
+    let code = code.trim_start();
+    let name = leading_ident(code)?;
+    let rest = code[name.len()..].trim_start();
+    if rest.starts_with('=') && !rest.starts_with("==") {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+fn leading_ident(s: &str) -> Option<&str> {
+    let end = s
+        .char_indices()
+        .find(|&(_, c)| !(c.is_alphanumeric() || c == '_'))
+        .map_or(s.len(), |(at, _)| at);
+    if end == 0 {
+        None
+    } else {
+        Some(&s[..end])
+    }
+}
+
+// その行で生きている(一番新しい)同名の束縛
+fn live(regions: &[Region], name: &str, line: usize) -> Option<usize> {
+    regions
+        .iter()
+        .rposition(|r| r.name == name && r.start <= line && r.end >= line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DANGLING: &str = "\
+let r;         // 'a
+{
+    let x = 5; // 'b
+    r = &x;
+}
+println!(\"{}\", r);
+";
+
+    #[test]
+    fn draws_notebook_diagram() {
+        let expected = "\
+let r;             // --------+- 'a
+{                  //         |
+    let x = 5;     // -+- 'b  |
+    r = &x;        //  |      |
+}                  // -+      |
+println!(\"{}\", r); //         |
+";
+        assert_eq!(render(DANGLING).unwrap(), expected);
+    }
+
+    #[test]
+    fn outer_closing_brace_ends_every_region() {
+        let src = "{\n    let x = 5;\n    let r = &x;\n}\n";
+        let expected = "\
+{
+    let x = 5;  // --------+- 'a
+    let r = &x; // -+- 'b  |
+}               // -+------+
+";
+        assert_eq!(render(src).unwrap(), expected);
+    }
+
+    #[test]
+    fn regions_follow_scopes() {
+        let d = parse(DANGLING).unwrap();
+        let r = &d.regions()[0];
+        let x = &d.regions()[1];
+        assert_eq!(
+            (r.name.as_str(), r.label.as_str(), r.start, r.end, r.closed),
+            ("r", "'a", 0, 5, false)
+        );
+        assert_eq!(
+            (x.name.as_str(), x.label.as_str(), x.start, x.end, x.closed),
+            ("x", "'b", 2, 4, true)
+        );
+    }
+
+    #[test]
+    fn finds_dangling_reference() {
+        let d = parse(DANGLING).unwrap();
+        assert_eq!(
+            d.dangling(),
+            vec![Dangling {
+                holder: String::from("r"),
+                referent: String::from("x"),
+                borrow_line: 3,
+                use_line: 5,
+            }]
+        );
+
+        let fixed = "let x = 5;\nlet r = &x;\nprintln!(\"r: {}\", r);\n";
+        assert!(parse(fixed).unwrap().dangling().is_empty());
+    }
+
+    #[test]
+    fn keeps_notes_and_labels_when_redrawn() {
+        let src = "let x = 5; // 'b // 'b誕生\nlet r = &x; // 'a\n";
+        let rendered = render(src).unwrap();
+        assert_eq!(
+            rendered,
+            "let x = 5;  // --------+- 'b   // 'b誕生\nlet r = &x; // -+- 'a  |\n"
+        );
+        // 描いた図をもう一度読ませても変わらない
+        assert_eq!(render(&rendered).unwrap(), rendered);
+        assert!(check(&rendered).is_ok());
+    }
+
+    #[test]
+    fn check_reports_stale_line() {
+        let stale = "let x = 5;  // ----+- 'b\nlet r = &x; // -+- 'a  |\n";
+        match check(stale) {
+            Err(CheckError::Mismatch { line, .. }) => assert_eq!(line, 0),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn duplicate_label_is_an_error() {
+        let src = "let x = 5; // 'a\nlet y = 6; // 'a\n";
+        assert_eq!(
+            parse(src).unwrap_err(),
+            DiagramError::DuplicateLabel {
+                label: String::from("'a"),
+                line: 1
+            }
+        );
+    }
+
+    #[test]
+    fn notebook_diagrams_are_up_to_date() {
+        let notebook = include_str!("others/lifetime.rs");
+        let lines: Vec<&str> = notebook.lines().collect();
+        let blocks = find_blocks(notebook);
+        assert!(!blocks.is_empty());
+        for block in blocks {
+            let text = lines[block.clone()].join("\n");
+            if let Err(e) = check(&text) {
+                panic!("lifetime.rs {}行目からの図: {}", block.start + 1, e);
+            }
+        }
+    }
+}
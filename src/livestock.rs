@@ -0,0 +1,590 @@
+// 家畜台帳
+// ./others/structure.rs の Kachik構造体(種族, 売値, 年齢, 生死)を使って、
+// 家畜の登録, 検索, 更新, 売値の履歴, 加齢, 死亡の記録 をする
+// CSV での読み書きもできる(書式は to_csv の説明参照)
+
+use crate::money::{Currency, Money, MoneyError};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// 家畜1頭(structure.rs の Kachik と同じフィールド)
+/// 売値の単位は元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kachik {
+    pub species: String,
+    pub price: u64,
+    pub age: u32,
+    pub is_alive: bool,
+}
+
+impl Kachik {
+    /// 生きている家畜を作る(structure.rs の build_kachik2 と同じくフィールド初期化省略記法)
+    pub fn new(species: &str, price: u64, age: u32) -> Kachik {
+        Kachik {
+            species: String::from(species),
+            price,
+            age,
+            is_alive: true,
+        }
+    }
+
+    /// 売値を通貨付きの金額(元)にする
+    pub fn price_in_yuan(&self) -> Result<Money, MoneyError> {
+        if self.price > i64::MAX as u64 {
+            return Err(MoneyError::Overflow);
+        }
+        Money::from_major(self.price as i64, Currency::CNY)
+    }
+}
+
+/// 台帳内の通し番号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KachikId(pub u32);
+
+impl fmt::Display for KachikId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// 死亡の記録
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Death {
+    pub age: u32,      // 死亡時の年齢
+    pub cause: String, // 死因
+}
+
+/// 台帳の1行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub id: KachikId,
+    pub name: String, // pochi, mowmow など
+    pub kachik: Kachik,
+    pub price_history: Vec<u64>, // 登録時からの売値の変遷(最後が現在の売値)
+    pub death: Option<Death>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    NotFound(KachikId),
+    AlreadyDead(KachikId),
+    DuplicateName(String),
+    Csv { line: usize, message: String }, // line は1始まり
+    IdsExhausted,                         // 番号を u32::MAX まで使い切った(番号は使い回さない)
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::NotFound(id) => write!(f, "{} は台帳にいません", id),
+            RegistryError::AlreadyDead(id) => write!(f, "{} は既に死んでいます", id),
+            RegistryError::DuplicateName(name) => {
+                write!(f, "{} という名前は既に使われています", name)
+            }
+            RegistryError::Csv { line, message } => write!(f, "CSV {}行目: {}", line, message),
+            RegistryError::IdsExhausted => {
+                write!(f, "番号を {} まで使い切ったので登録できません", u32::MAX)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// 家畜台帳
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    records: Vec<Record>, // id の昇順
+    next_id: u32,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// 登録して番号を返す。名前は台帳内で一意
+    pub fn add(&mut self, name: &str, kachik: Kachik) -> Result<KachikId, RegistryError> {
+        if self.find(name).is_some() {
+            return Err(RegistryError::DuplicateName(String::from(name)));
+        }
+        self.next_id = self
+            .next_id
+            .checked_add(1)
+            .ok_or(RegistryError::IdsExhausted)?;
+        let id = KachikId(self.next_id);
+        let death = if kachik.is_alive {
+            None
+        } else {
+            Some(Death {
+                age: kachik.age,
+                cause: String::new(),
+            })
+        };
+        self.records.push(Record {
+            id,
+            name: String::from(name),
+            price_history: vec![kachik.price],
+            kachik,
+            death,
+        });
+        Ok(id)
+    }
+
+    pub fn get(&self, id: KachikId) -> Option<&Record> {
+        self.index(id).map(|i| &self.records[i])
+    }
+
+    /// 名前で探す
+    pub fn find(&self, name: &str) -> Option<&Record> {
+        self.records.iter().find(|r| r.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Record> {
+        self.records.iter()
+    }
+
+    /// 種族で絞り込む
+    pub fn by_species<'a>(&'a self, species: &'a str) -> impl Iterator<Item = &'a Record> + 'a {
+        self.records
+            .iter()
+            .filter(move |r| r.kachik.species == species)
+    }
+
+    /// 生死で絞り込む
+    pub fn by_alive(&self, is_alive: bool) -> impl Iterator<Item = &Record> {
+        self.records
+            .iter()
+            .filter(move |r| r.kachik.is_alive == is_alive)
+    }
+
+    /// 家畜を書き換える
+    /// 構造体更新記法で一部だけ変えた Kachik を返すクロージャを渡す想定
+    ///     registry.update(id, |k| Kachik { age: 4, ..k })
+    /// 売値が変われば履歴に残り、生死は変えられない(死亡は record_death で記録する)
+    pub fn update<F>(&mut self, id: KachikId, f: F) -> Result<&Record, RegistryError>
+    where
+        F: FnOnce(Kachik) -> Kachik,
+    {
+        let i = self.index(id).ok_or(RegistryError::NotFound(id))?;
+        let record = &mut self.records[i];
+        let is_alive = record.kachik.is_alive;
+        let mut updated = f(record.kachik.clone());
+        updated.is_alive = is_alive;
+        if updated.price != record.kachik.price {
+            record.price_history.push(updated.price);
+        }
+        record.kachik = updated;
+        Ok(&self.records[i])
+    }
+
+    /// 売値を変える(pochi: 140_000元 -> 67_000_000元)
+    pub fn set_price(&mut self, id: KachikId, price: u64) -> Result<&Record, RegistryError> {
+        self.update(id, |k| Kachik { price, ..k })
+    }
+
+    /// 生きている全頭を years 歳 歳を取らせる(u32 の上限で止まる)
+    pub fn age_all(&mut self, years: u32) {
+        for r in self.records.iter_mut().filter(|r| r.kachik.is_alive) {
+            r.kachik.age = r.kachik.age.saturating_add(years);
+        }
+    }
+
+    /// 死亡を記録する
+    pub fn record_death(&mut self, id: KachikId, cause: &str) -> Result<&Record, RegistryError> {
+        let i = self.index(id).ok_or(RegistryError::NotFound(id))?;
+        let record = &mut self.records[i];
+        if !record.kachik.is_alive {
+            return Err(RegistryError::AlreadyDead(id));
+        }
+        record.kachik.is_alive = false;
+        record.death = Some(Death {
+            age: record.kachik.age,
+            cause: String::from(cause),
+        });
+        Ok(&self.records[i])
+    }
+
+    fn index(&self, id: KachikId) -> Option<usize> {
+        self.records.binary_search_by_key(&id, |r| r.id).ok()
+    }
+
+    /// CSV に書き出す
+    /// 1行目は見出しで、列は id,name,species,price,age,is_alive,price_history,death_cause
+    /// price_history は売値を `;` で繋いだもの、death_cause は生きていれば空
+    /// カンマ, ダブルクォート, 改行を含む値はダブルクォートで囲み、中の `"` は `""` にする
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(HEADER);
+        out.push('\n');
+        for r in &self.records {
+            let history: Vec<String> = r.price_history.iter().map(u64::to_string).collect();
+            let fields = [
+                r.id.0.to_string(),
+                r.name.clone(),
+                r.kachik.species.clone(),
+                r.kachik.price.to_string(),
+                r.kachik.age.to_string(),
+                r.kachik.is_alive.to_string(),
+                history.join(";"),
+                r.death
+                    .as_ref()
+                    .map(|d| d.cause.clone())
+                    .unwrap_or_default(),
+            ];
+            let quoted: Vec<String> = fields.iter().map(|f| quote(f)).collect();
+            out.push_str(&quoted.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// to_csv の書式を読み込む
+    pub fn from_csv(src: &str) -> Result<Registry, RegistryError> {
+        let mut rows = parse_csv(src)?.into_iter();
+        match rows.next() {
+            Some((_, header)) if header.join(",") == HEADER => {}
+            _ => {
+                return Err(RegistryError::Csv {
+                    line: 1,
+                    message: format!("見出しは {} でなければなりません", HEADER),
+                })
+            }
+        }
+        let mut registry = Registry::new();
+        for (line, row) in rows {
+            let err = |message: String| RegistryError::Csv { line, message };
+            if row.len() != 8 {
+                return Err(err(format!("列が8つではなく{}つあります", row.len())));
+            }
+            let number = |col: usize| -> Result<u64, RegistryError> {
+                row[col].parse().map_err(|_| {
+                    err(format!(
+                        "{}列目 `{}` は数値ではありません",
+                        col + 1,
+                        row[col]
+                    ))
+                })
+            };
+            // as u32 で切り詰めると 4294967297 が #1 になって他の行とぶつかるので範囲を確かめる
+            let small = |col: usize| -> Result<u32, RegistryError> {
+                u32::try_from(number(col)?).map_err(|_| {
+                    err(format!(
+                        "{}列目 `{}` は {} を超えています",
+                        col + 1,
+                        row[col],
+                        u32::MAX
+                    ))
+                })
+            };
+            let id = KachikId(small(0)?);
+            let price = number(3)?;
+            let age = small(4)?;
+            let is_alive = match row[5].as_str() {
+                "true" => true,
+                "false" => false,
+                other => return Err(err(format!("6列目 `{}` は true か false です", other))),
+            };
+            let mut price_history = Vec::new();
+            for p in row[6].split(';').filter(|p| !p.is_empty()) {
+                price_history.push(
+                    p.parse()
+                        .map_err(|_| err(format!("売値の履歴 `{}` は数値ではありません", p)))?,
+                );
+            }
+            if price_history.last() != Some(&price) {
+                price_history.push(price);
+            }
+            if id.0 <= registry.next_id {
+                return Err(err(format!(
+                    "番号 {} が重複しているか昇順ではありません",
+                    id
+                )));
+            }
+            if registry.find(&row[1]).is_some() {
+                return Err(err(format!("名前 {} が重複しています", row[1])));
+            }
+            registry.next_id = id.0;
+            registry.records.push(Record {
+                id,
+                name: row[1].clone(),
+                kachik: Kachik {
+                    species: row[2].clone(),
+                    price,
+                    age,
+                    is_alive,
+                },
+                price_history,
+                death: if is_alive {
+                    None
+                } else {
+                    Some(Death {
+                        age,
+                        cause: row[7].clone(),
+                    })
+                },
+            });
+        }
+        Ok(registry)
+    }
+}
+
+const HEADER: &str = "id,name,species,price,age,is_alive,price_history,death_cause";
+
+fn quote(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+// 手書きの CSV 読み込み
+// (行番号, フィールド) の並びを返す。行番号はその行が始まった行(1始まり)
+// ダブルクォートで囲まれたフィールドには `,` や改行を含められて、`""` は `"` 1つになる
+fn parse_csv(src: &str) -> Result<Vec<(usize, Vec<String>)>, RegistryError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut in_quotes = false;
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push((row_line, std::mem::take(&mut row)));
+                line += 1;
+                row_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(RegistryError::Csv {
+            line: row_line,
+            message: String::from("ダブルクォートが閉じていません"),
+        });
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn farm() -> (Registry, KachikId, KachikId) {
+        let mut r = Registry::new();
+        let pochi = r.add("pochi", Kachik::new("dog", 140_000, 3)).unwrap();
+        let mowmow = r.add("mowmow", Kachik::new("cow", 6_390_000, 9)).unwrap();
+        (r, pochi, mowmow)
+    }
+
+    #[test]
+    fn pochi_price_history() {
+        let (mut r, pochi, _) = farm();
+        r.set_price(pochi, 67_000_000).unwrap();
+        let rec = r.find("pochi").unwrap();
+        assert_eq!(rec.kachik.price, 67_000_000);
+        assert_eq!(rec.price_history, vec![140_000, 67_000_000]);
+        assert_eq!(
+            rec.kachik.price_in_yuan().unwrap().to_string(),
+            "CN¥67,000,000.00"
+        );
+        // 同じ値段を付け直しても履歴は増えない
+        r.set_price(pochi, 67_000_000).unwrap();
+        assert_eq!(r.get(pochi).unwrap().price_history.len(), 2);
+    }
+
+    #[test]
+    fn ageing_and_death() {
+        let (mut r, pochi, mowmow) = farm();
+        r.record_death(mowmow, "老衰").unwrap();
+        r.age_all(2);
+        assert_eq!(r.get(pochi).unwrap().kachik.age, 5);
+        let cow = r.get(mowmow).unwrap();
+        assert_eq!(cow.kachik.age, 9); // 死んだら歳は取らない
+        assert_eq!(
+            cow.death,
+            Some(Death {
+                age: 9,
+                cause: String::from("老衰")
+            })
+        );
+        assert_eq!(
+            r.record_death(mowmow, "?").unwrap_err(),
+            RegistryError::AlreadyDead(mowmow)
+        );
+        assert_eq!(
+            r.record_death(KachikId(99), "?").unwrap_err(),
+            RegistryError::NotFound(KachikId(99))
+        );
+    }
+
+    #[test]
+    fn update_cannot_resurrect() {
+        let (mut r, _, mowmow) = farm();
+        r.record_death(mowmow, "老衰").unwrap();
+        let rec = r
+            .update(mowmow, |k| Kachik {
+                is_alive: true,
+                age: 10,
+                ..k
+            })
+            .unwrap();
+        assert!(!rec.kachik.is_alive);
+        assert_eq!(rec.kachik.age, 10);
+    }
+
+    #[test]
+    fn filters() {
+        let (mut r, _, mowmow) = farm();
+        r.add("cowcow", Kachik::new("cow", 3_200_000, 9)).unwrap();
+        r.record_death(mowmow, "").unwrap();
+        let cows: Vec<&str> = r.by_species("cow").map(|r| r.name.as_str()).collect();
+        assert_eq!(cows, ["mowmow", "cowcow"]);
+        let alive: Vec<&str> = r.by_alive(true).map(|r| r.name.as_str()).collect();
+        assert_eq!(alive, ["pochi", "cowcow"]);
+        assert_eq!(r.by_alive(false).count(), 1);
+        assert_eq!(
+            r.add("pochi", Kachik::new("cat", 1, 1)).unwrap_err(),
+            RegistryError::DuplicateName(String::from("pochi"))
+        );
+    }
+
+    #[test]
+    fn struct_update_clones_leave_original_intact() {
+        // structure.rs の cowcow と同じく mowmow の残りのフィールドを使うが、
+        // ..mowmow だと species(String) がムーブされてしまうので clone してから使う
+        let (r, _, mowmow) = farm();
+        let original = r.get(mowmow).unwrap();
+        let cowcow = Kachik {
+            price: 3_200_000,
+            is_alive: false,
+            ..original.kachik.clone()
+        };
+        assert_eq!(cowcow.species, "cow");
+        assert_eq!(cowcow.age, 9);
+        assert_eq!(original.kachik.price, 6_390_000);
+        assert!(original.kachik.is_alive);
+
+        // レコードごと複製しても履歴は別物
+        let mut copy = Record {
+            name: String::from("cowcow"),
+            ..original.clone()
+        };
+        copy.price_history.push(1);
+        copy.kachik.species.push_str("-copy");
+        assert_eq!(original.price_history, vec![6_390_000]);
+        assert_eq!(original.kachik.species, "cow");
+        assert_eq!(copy.id, original.id);
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let (mut r, pochi, mowmow) = farm();
+        r.set_price(pochi, 67_000_000).unwrap();
+        r.add("ちゃ,ちゃ", Kachik::new("cat \"mike\"", 0, 1))
+            .unwrap();
+        r.record_death(mowmow, "老衰\n(大往生)").unwrap();
+        let csv = r.to_csv();
+        assert!(csv.starts_with(HEADER));
+        assert!(csv.contains("1,pochi,dog,67000000,3,true,140000;67000000,\n"));
+
+        let back = Registry::from_csv(&csv).unwrap();
+        assert_eq!(
+            back.iter().collect::<Vec<_>>(),
+            r.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(back.to_csv(), csv);
+
+        // 読み込んだ後の番号は続きから
+        let mut back = back;
+        assert_eq!(
+            back.add("tama", Kachik::new("cat", 1, 1)).unwrap(),
+            KachikId(4)
+        );
+    }
+
+    #[test]
+    fn csv_errors_report_lines() {
+        let bad = format!("{}\n1,pochi,dog,abc,3,true,,\n", HEADER);
+        match Registry::from_csv(&bad) {
+            Err(RegistryError::Csv { line, .. }) => assert_eq!(line, 2),
+            other => panic!("{:?}", other),
+        }
+        let unclosed = format!("{}\n1,\"pochi,dog,1,3,true,,\n", HEADER);
+        match Registry::from_csv(&unclosed) {
+            Err(RegistryError::Csv { line, .. }) => assert_eq!(line, 2),
+            other => panic!("{:?}", other),
+        }
+        assert!(Registry::from_csv("name,price\n").is_err());
+    }
+
+    #[test]
+    fn ids_run_out_instead_of_wrapping() {
+        let csv = format!("{}\n4294967295,pochi,dog,1,3,true,,\n", HEADER);
+        let mut r = Registry::from_csv(&csv).unwrap();
+        let before = r.len();
+        assert_eq!(
+            r.add("mowmow", Kachik::new("cow", 2, 1)),
+            Err(RegistryError::IdsExhausted)
+        );
+        assert_eq!(r.len(), before);
+        assert!(r.find("mowmow").is_none());
+    }
+
+    #[test]
+    fn csv_rejects_numbers_too_large_for_u32() {
+        for row in &[
+            "4294967297,pochi,dog,1,3,true,,",
+            "1,pochi,dog,1,4294967296,true,,",
+        ] {
+            match Registry::from_csv(&format!("{}\n{}\n", HEADER, row)) {
+                Err(RegistryError::Csv { line, message }) => {
+                    assert_eq!(line, 2);
+                    assert!(message.contains("4294967295"), "{}", message);
+                }
+                other => panic!("{:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn ageing_stops_at_the_limit() {
+        let (mut r, pochi, _) = farm();
+        r.age_all(u32::MAX);
+        r.age_all(1);
+        assert_eq!(r.get(pochi).unwrap().kachik.age, u32::MAX);
+    }
+}
@@ -0,0 +1,786 @@
+// 自作の Option と Result
+// ./others/enm_mch_iflet.rs と ./others/generics_and_traits.rs でコメントの中にだけ書いた
+// enum Option<T> { Some(T), None } と enum Result<T, E> { Ok(T), Err(E) } を、本当に使える形で作る
+// 名前が std とぶつからないように Maybe(Just / Nothing) と Outcome(Success / Failure) にした
+// ? は自作の型には使えない(Try トレイトが安定していない)ので、同じことをする attempt! マクロを用意した
+
+use std::convert::Infallible;
+use std::iter::FromIterator;
+
+/// 値が有るかもしれない(std の Option)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Maybe<T> {
+    #[default]
+    Nothing,
+    Just(T),
+}
+
+/// 成功か失敗か(std の Result)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Outcome<T, E> {
+    Success(T),
+    Failure(E),
+}
+
+use Maybe::{Just, Nothing};
+use Outcome::{Failure, Success};
+
+impl<T> Maybe<T> {
+    pub fn is_just(&self) -> bool {
+        matches!(self, Just(_))
+    }
+
+    pub fn is_nothing(&self) -> bool {
+        !self.is_just()
+    }
+
+    pub fn is_just_and(self, f: impl FnOnce(T) -> bool) -> bool {
+        match self {
+            Just(x) => f(x),
+            Nothing => false,
+        }
+    }
+
+    pub fn is_nothing_or(self, f: impl FnOnce(T) -> bool) -> bool {
+        match self {
+            Just(x) => f(x),
+            Nothing => true,
+        }
+    }
+
+    pub fn as_ref(&self) -> Maybe<&T> {
+        match self {
+            Just(x) => Just(x),
+            Nothing => Nothing,
+        }
+    }
+
+    pub fn as_mut(&mut self) -> Maybe<&mut T> {
+        match self {
+            Just(x) => Just(x),
+            Nothing => Nothing,
+        }
+    }
+
+    /// Nothing なら msg でパニック
+    pub fn expect(self, msg: &str) -> T {
+        match self {
+            Just(x) => x,
+            Nothing => panic!("{}", msg),
+        }
+    }
+
+    pub fn unwrap(self) -> T {
+        self.expect("Nothing を unwrap しました")
+    }
+
+    pub fn unwrap_or(self, default: T) -> T {
+        match self {
+            Just(x) => x,
+            Nothing => default,
+        }
+    }
+
+    pub fn unwrap_or_else(self, f: impl FnOnce() -> T) -> T {
+        match self {
+            Just(x) => x,
+            Nothing => f(),
+        }
+    }
+
+    pub fn unwrap_or_default(self) -> T
+    where
+        T: Default,
+    {
+        self.unwrap_or_else(T::default)
+    }
+
+    /// plus_one の match はこれ1つで書ける(x.map(|i| i + 1))
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Maybe<U> {
+        match self {
+            Just(x) => Just(f(x)),
+            Nothing => Nothing,
+        }
+    }
+
+    /// Just のときだけ中身を覗く
+    pub fn inspect(self, f: impl FnOnce(&T)) -> Maybe<T> {
+        if let Just(x) = &self {
+            f(x);
+        }
+        self
+    }
+
+    pub fn map_or<U>(self, default: U, f: impl FnOnce(T) -> U) -> U {
+        match self {
+            Just(x) => f(x),
+            Nothing => default,
+        }
+    }
+
+    pub fn map_or_else<U>(self, default: impl FnOnce() -> U, f: impl FnOnce(T) -> U) -> U {
+        match self {
+            Just(x) => f(x),
+            Nothing => default(),
+        }
+    }
+
+    pub fn ok_or<E>(self, err: E) -> Outcome<T, E> {
+        match self {
+            Just(x) => Success(x),
+            Nothing => Failure(err),
+        }
+    }
+
+    pub fn ok_or_else<E>(self, err: impl FnOnce() -> E) -> Outcome<T, E> {
+        match self {
+            Just(x) => Success(x),
+            Nothing => Failure(err()),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.as_ref())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut(self.as_mut())
+    }
+
+    pub fn and<U>(self, other: Maybe<U>) -> Maybe<U> {
+        match self {
+            Just(_) => other,
+            Nothing => Nothing,
+        }
+    }
+
+    pub fn and_then<U>(self, f: impl FnOnce(T) -> Maybe<U>) -> Maybe<U> {
+        match self {
+            Just(x) => f(x),
+            Nothing => Nothing,
+        }
+    }
+
+    pub fn filter(self, predicate: impl FnOnce(&T) -> bool) -> Maybe<T> {
+        match self {
+            Just(x) if predicate(&x) => Just(x),
+            _ => Nothing,
+        }
+    }
+
+    pub fn or(self, other: Maybe<T>) -> Maybe<T> {
+        match self {
+            Just(x) => Just(x),
+            Nothing => other,
+        }
+    }
+
+    pub fn or_else(self, f: impl FnOnce() -> Maybe<T>) -> Maybe<T> {
+        match self {
+            Just(x) => Just(x),
+            Nothing => f(),
+        }
+    }
+
+    /// どちらか片方だけが Just ならそれを
+    pub fn xor(self, other: Maybe<T>) -> Maybe<T> {
+        match (self, other) {
+            (Just(x), Nothing) | (Nothing, Just(x)) => Just(x),
+            _ => Nothing,
+        }
+    }
+
+    /// 中身を value にして、入れた値への参照を返す
+    pub fn insert(&mut self, value: T) -> &mut T {
+        *self = Just(value);
+        match self {
+            Just(x) => x,
+            Nothing => unreachable!(),
+        }
+    }
+
+    pub fn get_or_insert_with(&mut self, f: impl FnOnce() -> T) -> &mut T {
+        if self.is_nothing() {
+            *self = Just(f());
+        }
+        match self {
+            Just(x) => x,
+            Nothing => unreachable!(),
+        }
+    }
+
+    /// 中身を取り出して Nothing を残す
+    pub fn take(&mut self) -> Maybe<T> {
+        std::mem::take(self)
+    }
+
+    /// 中身を value にして、前の中身を返す
+    pub fn replace(&mut self, value: T) -> Maybe<T> {
+        std::mem::replace(self, Just(value))
+    }
+
+    pub fn zip<U>(self, other: Maybe<U>) -> Maybe<(T, U)> {
+        match (self, other) {
+            (Just(a), Just(b)) => Just((a, b)),
+            _ => Nothing,
+        }
+    }
+}
+
+impl<T, U> Maybe<(T, U)> {
+    pub fn unzip(self) -> (Maybe<T>, Maybe<U>) {
+        match self {
+            Just((a, b)) => (Just(a), Just(b)),
+            Nothing => (Nothing, Nothing),
+        }
+    }
+}
+
+impl<T> Maybe<Maybe<T>> {
+    pub fn flatten(self) -> Maybe<T> {
+        self.and_then(|m| m)
+    }
+}
+
+impl<T, E> Maybe<Outcome<T, E>> {
+    /// Just(Failure(e)) は Failure(e) に、Nothing は Success(Nothing) になる
+    pub fn transpose(self) -> Outcome<Maybe<T>, E> {
+        match self {
+            Just(Success(x)) => Success(Just(x)),
+            Just(Failure(e)) => Failure(e),
+            Nothing => Success(Nothing),
+        }
+    }
+}
+
+impl<T: Clone> Maybe<&T> {
+    pub fn cloned(self) -> Maybe<T> {
+        self.map(T::clone)
+    }
+}
+
+impl<T: Copy> Maybe<&T> {
+    pub fn copied(self) -> Maybe<T> {
+        self.map(|x| *x)
+    }
+}
+
+impl<T, E> Outcome<T, E> {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Success(_))
+    }
+
+    pub fn is_failure(&self) -> bool {
+        !self.is_success()
+    }
+
+    pub fn is_success_and(self, f: impl FnOnce(T) -> bool) -> bool {
+        match self {
+            Success(x) => f(x),
+            Failure(_) => false,
+        }
+    }
+
+    pub fn is_failure_and(self, f: impl FnOnce(E) -> bool) -> bool {
+        match self {
+            Success(_) => false,
+            Failure(e) => f(e),
+        }
+    }
+
+    /// std の ok()
+    pub fn success(self) -> Maybe<T> {
+        match self {
+            Success(x) => Just(x),
+            Failure(_) => Nothing,
+        }
+    }
+
+    /// std の err()
+    pub fn failure(self) -> Maybe<E> {
+        match self {
+            Success(_) => Nothing,
+            Failure(e) => Just(e),
+        }
+    }
+
+    pub fn as_ref(&self) -> Outcome<&T, &E> {
+        match self {
+            Success(x) => Success(x),
+            Failure(e) => Failure(e),
+        }
+    }
+
+    pub fn as_mut(&mut self) -> Outcome<&mut T, &mut E> {
+        match self {
+            Success(x) => Success(x),
+            Failure(e) => Failure(e),
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Outcome<U, E> {
+        match self {
+            Success(x) => Success(f(x)),
+            Failure(e) => Failure(e),
+        }
+    }
+
+    pub fn map_or<U>(self, default: U, f: impl FnOnce(T) -> U) -> U {
+        match self {
+            Success(x) => f(x),
+            Failure(_) => default,
+        }
+    }
+
+    pub fn map_or_else<U>(self, default: impl FnOnce(E) -> U, f: impl FnOnce(T) -> U) -> U {
+        match self {
+            Success(x) => f(x),
+            Failure(e) => default(e),
+        }
+    }
+
+    pub fn map_err<F>(self, f: impl FnOnce(E) -> F) -> Outcome<T, F> {
+        match self {
+            Success(x) => Success(x),
+            Failure(e) => Failure(f(e)),
+        }
+    }
+
+    pub fn inspect(self, f: impl FnOnce(&T)) -> Outcome<T, E> {
+        if let Success(x) = &self {
+            f(x);
+        }
+        self
+    }
+
+    pub fn inspect_err(self, f: impl FnOnce(&E)) -> Outcome<T, E> {
+        if let Failure(e) = &self {
+            f(e);
+        }
+        self
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.as_ref().success())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut(self.as_mut().success())
+    }
+
+    pub fn and<U>(self, other: Outcome<U, E>) -> Outcome<U, E> {
+        match self {
+            Success(_) => other,
+            Failure(e) => Failure(e),
+        }
+    }
+
+    pub fn and_then<U>(self, f: impl FnOnce(T) -> Outcome<U, E>) -> Outcome<U, E> {
+        match self {
+            Success(x) => f(x),
+            Failure(e) => Failure(e),
+        }
+    }
+
+    pub fn or<F>(self, other: Outcome<T, F>) -> Outcome<T, F> {
+        match self {
+            Success(x) => Success(x),
+            Failure(_) => other,
+        }
+    }
+
+    pub fn or_else<F>(self, f: impl FnOnce(E) -> Outcome<T, F>) -> Outcome<T, F> {
+        match self {
+            Success(x) => Success(x),
+            Failure(e) => f(e),
+        }
+    }
+
+    pub fn unwrap_or(self, default: T) -> T {
+        match self {
+            Success(x) => x,
+            Failure(_) => default,
+        }
+    }
+
+    pub fn unwrap_or_else(self, f: impl FnOnce(E) -> T) -> T {
+        match self {
+            Success(x) => x,
+            Failure(e) => f(e),
+        }
+    }
+
+    pub fn unwrap_or_default(self) -> T
+    where
+        T: Default,
+    {
+        self.unwrap_or_else(|_| T::default())
+    }
+}
+
+impl<T, E: std::fmt::Debug> Outcome<T, E> {
+    /// Failure なら msg と中身でパニック
+    pub fn expect(self, msg: &str) -> T {
+        match self {
+            Success(x) => x,
+            Failure(e) => panic!("{}: {:?}", msg, e),
+        }
+    }
+
+    pub fn unwrap(self) -> T {
+        self.expect("Failure を unwrap しました")
+    }
+}
+
+impl<T: std::fmt::Debug, E> Outcome<T, E> {
+    pub fn expect_err(self, msg: &str) -> E {
+        match self {
+            Success(x) => panic!("{}: {:?}", msg, x),
+            Failure(e) => e,
+        }
+    }
+
+    pub fn unwrap_err(self) -> E {
+        self.expect_err("Success を unwrap_err しました")
+    }
+}
+
+impl<T, E> Outcome<Maybe<T>, E> {
+    /// Success(Nothing) は Nothing に、Failure(e) は Just(Failure(e)) になる
+    pub fn transpose(self) -> Maybe<Outcome<T, E>> {
+        match self {
+            Success(Just(x)) => Just(Success(x)),
+            Success(Nothing) => Nothing,
+            Failure(e) => Just(Failure(e)),
+        }
+    }
+}
+
+/// Maybe と Outcome の中身を借りる反復子(0個か1個)
+#[derive(Debug, Clone)]
+pub struct Iter<'a, T>(Maybe<&'a T>);
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.take().into()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.0.is_just() as usize;
+        (n, Some(n))
+    }
+}
+
+#[derive(Debug)]
+pub struct IterMut<'a, T>(Maybe<&'a mut T>);
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.0.take().into()
+    }
+}
+
+/// 中身を取り出す反復子(0個か1個)
+#[derive(Debug, Clone)]
+pub struct IntoIter<T>(Maybe<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.take().into()
+    }
+}
+
+impl<T> IntoIterator for Maybe<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Maybe<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, E> IntoIterator for Outcome<T, E> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self.success())
+    }
+}
+
+/// 1つでも Nothing があれば Nothing
+impl<T, C: FromIterator<T>> FromIterator<Maybe<T>> for Maybe<C> {
+    fn from_iter<I: IntoIterator<Item = Maybe<T>>>(iter: I) -> Maybe<C> {
+        let mut missing = false;
+        let collected = iter
+            .into_iter()
+            .map_while(|m| match m {
+                Just(x) => Some(x),
+                Nothing => {
+                    missing = true;
+                    None
+                }
+            })
+            .collect();
+        if missing {
+            Nothing
+        } else {
+            Just(collected)
+        }
+    }
+}
+
+/// 最初の Failure で止まってそれを返す
+impl<T, E, C: FromIterator<T>> FromIterator<Outcome<T, E>> for Outcome<C, E> {
+    fn from_iter<I: IntoIterator<Item = Outcome<T, E>>>(iter: I) -> Outcome<C, E> {
+        let mut failure = None;
+        let collected = iter
+            .into_iter()
+            .map_while(|o| match o {
+                Success(x) => Some(x),
+                Failure(e) => {
+                    failure = Some(e);
+                    None
+                }
+            })
+            .collect();
+        match failure {
+            Some(e) => Failure(e),
+            None => Success(collected),
+        }
+    }
+}
+
+impl<T> From<T> for Maybe<T> {
+    fn from(x: T) -> Maybe<T> {
+        Just(x)
+    }
+}
+
+impl<T> From<Option<T>> for Maybe<T> {
+    fn from(o: Option<T>) -> Maybe<T> {
+        match o {
+            Some(x) => Just(x),
+            None => Nothing,
+        }
+    }
+}
+
+impl<T> From<Maybe<T>> for Option<T> {
+    fn from(m: Maybe<T>) -> Option<T> {
+        match m {
+            Just(x) => Some(x),
+            Nothing => None,
+        }
+    }
+}
+
+impl<T, E> From<Result<T, E>> for Outcome<T, E> {
+    fn from(r: Result<T, E>) -> Outcome<T, E> {
+        match r {
+            Ok(x) => Success(x),
+            Err(e) => Failure(e),
+        }
+    }
+}
+
+impl<T, E> From<Outcome<T, E>> for Result<T, E> {
+    fn from(o: Outcome<T, E>) -> Result<T, E> {
+        match o {
+            Success(x) => Ok(x),
+            Failure(e) => Err(e),
+        }
+    }
+}
+
+/// ? の前半: 続けるか(Ok)、ここで返すか(Err)を決める
+/// Residual は返すときに持っていくもの(Maybe なら Nothing, Outcome なら Failure)
+pub trait Branch {
+    type Output;
+    type Residual;
+    fn branch(self) -> Result<Self::Output, Self::Residual>;
+}
+
+/// ? の後半: 持ってきた Residual から関数の戻り値を作る
+pub trait FromResidual<R> {
+    fn from_residual(residual: R) -> Self;
+}
+
+impl<T> Branch for Maybe<T> {
+    type Output = T;
+    type Residual = Maybe<Infallible>;
+
+    fn branch(self) -> Result<T, Maybe<Infallible>> {
+        match self {
+            Just(x) => Ok(x),
+            Nothing => Err(Nothing),
+        }
+    }
+}
+
+impl<T> FromResidual<Maybe<Infallible>> for Maybe<T> {
+    fn from_residual(_: Maybe<Infallible>) -> Maybe<T> {
+        Nothing
+    }
+}
+
+impl<T, E> Branch for Outcome<T, E> {
+    type Output = T;
+    type Residual = Outcome<Infallible, E>;
+
+    fn branch(self) -> Result<T, Outcome<Infallible, E>> {
+        match self {
+            Success(x) => Ok(x),
+            Failure(e) => Err(Failure(e)),
+        }
+    }
+}
+
+/// std の ? と同じく、エラーの型は From で変換する
+impl<T, E, F: From<E>> FromResidual<Outcome<Infallible, E>> for Outcome<T, F> {
+    fn from_residual(residual: Outcome<Infallible, E>) -> Outcome<T, F> {
+        match residual {
+            Failure(e) => Failure(F::from(e)),
+            Success(never) => match never {},
+        }
+    }
+}
+
+/// 自作の型のための ?
+/// attempt!(m) は m が Just(x) / Success(x) なら x になり、そうでなければ関数から Nothing / Failure を返す
+#[macro_export]
+macro_rules! attempt {
+    ($e:expr) => {
+        match $crate::maybe::Branch::branch($e) {
+            ::std::result::Result::Ok(x) => x,
+            ::std::result::Result::Err(residual) => {
+                return $crate::maybe::FromResidual::from_residual(residual)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // enm_mch_iflet.rs の plus_one を Maybe で
+    fn plus_one(x: Maybe<i32>) -> Maybe<i32> {
+        match x {
+            Nothing => Nothing,
+            Just(i) => Just(i + 1),
+        }
+    }
+
+    #[test]
+    fn plus_one_by_hand_and_by_map() {
+        assert_eq!(plus_one(Just(5)), Just(6));
+        assert_eq!(plus_one(Nothing), Nothing);
+        assert_eq!(Just(5).map(|i| i + 1), plus_one(Just(5)));
+    }
+
+    #[test]
+    fn combinators() {
+        let five: Maybe<i32> = Just(5);
+        assert_eq!(five.filter(|x| x % 2 == 0), Nothing);
+        assert_eq!(five.zip(Just("five")), Just((5, "five")));
+        assert_eq!(Just((1, 'a')).unzip(), (Just(1), Just('a')));
+        assert_eq!(five.xor(Nothing), Just(5));
+        assert_eq!(five.xor(Just(6)), Nothing);
+        assert_eq!(five.ok_or("無い"), Success(5));
+        assert_eq!(Nothing::<i32>.ok_or("無い"), Failure("無い"));
+        assert_eq!(Just(Just(3)).flatten(), Just(3));
+        assert_eq!(Just(Success::<i32, &str>(1)).transpose(), Success(Just(1)));
+        assert_eq!(Success::<Maybe<i32>, &str>(Nothing).transpose(), Nothing);
+        assert_eq!(five.iter().collect::<Vec<_>>(), vec![&5]);
+        assert_eq!(Nothing::<i32>.into_iter().count(), 0);
+
+        let mut m = Nothing;
+        *m.get_or_insert_with(|| 1) += 10;
+        assert_eq!(m, Just(11));
+        assert_eq!(m.replace(2), Just(11));
+        assert_eq!(m.take(), Just(2));
+        assert_eq!(m, Nothing);
+
+        let parsed: Outcome<i32, String> = Success(3);
+        assert_eq!(parsed.map_err(|e| e.len()), Success(3));
+        assert_eq!(
+            Failure::<i32, &str>("x").or_else(|e| Success::<i32, usize>(e.len() as i32)),
+            Success(1)
+        );
+        assert_eq!(Failure::<i32, &str>("x").success(), Nothing);
+        assert_eq!(Failure::<i32, &str>("x").unwrap_or_default(), 0);
+    }
+
+    #[test]
+    fn collecting() {
+        let all: Maybe<Vec<i32>> = vec![Just(1), Just(2)].into_iter().collect();
+        assert_eq!(all, Just(vec![1, 2]));
+        let some: Maybe<Vec<i32>> = vec![Just(1), Nothing, Just(3)].into_iter().collect();
+        assert_eq!(some, Nothing);
+        let first_failure: Outcome<Vec<i32>, &str> = vec![Success(1), Failure("a"), Failure("b")]
+            .into_iter()
+            .collect();
+        assert_eq!(first_failure, Failure("a"));
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum AppError {
+        Parse(String),
+    }
+
+    impl From<&str> for AppError {
+        fn from(s: &str) -> AppError {
+            AppError::Parse(String::from(s))
+        }
+    }
+
+    fn parse_digit(c: char) -> Outcome<u32, &'static str> {
+        c.to_digit(10).ok_or("数字ではない").into()
+    }
+
+    // attempt! で途中で抜ける。エラーは From で AppError になる
+    fn sum_digits(s: &str) -> Outcome<u32, AppError> {
+        let mut total = 0;
+        for c in s.chars() {
+            total += attempt!(parse_digit(c));
+        }
+        Success(total)
+    }
+
+    fn first_even(xs: &[i32]) -> Maybe<i32> {
+        let first: i32 = attempt!(Maybe::from(xs.first().copied()));
+        let doubled = attempt!(Just(first * 2).filter(|x| x % 4 == 0));
+        Just(doubled)
+    }
+
+    #[test]
+    fn attempt_propagates() {
+        assert_eq!(sum_digits("123"), Success(6));
+        assert_eq!(
+            sum_digits("1x3"),
+            Failure(AppError::Parse(String::from("数字ではない")))
+        );
+        assert_eq!(first_even(&[]), Nothing);
+        assert_eq!(first_even(&[1]), Nothing);
+        assert_eq!(first_even(&[2]), Just(4));
+        // std の ? を使いたいときは変換してから
+        let std_side = || -> Option<i32> { Some(Option::<i32>::from(Just(1))? + 1) };
+        assert_eq!(std_side(), Some(2));
+    }
+}
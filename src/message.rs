@@ -0,0 +1,553 @@
+// Message の配送係
+// ./others/enm_mch_iflet.rs の Message(Quit, Move, Write, ChangeColor)を
+// 位置, 文字の入れ物, 色, 動いているか を持つ状態に順番に当てていく
+// 種類ごとに後から処理(ハンドラ)を足せて、取り消し/やり直しと再生用の記録もある
+
+use crate::colour::Rgb;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// enm_mch_iflet.rs と同じ4つの列挙子
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Quit,
+    Move { x: i32, y: i32 }, // その位置へ動く(移動量ではない)
+    Write(String),           // 入れ物の後ろに書き足す
+    ChangeColor(i32, i32, i32),
+}
+
+/// Message の種類(ハンドラの登録に使う)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    Quit,
+    Move,
+    Write,
+    ChangeColor,
+}
+
+impl Message {
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Message::Quit => MessageKind::Quit,
+            Message::Move { .. } => MessageKind::Move,
+            Message::Write(_) => MessageKind::Write,
+            Message::ChangeColor(..) => MessageKind::ChangeColor,
+        }
+    }
+}
+
+/// Message を当てる先の状態
+#[derive(Debug, Clone, PartialEq)]
+pub struct State {
+    pub position: (i32, i32),
+    pub text: String,
+    pub colour: Rgb,
+    pub running: bool,
+}
+
+impl Default for State {
+    // 原点, 空, 黒, 動いている
+    fn default() -> State {
+        State {
+            position: (0, 0),
+            text: String::new(),
+            colour: Rgb::from_u8(0, 0, 0),
+            running: true,
+        }
+    }
+}
+
+impl fmt::Display for State {
+    // (3, 4) #ff0000 "hello" のように(止まっていたら最後に [停止])
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}) {} {:?}",
+            self.position.0,
+            self.position.1,
+            self.colour.to_hex(),
+            self.text
+        )?;
+        if !self.running {
+            write!(f, " [停止]")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DispatchError {
+    Stopped(Message),                              // Quit の後に来た
+    ColourOutOfRange(i32, i32, i32),               // ChangeColor の値が 0〜255 に無い
+    Rejected { message: Message, reason: String }, // ハンドラに断られた
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DispatchError::Stopped(m) => write!(f, "Quit の後なので {:?} は受け付けません", m),
+            DispatchError::ColourOutOfRange(r, g, b) => {
+                write!(f, "色 ({}, {}, {}) は 0〜255 の範囲にありません", r, g, b)
+            }
+            DispatchError::Rejected { message, reason } => {
+                write!(f, "{:?} は断られました: {}", message, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DispatchError {}
+
+/// 再生用の記録の1件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Applied(Message),
+    Undo,
+    Redo,
+}
+
+/// 登録したハンドラの番号(off で外すのに使う)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandlerId(pub usize);
+
+// 組み込みの処理の後に呼ばれる。状態を書き換えてもよいし Err で Message ごと断ってもよい
+type Handler = Box<dyn FnMut(&Message, &mut State) -> Result<(), String>>;
+
+/// Message を状態に当てる係
+///
+/// 1つ当てるたびに前の状態との差分を取っておくので、ハンドラが書き換えた分も含めて取り消せる
+/// 文字の入れ物は変わった後ろの部分だけを取っておく(Write を何万回当てても入れ物を丸ごと写さない)
+pub struct Dispatcher {
+    state: State,
+    handlers: Vec<(HandlerId, MessageKind, Handler)>, // 登録順
+    next_handler: usize,
+    undo: Vec<(Message, Change)>, // 当てた Message と当てる前との差分
+    redo: Vec<(Message, Change)>, // 取り消した Message と取り消す前との差分
+    log: Vec<Event>,
+}
+
+// 状態の片側を覚えておく差分
+// swap すると状態がもう片側になり、差分は元の側を覚える(取り消しにもやり直しにも同じものを使う)
+#[derive(Debug)]
+struct Change {
+    position: (i32, i32),
+    colour: Rgb,
+    running: bool,
+    at: usize,    // text はここまで両側で同じ
+    tail: String, // at から後ろの text
+}
+
+impl Change {
+    fn swap(&mut self, state: &mut State) {
+        std::mem::swap(&mut self.position, &mut state.position);
+        std::mem::swap(&mut self.colour, &mut state.colour);
+        std::mem::swap(&mut self.running, &mut state.running);
+        let tail = state.text.split_off(self.at);
+        state.text.push_str(&self.tail);
+        self.tail = tail;
+    }
+}
+
+impl Default for Dispatcher {
+    fn default() -> Dispatcher {
+        Dispatcher::new()
+    }
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("state", &self.state)
+            .field("handlers", &self.handlers.len())
+            .field("undo", &self.undo.len())
+            .field("redo", &self.redo.len())
+            .field("log", &self.log)
+            .finish()
+    }
+}
+
+impl Dispatcher {
+    pub fn new() -> Dispatcher {
+        Dispatcher::with_state(State::default())
+    }
+
+    pub fn with_state(state: State) -> Dispatcher {
+        Dispatcher {
+            state,
+            handlers: Vec::new(),
+            next_handler: 0,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// kind の Message を当てた後に呼ぶ処理を足す(同じ種類なら登録順に呼ぶ)
+    pub fn on<F>(&mut self, kind: MessageKind, handler: F) -> HandlerId
+    where
+        F: FnMut(&Message, &mut State) -> Result<(), String> + 'static,
+    {
+        let id = HandlerId(self.next_handler);
+        self.next_handler += 1;
+        self.handlers.push((id, kind, Box::new(handler)));
+        id
+    }
+
+    /// ハンドラを外す(外せたら true)
+    pub fn off(&mut self, id: HandlerId) -> bool {
+        let before = self.handlers.len();
+        self.handlers.retain(|(h, _, _)| *h != id);
+        self.handlers.len() != before
+    }
+
+    /// Message を1つ当てる
+    /// 失敗したら状態は当てる前のままで、記録にも残らない
+    pub fn dispatch(&mut self, message: Message) -> Result<&State, DispatchError> {
+        if !self.state.running {
+            return Err(DispatchError::Stopped(message));
+        }
+        let kind = message.kind();
+        let mut change = Change {
+            position: self.state.position,
+            colour: self.state.colour,
+            running: self.state.running,
+            at: self.state.text.len(),
+            tail: String::new(),
+        };
+        // 組み込みの処理は後ろに書き足すだけだが、ハンドラは text のどこを書き換えるか分からないので
+        // その種類のハンドラがあるときだけ元の text を写しておく
+        let before = if self.handlers.iter().any(|(_, k, _)| *k == kind) {
+            Some(self.state.text.clone())
+        } else {
+            None
+        };
+        match &message {
+            Message::Quit => self.state.running = false,
+            Message::Move { x, y } => self.state.position = (*x, *y),
+            Message::Write(s) => self.state.text.push_str(s),
+            &Message::ChangeColor(r, g, b) => self.state.colour = to_rgb(r, g, b)?, // 失敗ならまだ何も変えていない
+        }
+        let mut rejected = None;
+        for (_, k, handler) in self.handlers.iter_mut() {
+            if *k == kind {
+                if let Err(reason) = handler(&message, &mut self.state) {
+                    rejected = Some(reason);
+                    break;
+                }
+            }
+        }
+        if let Some(before) = before {
+            change.at = common_prefix(&before, &self.state.text);
+            change.tail = String::from(&before[change.at..]);
+        }
+        if let Some(reason) = rejected {
+            // 断られたら当てる前に戻す
+            change.swap(&mut self.state);
+            return Err(DispatchError::Rejected { message, reason });
+        }
+        self.undo.push((message.clone(), change));
+        self.redo.clear();
+        self.log.push(Event::Applied(message));
+        Ok(&self.state)
+    }
+
+    /// 並びを先頭から当てていき、最初の失敗で止まる(それまでに当てた分はそのまま)
+    pub fn run<I>(&mut self, messages: I) -> Result<&State, DispatchError>
+    where
+        I: IntoIterator<Item = Message>,
+    {
+        for m in messages {
+            self.dispatch(m)?;
+        }
+        Ok(&self.state)
+    }
+
+    /// 最後に当てた Message を取り消して返す(無ければ None)
+    pub fn undo(&mut self) -> Option<Message> {
+        let (message, mut change) = self.undo.pop()?;
+        change.swap(&mut self.state);
+        self.redo.push((message.clone(), change));
+        self.log.push(Event::Undo);
+        Some(message)
+    }
+
+    /// 最後に取り消した Message をやり直して返す(無ければ None)
+    /// ハンドラは呼び直さず、取り消す前の状態に戻すだけ
+    pub fn redo(&mut self) -> Option<Message> {
+        let (message, mut change) = self.redo.pop()?;
+        change.swap(&mut self.state);
+        self.undo.push((message.clone(), change));
+        self.log.push(Event::Redo);
+        Some(message)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// いま効いている Message(取り消した分は入らない)を古い順に
+    pub fn history(&self) -> impl Iterator<Item = &Message> {
+        self.undo.iter().map(|(m, _)| m)
+    }
+
+    /// 成功した dispatch, undo, redo の記録(古い順)
+    pub fn log(&self) -> &[Event] {
+        &self.log
+    }
+
+    /// 記録を順に当て直す
+    /// 同じハンドラを付けた新しい Dispatcher に log() を流すと同じ状態になる
+    pub fn replay<'a, I>(&mut self, events: I) -> Result<&State, DispatchError>
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        for e in events {
+            match e {
+                Event::Applied(m) => {
+                    self.dispatch(m.clone())?;
+                }
+                Event::Undo => {
+                    self.undo();
+                }
+                Event::Redo => {
+                    self.redo();
+                }
+            }
+        }
+        Ok(&self.state)
+    }
+}
+
+// a と b の先頭から同じ部分のバイト数(どちらでも文字の境目になるところまで)
+fn common_prefix(a: &str, b: &str) -> usize {
+    let mut n = a.bytes().zip(b.bytes()).take_while(|(x, y)| x == y).count();
+    while !(a.is_char_boundary(n) && b.is_char_boundary(n)) {
+        n -= 1;
+    }
+    n
+}
+
+fn to_rgb(r: i32, g: i32, b: i32) -> Result<Rgb, DispatchError> {
+    let byte = |v: i32| u8::try_from(v).map_err(|_| DispatchError::ColourOutOfRange(r, g, b));
+    Ok(Rgb::from_u8(byte(r)?, byte(g)?, byte(b)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn script() -> Vec<Message> {
+        vec![
+            Message::Move { x: 3, y: 4 },
+            Message::Write(String::from("hello")),
+            Message::ChangeColor(255, 0, 0),
+            Message::Write(String::from(", world")),
+            Message::Move { x: -1, y: 7 },
+        ]
+    }
+
+    #[test]
+    fn applies_script_in_order() {
+        let mut d = Dispatcher::new();
+        d.run(script()).unwrap();
+        let s = d.state();
+        assert_eq!(s.position, (-1, 7));
+        assert_eq!(s.text, "hello, world");
+        assert_eq!(s.colour.to_u8(), (255, 0, 0));
+        assert!(s.running);
+        assert_eq!(s.to_string(), "(-1, 7) #ff0000 \"hello, world\"");
+        assert_eq!(d.history().count(), 5);
+    }
+
+    #[test]
+    fn quit_stops_everything_after() {
+        let mut d = Dispatcher::new();
+        let mut msgs = script();
+        msgs.insert(2, Message::Quit);
+        let err = d.run(msgs).unwrap_err();
+        assert_eq!(err, DispatchError::Stopped(Message::ChangeColor(255, 0, 0)));
+        assert_eq!(d.state().text, "hello");
+        assert!(!d.state().running);
+        assert_eq!(d.history().last(), Some(&Message::Quit));
+    }
+
+    #[test]
+    fn bad_colour_leaves_state_alone() {
+        let mut d = Dispatcher::new();
+        d.dispatch(Message::Write(String::from("a"))).unwrap();
+        let before = d.state().clone();
+        assert_eq!(
+            d.dispatch(Message::ChangeColor(0, 256, 0)),
+            Err(DispatchError::ColourOutOfRange(0, 256, 0))
+        );
+        assert_eq!(d.state(), &before);
+        assert_eq!(d.log().len(), 1);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut d = Dispatcher::new();
+        d.run(script()).unwrap();
+        assert_eq!(d.undo(), Some(Message::Move { x: -1, y: 7 }));
+        assert_eq!(d.undo(), Some(Message::Write(String::from(", world"))));
+        assert_eq!(d.state().position, (3, 4));
+        assert_eq!(d.state().text, "hello");
+        assert_eq!(d.redo(), Some(Message::Write(String::from(", world"))));
+        assert_eq!(d.state().text, "hello, world");
+        // 新しく当てるとやり直しは消える
+        d.dispatch(Message::Quit).unwrap();
+        assert!(!d.can_redo());
+        assert_eq!(d.redo(), None);
+        // Quit も取り消せる
+        assert_eq!(d.undo(), Some(Message::Quit));
+        assert!(d.state().running);
+        while d.undo().is_some() {}
+        assert_eq!(d.state(), &State::default());
+        assert_eq!(d.history().count(), 0);
+    }
+
+    #[test]
+    fn handlers_run_in_order_and_can_reject() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut d = Dispatcher::new();
+        let log = Rc::clone(&seen);
+        d.on(MessageKind::Write, move |m, _| {
+            log.borrow_mut().push(format!("1:{:?}", m));
+            Ok(())
+        });
+        let log = Rc::clone(&seen);
+        let second = d.on(MessageKind::Write, move |m, _| {
+            log.borrow_mut().push(format!("2:{:?}", m));
+            Ok(())
+        });
+        // 画面の外には出さない
+        d.on(MessageKind::Move, |_, s| {
+            s.position = (s.position.0.clamp(0, 79), s.position.1.clamp(0, 24));
+            Ok(())
+        });
+        // 悪口は書かせない
+        d.on(MessageKind::Write, |m, _| match m {
+            Message::Write(s) if s.contains("クサイ") => Err(String::from("悪口")),
+            _ => Ok(()),
+        });
+
+        d.run(script()).unwrap();
+        assert_eq!(d.state().position, (0, 7));
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                "1:Write(\"hello\")",
+                "2:Write(\"hello\")",
+                "1:Write(\", world\")",
+                "2:Write(\", world\")",
+            ]
+        );
+
+        assert!(d.off(second));
+        assert!(!d.off(second));
+        let err = d
+            .dispatch(Message::Write(String::from("クサイ")))
+            .unwrap_err();
+        assert!(matches!(err, DispatchError::Rejected { .. }));
+        assert_eq!(d.state().text, "hello, world");
+        assert_eq!(seen.borrow().len(), 5); // 断られても前のハンドラは呼ばれている
+        assert_eq!(seen.borrow()[4], "1:Write(\"クサイ\")");
+
+        // クランプされた分も取り消しで戻る
+        d.dispatch(Message::Move { x: 100, y: 100 }).unwrap();
+        assert_eq!(d.state().position, (79, 24));
+        d.undo();
+        assert_eq!(d.state().position, (0, 7));
+    }
+
+    #[test]
+    fn undo_keeps_only_what_changed() {
+        let mut d = Dispatcher::new();
+        for _ in 0..10_000 {
+            d.dispatch(Message::Write(String::from("あいう"))).unwrap();
+        }
+        // 書き足しただけなら差分に入れ物の中身は入らない
+        assert!(d.undo.iter().all(|(_, c)| c.tail.is_empty()));
+        assert_eq!(d.undo.last().unwrap().1.at, 3 * 9_999 * "あ".len());
+        while d.undo().is_some() {}
+        assert_eq!(d.state().text, "");
+        while d.redo().is_some() {}
+        assert_eq!(d.state().text.len(), 10_000 * "あいう".len());
+    }
+
+    #[test]
+    fn handlers_rewriting_text_are_undone() {
+        let mut d = Dispatcher::new();
+        // 書いた後で「くさい」を伏せ字にする
+        d.on(MessageKind::Write, |_, s| {
+            s.text = s.text.replace("くさい", "○○○");
+            Ok(())
+        });
+        d.on(MessageKind::Write, |m, _| match m {
+            Message::Write(s) if s.is_empty() => Err(String::from("空")),
+            _ => Ok(()),
+        });
+        d.dispatch(Message::Write(String::from("なんかく")))
+            .unwrap();
+        d.dispatch(Message::Write(String::from("さいね"))).unwrap();
+        assert_eq!(d.state().text, "なんか○○○ね");
+        assert_eq!(d.undo.last().unwrap().1.tail, "く");
+        // 断られたら伏せ字にした分も戻る
+        assert!(d.dispatch(Message::Write(String::new())).is_err());
+        assert_eq!(d.state().text, "なんか○○○ね");
+        d.undo();
+        assert_eq!(d.state().text, "なんかく");
+        d.redo();
+        assert_eq!(d.state().text, "なんか○○○ね");
+        d.undo();
+        d.undo();
+        assert_eq!(d.state(), &State::default());
+    }
+
+    #[test]
+    fn replaying_the_log_gives_the_same_state() {
+        let clamp = |_: &Message, s: &mut State| {
+            s.position.0 = s.position.0.max(0);
+            Ok(())
+        };
+        let mut d = Dispatcher::new();
+        d.on(MessageKind::Move, clamp);
+        d.run(script()).unwrap();
+        d.undo();
+        d.undo();
+        d.redo();
+        d.dispatch(Message::ChangeColor(0, 128, 255)).unwrap();
+        let _ = d.dispatch(Message::ChangeColor(-1, 0, 0)); // 失敗は記録されない
+        d.dispatch(Message::Quit).unwrap();
+        assert_eq!(
+            &d.log()[5..],
+            &[
+                Event::Undo,
+                Event::Undo,
+                Event::Redo,
+                Event::Applied(Message::ChangeColor(0, 128, 255)),
+                Event::Applied(Message::Quit),
+            ]
+        );
+
+        let mut again = Dispatcher::new();
+        again.on(MessageKind::Move, clamp);
+        again.replay(d.log()).unwrap();
+        assert_eq!(again.state(), d.state());
+        assert_eq!(again.log(), d.log());
+        assert_eq!(
+            again.history().collect::<Vec<_>>(),
+            d.history().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,687 @@
+// 通貨付きの金額
+// Kachik.price(元) や 札の金額(円) はただの整数で、通貨が違っても足せてしまう
+// Money は 通貨コード(ISO 4217) と 最小単位(円なら1円, ドルなら1セント)の整数 を持ち、
+// 違う通貨どうしの計算は MoneyError::CurrencyMismatch になる
+// 桁あふれも黙って回り込まずに MoneyError::Overflow を返す
+
+use crate::numerals::{self, Style};
+use std::collections::HashMap;
+use std::fmt;
+
+/// 通貨
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+    pub code: &'static str,    // ISO 4217 のコード(JPY など)
+    pub minor_units: u32,      // 小数点以下の桁数(円は0, ドルは2)
+    pub symbol: &'static str,  // 英語圏での記号
+    pub ja_name: &'static str, // 日本語での単位
+}
+
+impl Currency {
+    pub const JPY: Currency = Currency {
+        code: "JPY",
+        minor_units: 0,
+        symbol: "¥",
+        ja_name: "円",
+    };
+    pub const CNY: Currency = Currency {
+        code: "CNY",
+        minor_units: 2,
+        symbol: "CN¥",
+        ja_name: "元",
+    };
+    pub const USD: Currency = Currency {
+        code: "USD",
+        minor_units: 2,
+        symbol: "$",
+        ja_name: "ドル",
+    };
+    pub const EUR: Currency = Currency {
+        code: "EUR",
+        minor_units: 2,
+        symbol: "€",
+        ja_name: "ユーロ",
+    };
+    pub const GBP: Currency = Currency {
+        code: "GBP",
+        minor_units: 2,
+        symbol: "£",
+        ja_name: "ポンド",
+    };
+    pub const KRW: Currency = Currency {
+        code: "KRW",
+        minor_units: 0,
+        symbol: "₩",
+        ja_name: "ウォン",
+    };
+
+    /// 知っている通貨の一覧
+    pub const ALL: [Currency; 6] = [
+        Currency::JPY,
+        Currency::CNY,
+        Currency::USD,
+        Currency::EUR,
+        Currency::GBP,
+        Currency::KRW,
+    ];
+
+    /// コードから通貨を探す(大文字小文字は区別しない)
+    pub fn from_code(code: &str) -> Result<Currency, MoneyError> {
+        Currency::ALL
+            .iter()
+            .find(|c| c.code.eq_ignore_ascii_case(code))
+            .copied()
+            .ok_or_else(|| MoneyError::UnknownCurrency(String::from(code)))
+    }
+
+    // 1主単位あたりの最小単位の数(ドルなら100)
+    fn scale(&self) -> i128 {
+        10i128.pow(self.minor_units)
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.code)
+    }
+}
+
+/// 割り算で端数が出たときの丸め方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    HalfUp,   // 四捨五入(0.5 は 0 から遠い方へ)
+    HalfEven, // 銀行家の丸め(0.5 は偶数の方へ)
+    Down,     // 切り捨て(0 に近い方へ)
+    Up,       // 切り上げ(0 から遠い方へ)
+    Floor,    // 負の無限大の方へ
+    Ceiling,  // 正の無限大の方へ
+}
+
+/// n / d を丸め方 mode で整数にする
+pub fn div_round(n: i128, d: i128, mode: Rounding) -> Option<i128> {
+    if d == 0 {
+        return None;
+    }
+    let q = n.checked_div(d)?;
+    let r = n % d;
+    if r == 0 {
+        return Some(q);
+    }
+    let negative = (n < 0) != (d < 0);
+    let away = if negative { q - 1 } else { q + 1 };
+    let twice = r.unsigned_abs() * 2;
+    let half = twice.cmp(&d.unsigned_abs());
+    Some(match mode {
+        Rounding::Down => q,
+        Rounding::Up => away,
+        Rounding::Floor => {
+            if negative {
+                away
+            } else {
+                q
+            }
+        }
+        Rounding::Ceiling => {
+            if negative {
+                q
+            } else {
+                away
+            }
+        }
+        Rounding::HalfUp => {
+            if half.is_lt() {
+                q
+            } else {
+                away
+            }
+        }
+        Rounding::HalfEven => {
+            if half.is_gt() || (half.is_eq() && q % 2 != 0) {
+                away
+            } else {
+                q
+            }
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoneyError {
+    CurrencyMismatch { left: Currency, right: Currency },
+    Overflow,
+    DivisionByZero,
+    UnknownCurrency(String),
+    NoRate { from: Currency, to: Currency },
+    Parse(String),
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoneyError::CurrencyMismatch { left, right } => {
+                write!(f, "{} と {} は通貨が違うので計算できません", left, right)
+            }
+            MoneyError::Overflow => write!(f, "金額が大きすぎます"),
+            MoneyError::DivisionByZero => write!(f, "0 では割れません"),
+            MoneyError::UnknownCurrency(code) => write!(f, "{} という通貨は知りません", code),
+            MoneyError::NoRate { from, to } => {
+                write!(f, "{} から {} への為替レートがありません", from, to)
+            }
+            MoneyError::Parse(s) => write!(f, "`{}` は金額として読めません", s),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+/// 通貨付きの金額
+/// 中身は最小単位の整数(1234.56ドルなら 123456)なので浮動小数点の誤差は出ない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+    minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    /// 最小単位で作る(Money::from_minor(123456, Currency::USD) は 1234.56ドル)
+    pub fn from_minor(minor: i64, currency: Currency) -> Money {
+        Money { minor, currency }
+    }
+
+    /// 主単位で作る(Money::from_major(67_000_000, Currency::JPY) は 6700万円)
+    pub fn from_major(major: i64, currency: Currency) -> Result<Money, MoneyError> {
+        let minor = (major as i128) * currency.scale();
+        Money::from_i128(minor, currency)
+    }
+
+    pub fn zero(currency: Currency) -> Money {
+        Money::from_minor(0, currency)
+    }
+
+    /// 10進の文字列から作る。`,` と `_` は桁区切りとして無視する
+    /// 通貨の桁数より細かい端数は rounding で丸める
+    ///     Money::parse("1,234.565", Currency::USD, Rounding::HalfEven) は 1234.56ドル
+    pub fn parse(s: &str, currency: Currency, rounding: Rounding) -> Result<Money, MoneyError> {
+        let err = || MoneyError::Parse(String::from(s));
+        let cleaned: String = s.trim().chars().filter(|&c| c != ',' && c != '_').collect();
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.strip_prefix('+').unwrap_or(&cleaned)),
+        };
+        let (int, frac) = match digits.find('.') {
+            Some(at) => (&digits[..at], &digits[at + 1..]),
+            None => (digits, ""),
+        };
+        let is_digits = |p: &str| p.chars().all(|c| c.is_ascii_digit());
+        if (int.is_empty() && frac.is_empty()) || !is_digits(int) || !is_digits(frac) {
+            return Err(err());
+        }
+        if int.len() + frac.len() > 36 {
+            return Err(MoneyError::Overflow);
+        }
+        let mut value: i128 = format!("{}{}", int, frac).parse().map_err(|_| err())?;
+        if negative {
+            value = -value;
+        }
+        // value / 10^frac.len() を 最小単位に直す
+        let minor_units = currency.minor_units as usize;
+        let minor = if frac.len() <= minor_units {
+            value
+                .checked_mul(10i128.pow((minor_units - frac.len()) as u32))
+                .ok_or(MoneyError::Overflow)?
+        } else {
+            let excess = 10i128.pow((frac.len() - minor_units) as u32);
+            div_round(value, excess, rounding).ok_or(MoneyError::Overflow)?
+        };
+        Money::from_i128(minor, currency)
+    }
+
+    pub fn minor(&self) -> i64 {
+        self.minor
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.minor == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.minor < 0
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let minor = self
+            .minor
+            .checked_add(other.minor)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::from_minor(minor, self.currency))
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let minor = self
+            .minor
+            .checked_sub(other.minor)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::from_minor(minor, self.currency))
+    }
+
+    pub fn checked_neg(self) -> Result<Money, MoneyError> {
+        let minor = self.minor.checked_neg().ok_or(MoneyError::Overflow)?;
+        Ok(Money::from_minor(minor, self.currency))
+    }
+
+    /// 整数倍(単価 × 個数)
+    pub fn checked_mul(self, n: i64) -> Result<Money, MoneyError> {
+        let minor = self.minor.checked_mul(n).ok_or(MoneyError::Overflow)?;
+        Ok(Money::from_minor(minor, self.currency))
+    }
+
+    /// num/den 倍して丸める(税率 10% なら mul_ratio(10, 100, ..))
+    pub fn mul_ratio(self, num: i64, den: i64, rounding: Rounding) -> Result<Money, MoneyError> {
+        if den == 0 {
+            return Err(MoneyError::DivisionByZero);
+        }
+        let minor = div_round(self.minor as i128 * num as i128, den as i128, rounding)
+            .ok_or(MoneyError::Overflow)?;
+        Money::from_i128(minor, self.currency)
+    }
+
+    /// n 等分して丸める
+    pub fn div_round(self, n: i64, rounding: Rounding) -> Result<Money, MoneyError> {
+        self.mul_ratio(1, n, rounding)
+    }
+
+    /// 比率で分ける。端数は前の取り分から1最小単位ずつ配るので合計は元の金額と一致する
+    ///     1000円 を [1, 1, 1] で分けると [334, 333, 333]
+    pub fn allocate(self, ratios: &[u32]) -> Result<Vec<Money>, MoneyError> {
+        let total: i128 = ratios.iter().map(|&r| r as i128).sum();
+        if total == 0 {
+            return Err(MoneyError::DivisionByZero);
+        }
+        let whole = self.minor as i128;
+        let mut shares: Vec<i128> = ratios.iter().map(|&r| whole * r as i128 / total).collect();
+        let mut rest = whole - shares.iter().sum::<i128>();
+        let step = rest.signum();
+        for share in shares.iter_mut() {
+            if rest == 0 {
+                break;
+            }
+            *share += step;
+            rest -= step;
+        }
+        Ok(shares
+            .into_iter()
+            .map(|s| Money::from_minor(s as i64, self.currency))
+            .collect())
+    }
+
+    /// 為替レート表を使って別の通貨に両替する
+    pub fn convert<R: ExchangeRates + ?Sized>(
+        self,
+        to: Currency,
+        rates: &R,
+        rounding: Rounding,
+    ) -> Result<Money, MoneyError> {
+        if to == self.currency {
+            return Ok(self);
+        }
+        let rate = rates.rate(self.currency, to).ok_or(MoneyError::NoRate {
+            from: self.currency,
+            to,
+        })?;
+        // minor / 10^from * rate * 10^to
+        let num = (self.minor as i128)
+            .checked_mul(rate.num)
+            .and_then(|n| n.checked_mul(to.scale()))
+            .ok_or(MoneyError::Overflow)?;
+        let den = rate
+            .den
+            .checked_mul(self.currency.scale())
+            .ok_or(MoneyError::Overflow)?;
+        let minor = div_round(num, den, rounding).ok_or(MoneyError::Overflow)?;
+        Money::from_i128(minor, to)
+    }
+
+    /// 地域の書式で表示する
+    ///     Locale::En: ¥67,000,000 / $1,234.56
+    ///     Locale::Ja: 6700万円 / 1億2345万6789円 / 1234.56ドル
+    ///     Locale::Kanji: 六千七百万円 / 千二百三十四点五六ドル
+    ///     Locale::Daiji: 金陸阡漆佰萬円也(証書などに書く形)
+    pub fn format(&self, locale: Locale) -> String {
+        let scale = self.currency.scale() as u128;
+        let abs = (self.minor as i128).unsigned_abs();
+        let (int, frac) = (abs / scale, abs % scale);
+        let sign = if self.minor < 0 { "-" } else { "" };
+        let frac = if self.currency.minor_units == 0 || (locale != Locale::En && frac == 0) {
+            String::new()
+        } else {
+            format!(".{:0w$}", frac, w = self.currency.minor_units as usize)
+        };
+        match locale {
+            Locale::En => format!(
+                "{}{}{}{}",
+                sign,
+                self.currency.symbol,
+                numerals::format_unsigned(int, Style::Thousands),
+                frac
+            ),
+            Locale::Ja => format!(
+                "{}{}{}{}",
+                sign,
+                group_man(int, !frac.is_empty()),
+                frac,
+                self.currency.ja_name
+            ),
+            Locale::Kanji | Locale::Daiji => {
+                let style = if locale == Locale::Kanji {
+                    Style::Kanji
+                } else {
+                    Style::Daiji
+                };
+                let sign = if self.minor < 0 { "マイナス" } else { "" };
+                // 小数部は 点 の後に1桁ずつ
+                let frac: String = frac
+                    .chars()
+                    .map(|c| match c.to_digit(10) {
+                        Some(d) => numerals::format_unsigned(d as u128, style),
+                        None => String::from("点"),
+                    })
+                    .collect();
+                let body = format!(
+                    "{}{}{}{}",
+                    sign,
+                    numerals::format_unsigned(int, style),
+                    frac,
+                    self.currency.ja_name
+                );
+                if locale == Locale::Daiji {
+                    format!("金{}也", body)
+                } else {
+                    body
+                }
+            }
+        }
+    }
+
+    fn same_currency(self, other: Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch {
+                left: self.currency,
+                right: other.currency,
+            })
+        }
+    }
+
+    fn from_i128(minor: i128, currency: Currency) -> Result<Money, MoneyError> {
+        if minor > i64::MAX as i128 || minor < i64::MIN as i128 {
+            return Err(MoneyError::Overflow);
+        }
+        Ok(Money::from_minor(minor as i64, currency))
+    }
+}
+
+/// Display は英語圏の書式(¥67,000,000)
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.format(Locale::En))
+    }
+}
+
+/// 金額の表示書式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En, // 記号 + 3桁区切り
+    Ja, // 万, 億, 兆 の4桁区切り + 日本語の単位
+    Kanji, // 漢数字 + 日本語の単位
+    Daiji, // 金 + 大字 + 日本語の単位 + 也
+}
+
+// 6700万, 1億2345万6789 のように(numerals の Man)
+// keep_low なら小数部が続くので一の位の区切りが0でも書く(6700万0.5)
+fn group_man(n: u128, keep_low: bool) -> String {
+    let mut out = numerals::format_unsigned(n, Style::Man);
+    if keep_low && n >= 10_000 && n.is_multiple_of(10_000) {
+        out.push('0');
+    }
+    out
+}
+
+/// 為替レート
+/// 1 from = num/den to(有理数で持つので逆向きのレートも誤差なく作れる)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    num: i128,
+    den: i128,
+}
+
+impl Rate {
+    pub fn new(num: i128, den: i128) -> Option<Rate> {
+        if num <= 0 || den <= 0 {
+            return None;
+        }
+        Some(Rate { num, den })
+    }
+
+    /// "20.5" のような10進の文字列から作る
+    pub fn parse(s: &str) -> Option<Rate> {
+        let s = s.trim();
+        let (int, frac) = match s.find('.') {
+            Some(at) => (&s[..at], &s[at + 1..]),
+            None => (s, ""),
+        };
+        if int.is_empty() && frac.is_empty() || frac.len() > 18 {
+            return None;
+        }
+        if !(int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())) {
+            return None;
+        }
+        let num = format!("{}{}", int, frac).parse().ok()?;
+        Rate::new(num, 10i128.pow(frac.len() as u32))
+    }
+
+    pub fn inverse(self) -> Rate {
+        Rate {
+            num: self.den,
+            den: self.num,
+        }
+    }
+}
+
+/// 為替レートの提供元
+/// 固定の表でも、どこかから取ってくるものでも、このトレイトを実装すれば convert に渡せる
+pub trait ExchangeRates {
+    /// 1 from が何 to か
+    fn rate(&self, from: Currency, to: Currency) -> Option<Rate>;
+}
+
+/// 手で登録する為替レート表
+/// 逆向きのレートは登録されていなければ逆数を使う
+#[derive(Debug, Clone, Default)]
+pub struct RateTable {
+    rates: HashMap<(&'static str, &'static str), Rate>,
+}
+
+impl RateTable {
+    pub fn new() -> RateTable {
+        RateTable::default()
+    }
+
+    pub fn set(&mut self, from: Currency, to: Currency, rate: Rate) -> &mut RateTable {
+        self.rates.insert((from.code, to.code), rate);
+        self
+    }
+}
+
+impl ExchangeRates for RateTable {
+    fn rate(&self, from: Currency, to: Currency) -> Option<Rate> {
+        self.rates
+            .get(&(from.code, to.code))
+            .copied()
+            .or_else(|| self.rates.get(&(to.code, from.code)).map(|r| r.inverse()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yen(n: i64) -> Money {
+        Money::from_major(n, Currency::JPY).unwrap()
+    }
+
+    #[test]
+    fn same_currency_adds() {
+        assert_eq!(yen(1000).checked_add(yen(5000)), Ok(yen(6000)));
+        assert_eq!(yen(1000).checked_sub(yen(5000)), Ok(yen(-4000)));
+        assert_eq!(yen(2000).checked_mul(3), Ok(yen(6000)));
+    }
+
+    #[test]
+    fn mixing_currencies_is_an_error() {
+        let dollars = Money::from_minor(100, Currency::USD);
+        assert_eq!(
+            yen(100).checked_add(dollars),
+            Err(MoneyError::CurrencyMismatch {
+                left: Currency::JPY,
+                right: Currency::USD
+            })
+        );
+    }
+
+    #[test]
+    fn overflow_is_checked() {
+        let max = Money::from_minor(i64::MAX, Currency::JPY);
+        assert_eq!(max.checked_add(yen(1)), Err(MoneyError::Overflow));
+        assert_eq!(max.checked_mul(2), Err(MoneyError::Overflow));
+        assert_eq!(
+            Money::from_major(i64::MAX, Currency::USD),
+            Err(MoneyError::Overflow)
+        );
+        assert_eq!(
+            Money::from_minor(i64::MIN, Currency::JPY).checked_neg(),
+            Err(MoneyError::Overflow)
+        );
+    }
+
+    #[test]
+    fn rounding_modes() {
+        use Rounding::*;
+        let cases = [
+            // n, d, HalfUp, HalfEven, Down, Up, Floor, Ceiling
+            (5, 2, 3, 2, 2, 3, 2, 3),
+            (7, 2, 4, 4, 3, 4, 3, 4),
+            (-5, 2, -3, -2, -2, -3, -3, -2),
+            (7, 3, 2, 2, 2, 3, 2, 3),
+            (-7, 3, -2, -2, -2, -3, -3, -2),
+            (6, 3, 2, 2, 2, 2, 2, 2),
+        ];
+        for &(n, d, hu, he, dn, up, fl, ce) in &cases {
+            let got: Vec<i128> = [HalfUp, HalfEven, Down, Up, Floor, Ceiling]
+                .iter()
+                .map(|&m| div_round(n, d, m).unwrap())
+                .collect();
+            assert_eq!(got, vec![hu, he, dn, up, fl, ce], "{} / {}", n, d);
+        }
+        assert_eq!(div_round(1, 0, HalfUp), None);
+    }
+
+    #[test]
+    fn tax_and_allocation() {
+        let price = Money::from_minor(1999, Currency::USD);
+        assert_eq!(
+            price.mul_ratio(10, 100, Rounding::HalfUp).unwrap().minor(),
+            200
+        );
+        assert_eq!(
+            price.mul_ratio(10, 100, Rounding::Down).unwrap().minor(),
+            199
+        );
+        let shares = yen(1000).allocate(&[1, 1, 1]).unwrap();
+        assert_eq!(shares, vec![yen(334), yen(333), yen(333)]);
+        let shares = yen(-1000).allocate(&[1, 1, 1]).unwrap();
+        assert_eq!(shares, vec![yen(-334), yen(-333), yen(-333)]);
+        assert_eq!(yen(1).allocate(&[]), Err(MoneyError::DivisionByZero));
+    }
+
+    #[test]
+    fn parse_amounts() {
+        let usd = |s| Money::parse(s, Currency::USD, Rounding::HalfEven);
+        assert_eq!(usd("1,234.56").unwrap().minor(), 123_456);
+        assert_eq!(usd("1234.565").unwrap().minor(), 123_456);
+        assert_eq!(usd("1234.575").unwrap().minor(), 123_458);
+        assert_eq!(usd("-0.5").unwrap().minor(), -50);
+        assert_eq!(usd("12").unwrap().minor(), 1200);
+        assert!(usd("12.3.4").is_err());
+        assert!(usd("abc").is_err());
+        assert!(usd("").is_err());
+        let jpy = Money::parse("67_000_000", Currency::JPY, Rounding::HalfUp).unwrap();
+        assert_eq!(jpy, yen(67_000_000));
+    }
+
+    #[test]
+    fn locale_display() {
+        assert_eq!(yen(67_000_000).to_string(), "¥67,000,000");
+        assert_eq!(yen(67_000_000).format(Locale::Ja), "6700万円");
+        assert_eq!(yen(123_456_789).format(Locale::Ja), "1億2345万6789円");
+        assert_eq!(yen(100_000_000).format(Locale::Ja), "1億円");
+        assert_eq!(yen(0).format(Locale::Ja), "0円");
+        assert_eq!(yen(-1500).to_string(), "-¥1,500");
+        let dollars = Money::from_minor(123_456, Currency::USD);
+        assert_eq!(dollars.to_string(), "$1,234.56");
+        assert_eq!(dollars.format(Locale::Ja), "1234.56ドル");
+        let yuan = Money::from_major(67_000_000, Currency::CNY).unwrap();
+        assert_eq!(yuan.format(Locale::Ja), "6700万元");
+        let half = Money::parse("67000000.5", Currency::CNY, Rounding::HalfUp).unwrap();
+        assert_eq!(half.format(Locale::Ja), "6700万0.50元");
+        assert_eq!(yen(67_000_000).format(Locale::Kanji), "六千七百万円");
+        assert_eq!(yen(-10_000).format(Locale::Kanji), "マイナス一万円");
+        assert_eq!(yen(67_000_000).format(Locale::Daiji), "金陸阡漆佰萬円也");
+        assert_eq!(yen(0).format(Locale::Daiji), "金零円也");
+        assert_eq!(dollars.format(Locale::Kanji), "千二百三十四点五六ドル");
+        assert_eq!(half.format(Locale::Daiji), "金陸阡漆佰萬点伍零元也");
+        assert_eq!(half.to_string(), "CN¥67,000,000.50");
+    }
+
+    #[test]
+    fn conversion_with_rate_table() {
+        let mut rates = RateTable::new();
+        rates.set(Currency::CNY, Currency::JPY, Rate::parse("20.5").unwrap());
+        let pochi = Money::from_major(140_000, Currency::CNY).unwrap();
+        let in_yen = pochi
+            .convert(Currency::JPY, &rates, Rounding::HalfUp)
+            .unwrap();
+        assert_eq!(in_yen, yen(2_870_000));
+        // 逆向きは逆数を使う
+        let back = in_yen
+            .convert(Currency::CNY, &rates, Rounding::HalfUp)
+            .unwrap();
+        assert_eq!(back, pochi);
+        assert_eq!(
+            yen(1).convert(Currency::EUR, &rates, Rounding::HalfUp),
+            Err(MoneyError::NoRate {
+                from: Currency::JPY,
+                to: Currency::EUR
+            })
+        );
+        assert_eq!(
+            yen(5).convert(Currency::JPY, &rates, Rounding::HalfUp),
+            Ok(yen(5))
+        );
+    }
+
+    #[test]
+    fn currency_codes() {
+        assert_eq!(Currency::from_code("jpy"), Ok(Currency::JPY));
+        assert_eq!(
+            Currency::from_code("XXX"),
+            Err(MoneyError::UnknownCurrency(String::from("XXX")))
+        );
+    }
+}
@@ -0,0 +1,457 @@
+// 大きな数の書き方
+// ./others/structure.rs の 67000000元 や main.rs の 64_000 を読みやすく書く
+//   Plain     67000000
+//   Thousands 67,000,000
+//   Man       6700万          (4桁ごとに 万, 億, 兆 ... を挟む)
+//   Kanji     六千七百万
+//   Daiji     陸阡漆佰萬       (書き換えられにくい大字。1 も 壱拾, 壱佰 のように省かない)
+// parse はどの書き方でも(混ぜて 1億2345万, 六千7百 のように書いても)読める
+
+use std::convert::TryFrom;
+use std::fmt;
+
+/// 書き方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Style {
+    Plain,
+    Thousands,
+    Man,
+    Kanji,
+    Daiji,
+}
+
+impl Style {
+    pub const ALL: [Style; 5] = [
+        Style::Plain,
+        Style::Thousands,
+        Style::Man,
+        Style::Kanji,
+        Style::Daiji,
+    ];
+}
+
+// 4桁ごとの単位(u128 の最大値は 340澗 ほどなので 澗 まであれば足りる)
+const BIG_UNITS: [char; 10] = ['\0', '万', '億', '兆', '京', '垓', '秭', '穣', '溝', '澗'];
+
+const KANJI_DIGITS: [char; 10] = ['〇', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+const DAIJI_DIGITS: [char; 10] = ['零', '壱', '弐', '参', '肆', '伍', '陸', '漆', '捌', '玖'];
+
+// 十, 百, 千 の位(添字 1, 2, 3)
+const KANJI_SMALL: [char; 4] = ['\0', '十', '百', '千'];
+const DAIJI_SMALL: [char; 4] = ['\0', '拾', '佰', '阡'];
+
+/// n を style で書く(負なら - か マイナス を付ける)
+pub fn format(n: i128, style: Style) -> String {
+    let body = format_unsigned(n.unsigned_abs(), style);
+    match (n < 0, style) {
+        (false, _) => body,
+        (true, Style::Kanji) | (true, Style::Daiji) => format!("マイナス{}", body),
+        (true, _) => format!("-{}", body),
+    }
+}
+
+/// 符号なしの n を style で書く
+pub fn format_unsigned(n: u128, style: Style) -> String {
+    match style {
+        Style::Plain => n.to_string(),
+        Style::Thousands => group_thousands(n),
+        Style::Man => group_man(n),
+        Style::Kanji => kanji(n, false),
+        Style::Daiji => kanji(n, true),
+    }
+}
+
+fn group_thousands(n: u128) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// 下から4桁ずつ(添字が 万 の何乗か)
+fn groups(mut n: u128) -> Vec<u128> {
+    let mut groups = Vec::new();
+    while n > 0 {
+        groups.push(n % 10_000);
+        n /= 10_000;
+    }
+    groups
+}
+
+// 6700万, 1億2345万6789 のように(0 の区切りは省く)
+fn group_man(n: u128) -> String {
+    if n == 0 {
+        return String::from("0");
+    }
+    let mut out = String::new();
+    for (i, &g) in groups(n).iter().enumerate().rev() {
+        if g == 0 {
+            continue;
+        }
+        out.push_str(&g.to_string());
+        if i > 0 {
+            out.push(BIG_UNITS[i]);
+        }
+    }
+    out
+}
+
+fn kanji(n: u128, daiji: bool) -> String {
+    let (digits, small) = if daiji {
+        (DAIJI_DIGITS, DAIJI_SMALL)
+    } else {
+        (KANJI_DIGITS, KANJI_SMALL)
+    };
+    if n == 0 {
+        return digits[0].to_string();
+    }
+    let mut out = String::new();
+    for (i, &g) in groups(n).iter().enumerate().rev() {
+        if g == 0 {
+            continue;
+        }
+        for place in (1..4).rev() {
+            let d = (g / 10u128.pow(place as u32) % 10) as usize;
+            if d == 0 {
+                continue;
+            }
+            // 普通は 十, 百, 千 の前の 一 を書かない(大字では書く)
+            if d != 1 || daiji {
+                out.push(digits[d]);
+            }
+            out.push(small[place]);
+        }
+        if g % 10 != 0 {
+            out.push(digits[(g % 10) as usize]);
+        }
+        if i > 0 {
+            // 大字の 万 は 萬
+            out.push(if daiji && i == 1 { '萬' } else { BIG_UNITS[i] });
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumeralError {
+    Empty,
+    Unexpected { input: String, at: usize }, // at は何文字目か(0始まり)
+    Grouping(String),                        // 3桁区切りのカンマの位置がおかしい
+    Overflow(String),
+}
+
+impl fmt::Display for NumeralError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumeralError::Empty => write!(f, "数が空です"),
+            NumeralError::Unexpected { input, at } => {
+                let c = input.chars().nth(*at).unwrap_or(' ');
+                write!(f, "{} の {}文字目の {} が読めません", input, at + 1, c)
+            }
+            NumeralError::Grouping(s) => write!(f, "{} はカンマの位置がおかしい", s),
+            NumeralError::Overflow(s) => write!(f, "{} は大きすぎます", s),
+        }
+    }
+}
+
+impl std::error::Error for NumeralError {}
+
+// 1文字の意味
+enum Token {
+    Digit(u128),
+    Small(u32), // 十, 百, 千 の位
+    Big(u32),   // 万 の何乗か
+    Comma,
+}
+
+fn token(c: char) -> Option<Token> {
+    if let Some(d) = c.to_digit(10) {
+        return Some(Token::Digit(d as u128));
+    }
+    // 全角の数字
+    if ('０'..='９').contains(&c) {
+        return Some(Token::Digit((c as u32 - '０' as u32) as u128));
+    }
+    let digit = |d| Some(Token::Digit(d));
+    match c {
+        '〇' | '零' => digit(0),
+        '一' | '壱' | '壹' => digit(1),
+        '二' | '弐' | '貳' => digit(2),
+        '三' | '参' | '參' => digit(3),
+        '四' | '肆' => digit(4),
+        '五' | '伍' => digit(5),
+        '六' | '陸' => digit(6),
+        '七' | '漆' => digit(7),
+        '八' | '捌' => digit(8),
+        '九' | '玖' => digit(9),
+        '十' | '拾' => Some(Token::Small(1)),
+        '百' | '佰' => Some(Token::Small(2)),
+        '千' | '阡' | '仟' => Some(Token::Small(3)),
+        '萬' => Some(Token::Big(1)),
+        '𥝱' => Some(Token::Big(6)),
+        ',' | '，' => Some(Token::Comma),
+        c => BIG_UNITS[1..]
+            .iter()
+            .position(|&u| u == c)
+            .map(|i| Token::Big(i as u32 + 1)),
+    }
+}
+
+/// どの書き方で書かれた数でも読む
+///
+/// 67000000, 67,000,000, 6700万, 六千七百万, 陸阡漆佰萬 はどれも 67000000。
+/// 二〇二六 のような位取りの漢数字も読む。頭の - や マイナス は負の数
+pub fn parse(s: &str) -> Result<i128, NumeralError> {
+    let input = s.trim();
+    let (negative, body) = if let Some(rest) = input.strip_prefix("マイナス") {
+        (true, rest)
+    } else if let Some(rest) = input.strip_prefix(['-', '−']) {
+        (true, rest)
+    } else {
+        (false, input)
+    };
+    // 符号の分だけ文字の位置がずれる
+    let offset = input.chars().count() - body.chars().count();
+    let n = parse_unsigned(body).map_err(|e| match e {
+        NumeralError::Unexpected { at, .. } => NumeralError::Unexpected {
+            input: String::from(input),
+            at: at + offset,
+        },
+        NumeralError::Grouping(_) => NumeralError::Grouping(String::from(input)),
+        NumeralError::Overflow(_) => NumeralError::Overflow(String::from(input)),
+        e => e,
+    })?;
+    let overflow = || NumeralError::Overflow(String::from(input));
+    if negative {
+        // i128::MIN だけは正の側に収まらない
+        if n == i128::MIN.unsigned_abs() {
+            return Ok(i128::MIN);
+        }
+        i128::try_from(n).map(|n| -n).map_err(|_| overflow())
+    } else {
+        i128::try_from(n).map_err(|_| overflow())
+    }
+}
+
+/// 符号の無い数を読む(u128 の範囲まで)
+pub fn parse_unsigned(s: &str) -> Result<u128, NumeralError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(NumeralError::Empty);
+    }
+    let overflow = || NumeralError::Overflow(String::from(s));
+    let unexpected = |at| NumeralError::Unexpected {
+        input: String::from(s),
+        at,
+    };
+    let mut tokens = Vec::new();
+    for (i, c) in s.chars().enumerate() {
+        tokens.push(token(c).ok_or_else(|| unexpected(i))?);
+    }
+
+    // カンマがあれば 3桁区切りの書き方だけ
+    if tokens.iter().any(|t| matches!(t, Token::Comma)) {
+        let mut n: u128 = 0;
+        let mut run = 0; // カンマの後の桁数
+        let mut first = true;
+        for (i, t) in tokens.iter().enumerate() {
+            match t {
+                Token::Digit(d) => {
+                    n = n
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(*d))
+                        .ok_or_else(overflow)?;
+                    run += 1;
+                }
+                Token::Comma => {
+                    if run == 0 || run > 3 || (!first && run != 3) {
+                        return Err(NumeralError::Grouping(String::from(s)));
+                    }
+                    first = false;
+                    run = 0;
+                }
+                _ => return Err(unexpected(i)),
+            }
+        }
+        if run != 3 {
+            return Err(NumeralError::Grouping(String::from(s)));
+        }
+        return Ok(n);
+    }
+
+    // 数字だけなら位取りのまま
+    if tokens.iter().all(|t| matches!(t, Token::Digit(_))) {
+        let mut n: u128 = 0;
+        for t in &tokens {
+            if let Token::Digit(d) = t {
+                n = n
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(*d))
+                    .ok_or_else(overflow)?;
+            }
+        }
+        return Ok(n);
+    }
+
+    // 単位の混ざった書き方
+    let mut total: u128 = 0;
+    let mut group: u128 = 0; // いまの4桁の区切りのうち、十, 百, 千 で確定した分
+    let mut pending: Option<u128> = None; // 単位の付いていない数字の並び
+    let mut last_small = 4; // 直前の 十, 百, 千 の位(まだ無ければ 4)
+    let mut last_big = u32::MAX; // 直前の 万, 億 ... の乗数
+    for (i, t) in tokens.iter().enumerate() {
+        match *t {
+            Token::Digit(d) => {
+                let p = pending.unwrap_or(0) * 10 + d;
+                if p >= 10_000 {
+                    return Err(unexpected(i));
+                }
+                pending = Some(p);
+            }
+            Token::Small(place) => {
+                // 千 の後に 百, 十 は来てよいが、十 の後に 百 は来ない。二十 の 二 は1桁だけ
+                if place >= last_small || pending.is_some_and(|p| p > 9) {
+                    return Err(unexpected(i));
+                }
+                group += pending.take().unwrap_or(1) * 10u128.pow(place);
+                last_small = place;
+            }
+            Token::Big(power) => {
+                let low = pending.take();
+                // 万 だけで始まったり、億 の後に 兆 が来たり、千二十三百 のように下の桁が溢れたりしたら読めない
+                if power >= last_big
+                    || (group == 0 && low.is_none())
+                    || low.is_some_and(|p| p >= 10u128.pow(last_small))
+                {
+                    return Err(unexpected(i));
+                }
+                let value = (group + low.unwrap_or(0))
+                    .checked_mul(10_000u128.checked_pow(power).ok_or_else(overflow)?)
+                    .ok_or_else(overflow)?;
+                total = total.checked_add(value).ok_or_else(overflow)?;
+                group = 0;
+                last_small = 4;
+                last_big = power;
+            }
+            Token::Comma => unreachable!(),
+        }
+    }
+    if pending.is_some_and(|p| p >= 10u128.pow(last_small)) {
+        return Err(unexpected(tokens.len() - 1));
+    }
+    total
+        .checked_add(group + pending.unwrap_or(0))
+        .ok_or_else(overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles() {
+        let all = |n| Style::ALL.iter().map(|&s| format(n, s)).collect::<Vec<_>>();
+        assert_eq!(
+            all(67_000_000),
+            vec![
+                "67000000",
+                "67,000,000",
+                "6700万",
+                "六千七百万",
+                "陸阡漆佰萬"
+            ]
+        );
+        assert_eq!(
+            all(123_456_789),
+            vec![
+                "123456789",
+                "123,456,789",
+                "1億2345万6789",
+                "一億二千三百四十五万六千七百八十九",
+                "壱億弐阡参佰肆拾伍萬陸阡漆佰捌拾玖",
+            ]
+        );
+        assert_eq!(
+            all(10_010),
+            vec!["10010", "10,010", "1万10", "一万十", "壱萬壱拾"]
+        );
+        assert_eq!(all(0), vec!["0", "0", "0", "〇", "零"]);
+        assert_eq!(
+            all(-64_000),
+            vec![
+                "-64000",
+                "-64,000",
+                "-6万4000",
+                "マイナス六万四千",
+                "マイナス陸萬肆阡"
+            ]
+        );
+        assert_eq!(format(1_000_000_000_000, Style::Kanji), "一兆");
+        assert_eq!(
+            format_unsigned(u128::MAX, Style::Man),
+            "340澗2823溝6692穣938秭4634垓6337京4607兆4317億6821万1455"
+        );
+    }
+
+    #[test]
+    fn parses_every_style() {
+        for s in &[
+            "67000000",
+            "67,000,000",
+            "6700万",
+            "六千七百万",
+            "陸阡漆佰萬",
+            "六千七百萬",
+            "６７００万",
+            "六千7百万",
+            "  67,000,000 ",
+        ] {
+            assert_eq!(parse(s), Ok(67_000_000), "{}", s);
+        }
+        assert_eq!(parse("1億2345万6789"), Ok(123_456_789));
+        assert_eq!(parse("二〇二六"), Ok(2026));
+        assert_eq!(parse("千二"), Ok(1002));
+        assert_eq!(parse("十"), Ok(10));
+        assert_eq!(parse("一千万"), Ok(10_000_000));
+        assert_eq!(parse("マイナス五"), Ok(-5));
+        assert_eq!(parse("-1,000"), Ok(-1000));
+        assert_eq!(parse(&format(i128::MIN, Style::Kanji)), Ok(i128::MIN));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse(""), Err(NumeralError::Empty));
+        assert_eq!(parse("マイナス"), Err(NumeralError::Empty));
+        let at = |s: &str| match parse(s) {
+            Err(NumeralError::Unexpected { at, .. }) => at,
+            r => panic!("{}: {:?}", s, r),
+        };
+        assert_eq!(at("万"), 0); // 一万 と書く
+        assert_eq!(at("十百"), 1);
+        assert_eq!(at("一万一億"), 3);
+        assert_eq!(at("十二三"), 2); // 十 の後は1桁まで
+        assert_eq!(at("12345万"), 4);
+        assert_eq!(at("23百"), 2);
+        assert_eq!(at("-12a"), 3);
+        assert_eq!(
+            parse("1億2,345万"),
+            Err(NumeralError::Unexpected {
+                input: String::from("1億2,345万"),
+                at: 1
+            })
+        );
+        for s in &["1,00", "1000,000", ",100", "100,", "1,,000"] {
+            assert_eq!(parse(s), Err(NumeralError::Grouping(String::from(*s))));
+        }
+        assert!(matches!(parse("1000澗"), Err(NumeralError::Overflow(_))));
+        assert_eq!(
+            parse("六千七百ま").unwrap_err().to_string(),
+            "六千七百ま の 5文字目の ま が読めません"
+        );
+    }
+}
@@ -108,6 +108,8 @@ pub fn vector() {
 	];
 	// rowベクタの中身は全て SpreadsheetCell型となる
 	// 列挙子は違うため別の型を保持できる
+	//✔この SpreadsheetCell を A1 形式の升目に広げて式と再計算を付けたものは ../sheet.rs にある
+	//✔この row のような行を CSV から読んだり CSV に書いたりするのは ../csv.rs
 }
 
 pub fn string() {
@@ -414,6 +416,7 @@ pub fn hash_map() {
 		*count += 1; // count が可変参照を保持しているので参照外しをして
 	}
 	println!("{:?}", map); //< {"world": 2, "hello": 1, "wonderful": 1}
+	//✔ファイルやディレクトリの単語を数える(ストップワード, n-gram, 日本語, 集計の足し合わせ)なら ../wordfreq.rs
 	// or_insert関数は元々 キーに対する値への可変参照(&mut V)を返す
 	// ここでその可変参照を count変数に保持しているのでその値に代入するにはまず * で count を参照外ししなければならない
 
@@ -492,6 +495,8 @@ pub fn hash_map() {
 	println!("最頻値1: {:?}", statistics::mode(&int_iii_a)); //< 最頻値1: [57]
 	println!("最頻値2: {:?}", statistics::mode(&int_iii_b)); //< 最頻値2: [57, 66]
 	println!("最頻値3: {:?}", statistics::mode(&int_iii_c)); //< 最頻値3: [0, 1, 2, 3, 4, 5]
+	//✔整数以外でも使えて空でも panic しない版(分散, 分位数なども)は ../statistics.rs にある
+	//✔分布の形をヒストグラムや箱ひげ図で見るなら ../chart.rs
 
   // 単語のピッグ・ラテンへの簡易変換
    // 定義
@@ -516,4 +521,7 @@ pub fn hash_map() {
 	println!("{}", pig_latin::word_to("enum")); // enumhay
 	println!("{}", pig_latin::word_to("open")); // openhay
 	println!("{}", pig_latin::word_to("贵樣")); // 樣贵ay
+	//✔空文字列や多バイト文字でも落ちず、変えない入力は借用のまま返す1単語版は ../text.rs の pig_latin_word
+	//✔文ごと変換して元にも戻せる版(子音の並び, y, ハイフン)は ../pig_latin.rs にある
+	//✔rot13 や全角/半角などほかの変換とつないで流すなら ../transform.rs
 }
\ No newline at end of file
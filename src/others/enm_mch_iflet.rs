@@ -49,6 +49,7 @@ pub fn enumerate() {
     // Move : 匿名構造体を含む
     // Write : String を含む。
     // ChangeColor : 3つの i32値を持つタプルを含む。
+    //✔この Message を状態に当てていく係(取り消し, 再生つき)は ../message.rs にある
 
    // Option enumとNull値に勝る利点
     // Rust には値がないことを意味する null(トイレットペーパーホルダのみの状態)が存在しません
@@ -134,6 +135,7 @@ pub fn match_fc() {
     // 例えば以下の場合...
     nisenyen_kusaikana(BillII::NisenYen(Nioi::Kusai)); //< この二千札は Kusai
     // status には Nioi::Kusai が入る
+    //✔硬貨まで入れた金種と、臭い札を断ってちょうどのおつりを出すレジは ../register.rs にある
 
    // Option<T>とのマッチ
     // Option<T> でも同じように♪
@@ -146,6 +148,7 @@ pub fn match_fc() {
     let five = Some(5);
     let six = plus_one(five); //< 6
     let none = plus_one(None);//< None
+    //✔この match は five.map(|i| i + 1) と同じ(自作の Maybe での map は ../maybe.rs)
 
    // マッチは包括的
     // None の処理を忘れるとコンパイラがキレます
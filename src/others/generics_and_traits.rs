@@ -203,6 +203,7 @@ pub fn generics() {
     // Result enum は2つの型T, E に関してジェネリックで 2つの列挙子がある
     // 型T の値を保持する Ok と 型E の値を保持する Err です
     // この定義により Result enum を 成功する(型Tの値を返す)か 失敗する(型Eのエラーを返す) のどちらかの処理ができる
+    //✔この2つを自分で作って map や and_then まで生やしたものは ../maybe.rs にある(Maybe, Outcome)
     // あらゆる箇所に使用するのが便利になる。
     // 自分のコードで、保持している値の型のみが異なる構造体や enum定義があったら 代わりにジェネリックな型を使うと良い
 
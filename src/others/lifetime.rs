@@ -64,16 +64,17 @@ pub fn lifetime() {
   // 借用精査機
 	// Rustコンパイラには、スコープを比較して 全ての借用が有効であるかを決定する借用チェッカーがある
    	// 以下は 先ほどと同じコードに 変数のライフタイムを表示する注釈が付いている
+    //✔この図は ../lifetime_diagram.rs で自動生成している(`cargo run --bin lifetime_diagram -- --check src/others/lifetime.rs` で検査できる)
 
     /*
     {
-        let r;                // --------+- 'a   // a誕生🎊
-        {                     //         |
-            let x = 5;        // -+- 'b  |       // b誕生🎊
-            r = &x;           //  |      |
-        }                     // -+      |       // b死亡✞
-        println!("{}", r);    //         |
-    }                         // --------+       // a死亡✞
+        let r;             // --------+- 'a   // a誕生🎊
+        {                  //         |
+            let x = 5;     // -+- 'b  |       // b誕生🎊
+            r = &x;        //  |      |
+        }                  // -+      |       // b死亡✞
+        println!("{}", r); //         |
+    }                      // --------+       // a死亡✞
     */
 
     // ここで rのライフタイムは 'a 、xのライフタイムは 'b として注釈した
@@ -84,9 +85,9 @@ pub fn lifetime() {
     // 以下はコードを修正したので、ダングリング参照はなくなってエラーなくコンパイルできる
 
     {
-        let x = 5;            // ---------+- 'b   // 'b誕生🎊
-        let r = &x;           // --+- 'a  |       // 'a誕生🎊
-        println!("r: {}", r); //   |      |
+        let x = 5;            // --------+- 'b   // 'b誕生🎊
+        let r = &x;           // -+- 'a  |       // 'a誕生🎊
+        println!("r: {}", r); //  |      |
     }
 
     // ここで xのライフタイムは 'b であり、'a よりも大きい
@@ -211,6 +211,7 @@ pub fn slice() {
     }
     let word = first_word(&abab); // "AB ab" を入れてみる
     println!("{}", word); //< 2
+    //✔位置ではなく文字列の一部(&str)をそのまま返す版は ../text.rs の first_word にある
     // ただしこれでは abab が改変された際に word が同期されず、ただの役立たずと化す
 }
 
@@ -38,6 +38,7 @@ pub fn structure() {
     // 可変変数なら変更も可能
     pochi.price = 67_000_000;
     println!("このポチは{}元になりました", pochi.price); //< このポチは67000000元になりました
+    //✔6700万 や 六千七百万 のように読みやすく書くなら ../numerals.rs を使う
 
     // 関数内でインスタンス化するにはこの通り
     fn build_kachik(spc:String, prc:u64, age:u32, is_alv:bool) -> Kachik {
@@ -185,6 +186,7 @@ pub fn method() {
    // can_holdメソッドに比べさせる
    println!("Can rect1 hold meth_rect2? {}", meth_rect1.can_hold(meth_rect2)); //< Can rect1 hold meth_rect2? true
    println!("Can rect1 hold meth_rect3? {}", meth_rect1.can_hold(meth_rect3)); //< Can rect1 hold meth_rect3? false
+   //✔位置付きでまとめた長方形(回転ありの can_hold など)は ../geometry.rs にある
 }
 
 pub fn relate_fn() {
@@ -0,0 +1,569 @@
+// 長方形の詰め込み(ビンパッキング)
+// can_hold は「1つが1つに収まるか」しか答えないので、たくさんの長方形を
+// 入れ物(スプライトシートや印刷用紙など)に並べる方法をここにまとめる
+// 詰め方は棚(Shelf), ギロチン(Guillotine), MaxRects の3つ
+// 1枚に収まらなければ同じ大きさの入れ物を必要なだけ増やす(max_bins で上限を付けられる)
+
+use crate::geometry::{Rect, Rotation};
+use std::fmt;
+
+/// 詰め方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// 高さの揃った棚を上から順に作って左から並べる。速いけど隙間が多い
+    Shelf,
+    /// 空き領域を置いた長方形の右と下で2つに切り分けていく
+    Guillotine,
+    /// 空き領域を重なりを許した極大長方形の集まりで持つ。遅いけど一番よく詰まる
+    MaxRects,
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Strategy::Shelf => "shelf",
+            Strategy::Guillotine => "guillotine",
+            Strategy::MaxRects => "maxrects",
+        })
+    }
+}
+
+/// 1つの長方形の置き場所
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub item: usize, // pack に渡した順番
+    pub bin: usize,  // 何枚目の入れ物か
+    pub rect: Rect<u32>,
+    pub rotated: bool, // 90°回して置いたか
+}
+
+/// 詰め込みの結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packing {
+    pub bin_width: u32,
+    pub bin_height: u32,
+    pub bins: Vec<Vec<Placement>>,
+    pub unplaced: Vec<usize>, // 入れ物より大きいか max_bins を使い切って置けなかったもの
+}
+
+impl Packing {
+    pub fn bin_count(&self) -> usize {
+        self.bins.len()
+    }
+
+    pub fn placements(&self) -> impl Iterator<Item = &Placement> {
+        self.bins.iter().flatten()
+    }
+
+    /// item 番目の置き場所
+    pub fn placement(&self, item: usize) -> Option<&Placement> {
+        self.placements().find(|p| p.item == item)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.unplaced.is_empty()
+    }
+
+    /// bin 枚目の使用率(0.0 ~ 1.0)
+    pub fn bin_utilisation(&self, bin: usize) -> f64 {
+        let used: u64 = self.bins[bin].iter().map(|p| area(&p.rect)).sum();
+        used as f64 / self.bin_area() as f64
+    }
+
+    /// 使った入れ物全体での使用率(0.0 ~ 1.0)。1枚も使っていなければ 0.0
+    pub fn utilisation(&self) -> f64 {
+        if self.bins.is_empty() {
+            return 0.0;
+        }
+        let used: u64 = self.placements().map(|p| area(&p.rect)).sum();
+        used as f64 / (self.bin_area() * self.bins.len() as u64) as f64
+    }
+
+    /// 置き場所と使用率の一覧
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "{}枚 ({}x{}), 使用率 {:.1}%\n",
+            self.bins.len(),
+            self.bin_width,
+            self.bin_height,
+            self.utilisation() * 100.0
+        );
+        for (i, bin) in self.bins.iter().enumerate() {
+            out.push_str(&format!(
+                "bin {}: {}個, {:.1}%\n",
+                i,
+                bin.len(),
+                self.bin_utilisation(i) * 100.0
+            ));
+            for p in bin {
+                out.push_str(&format!("  #{} {}", p.item, p.rect));
+                if p.rotated {
+                    out.push_str(" 回転");
+                }
+                out.push('\n');
+            }
+        }
+        if !self.unplaced.is_empty() {
+            let ids: Vec<String> = self.unplaced.iter().map(|i| format!("#{}", i)).collect();
+            out.push_str(&format!("入らなかった: {}\n", ids.join(", ")));
+        }
+        out
+    }
+
+    fn bin_area(&self) -> u64 {
+        self.bin_width as u64 * self.bin_height as u64
+    }
+}
+
+/// 詰め込みの設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Packer {
+    width: u32,
+    height: u32,
+    strategy: Strategy,
+    rotation: Rotation,
+    max_bins: Option<usize>,
+}
+
+impl Packer {
+    /// 幅 x 高さ の入れ物に MaxRects で回転なし、枚数は無制限
+    pub fn new(width: u32, height: u32) -> Packer {
+        Packer {
+            width,
+            height,
+            strategy: Strategy::MaxRects,
+            rotation: Rotation::Fixed,
+            max_bins: None,
+        }
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Packer {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn rotation(mut self, rotation: Rotation) -> Packer {
+        self.rotation = rotation;
+        self
+    }
+
+    /// 入れ物の枚数の上限(Some(1) で1枚だけに詰める)
+    pub fn max_bins(mut self, max_bins: Option<usize>) -> Packer {
+        self.max_bins = max_bins;
+        self
+    }
+
+    /// (幅, 高さ) の並びを詰める
+    /// 大きいものから置いたほうがよく詰まるので、中では長い辺、面積の順に並べ替えてから置く
+    pub fn pack(&self, sizes: &[(u32, u32)]) -> Packing {
+        let mut order: Vec<usize> = (0..sizes.len()).collect();
+        order.sort_by_key(|&i| {
+            let (w, h) = sizes[i];
+            std::cmp::Reverse((w.max(h), w as u64 * h as u64))
+        });
+
+        let whole = Rect::with_size(self.width, self.height);
+        let mut bins: Vec<Box<dyn BinPacker>> = Vec::new();
+        let mut placed: Vec<Vec<Placement>> = Vec::new();
+        let mut unplaced = Vec::new();
+        'items: for item in order {
+            let (w, h) = sizes[item];
+            if !whole.fits(&Rect::with_size(w, h), self.rotation) {
+                unplaced.push(item);
+                continue;
+            }
+            for (bin, packer) in bins.iter_mut().enumerate() {
+                if let Some((rect, rotated)) = packer.insert(w, h, self.rotation) {
+                    placed[bin].push(Placement {
+                        item,
+                        bin,
+                        rect,
+                        rotated,
+                    });
+                    continue 'items;
+                }
+            }
+            if self.max_bins.is_some_and(|max| bins.len() >= max) {
+                unplaced.push(item);
+                continue;
+            }
+            let mut packer = self.new_bin();
+            let (rect, rotated) = packer
+                .insert(w, h, self.rotation)
+                .expect("空の入れ物には必ず入る");
+            placed.push(vec![Placement {
+                item,
+                bin: bins.len(),
+                rect,
+                rotated,
+            }]);
+            bins.push(packer);
+        }
+        unplaced.sort_unstable();
+        Packing {
+            bin_width: self.width,
+            bin_height: self.height,
+            bins: placed,
+            unplaced,
+        }
+    }
+
+    fn new_bin(&self) -> Box<dyn BinPacker> {
+        match self.strategy {
+            Strategy::Shelf => Box::new(ShelfBin::new(self.width, self.height)),
+            Strategy::Guillotine => Box::new(GuillotineBin::new(self.width, self.height)),
+            Strategy::MaxRects => Box::new(MaxRectsBin::new(self.width, self.height)),
+        }
+    }
+}
+
+fn area(r: &Rect<u32>) -> u64 {
+    r.width as u64 * r.height as u64
+}
+
+// 置く向きの候補(回した向きは幅と高さが違うときだけ)
+fn orientations(w: u32, h: u32, rotation: Rotation) -> Vec<(u32, u32, bool)> {
+    let mut v = vec![(w, h, false)];
+    if rotation == Rotation::Allowed && w != h {
+        v.push((h, w, true));
+    }
+    v
+}
+
+// 入れ物1枚ぶんの詰め方
+trait BinPacker {
+    /// 置けたら置いた場所と回したかを返す
+    fn insert(&mut self, w: u32, h: u32, rotation: Rotation) -> Option<(Rect<u32>, bool)>;
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32, // 次に置ける左端
+}
+
+struct ShelfBin {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl ShelfBin {
+    fn new(width: u32, height: u32) -> ShelfBin {
+        ShelfBin {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+}
+
+impl BinPacker for ShelfBin {
+    fn insert(&mut self, w: u32, h: u32, rotation: Rotation) -> Option<(Rect<u32>, bool)> {
+        let candidates = orientations(w, h, rotation);
+        // 今ある棚のうち、高さの無駄が一番少ないところに置く
+        let mut best: Option<(u32, usize, (u32, u32, bool))> = None;
+        for (i, shelf) in self.shelves.iter().enumerate() {
+            for &(ow, oh, rotated) in &candidates {
+                if oh <= shelf.height && shelf.x + ow <= self.width {
+                    let waste = shelf.height - oh;
+                    if best.is_none_or(|(b, _, _)| waste < b) {
+                        best = Some((waste, i, (ow, oh, rotated)));
+                    }
+                }
+            }
+        }
+        if let Some((_, i, (ow, oh, rotated))) = best {
+            let shelf = &mut self.shelves[i];
+            let rect = Rect::new(shelf.x, shelf.y, ow, oh);
+            shelf.x += ow;
+            return Some((rect, rotated));
+        }
+        // 新しい棚を作る。棚が低いほど後が詰まるので低い向きを選ぶ
+        let top = self.shelves.last().map_or(0, |s| s.y + s.height);
+        let (ow, oh, rotated) = candidates
+            .into_iter()
+            .filter(|&(ow, oh, _)| ow <= self.width && top + oh <= self.height)
+            .min_by_key(|&(_, oh, _)| oh)?;
+        self.shelves.push(Shelf {
+            y: top,
+            height: oh,
+            x: ow,
+        });
+        Some((Rect::new(0, top, ow, oh), rotated))
+    }
+}
+
+struct GuillotineBin {
+    free: Vec<Rect<u32>>,
+}
+
+impl GuillotineBin {
+    fn new(width: u32, height: u32) -> GuillotineBin {
+        GuillotineBin {
+            free: vec![Rect::with_size(width, height)],
+        }
+    }
+}
+
+impl BinPacker for GuillotineBin {
+    fn insert(&mut self, w: u32, h: u32, rotation: Rotation) -> Option<(Rect<u32>, bool)> {
+        // 余る面積が一番小さい空き領域を選ぶ(Best Area Fit)
+        let mut best: Option<(u64, usize, (u32, u32, bool))> = None;
+        for (i, f) in self.free.iter().enumerate() {
+            for (ow, oh, rotated) in orientations(w, h, rotation) {
+                if ow <= f.width && oh <= f.height {
+                    let waste = area(f) - ow as u64 * oh as u64;
+                    if best.is_none_or(|(b, _, _)| waste < b) {
+                        best = Some((waste, i, (ow, oh, rotated)));
+                    }
+                }
+            }
+        }
+        let (_, i, (ow, oh, rotated)) = best?;
+        let f = self.free.swap_remove(i);
+        let placed = Rect::new(f.x, f.y, ow, oh);
+        // 残りの短い方の辺に沿って切る(Shorter Axis Split)
+        let (right, bottom) = if f.width - ow < f.height - oh {
+            (
+                Rect::new(f.x + ow, f.y, f.width - ow, oh),
+                Rect::new(f.x, f.y + oh, f.width, f.height - oh),
+            )
+        } else {
+            (
+                Rect::new(f.x + ow, f.y, f.width - ow, f.height),
+                Rect::new(f.x, f.y + oh, ow, f.height - oh),
+            )
+        };
+        self.free
+            .extend([right, bottom].iter().copied().filter(|r| !r.is_empty()));
+        Some((placed, rotated))
+    }
+}
+
+struct MaxRectsBin {
+    free: Vec<Rect<u32>>,
+}
+
+impl MaxRectsBin {
+    fn new(width: u32, height: u32) -> MaxRectsBin {
+        MaxRectsBin {
+            free: vec![Rect::with_size(width, height)],
+        }
+    }
+
+    // placed と重なる空き領域を、placed を避けた極大な長方形(最大4つ)に分け直す
+    fn split_free(&mut self, placed: &Rect<u32>) {
+        let mut next = Vec::with_capacity(self.free.len() + 4);
+        for f in &self.free {
+            if !f.intersects(placed) {
+                next.push(*f);
+                continue;
+            }
+            if placed.x > f.x {
+                next.push(Rect::new(f.x, f.y, placed.x - f.x, f.height));
+            }
+            if placed.right() < f.right() {
+                next.push(Rect::new(
+                    placed.right(),
+                    f.y,
+                    f.right() - placed.right(),
+                    f.height,
+                ));
+            }
+            if placed.y > f.y {
+                next.push(Rect::new(f.x, f.y, f.width, placed.y - f.y));
+            }
+            if placed.bottom() < f.bottom() {
+                next.push(Rect::new(
+                    f.x,
+                    placed.bottom(),
+                    f.width,
+                    f.bottom() - placed.bottom(),
+                ));
+            }
+        }
+        // 他の空き領域に含まれるものは要らない(同じものが2つあれば前の方を残す)
+        let mut pruned: Vec<Rect<u32>> = Vec::with_capacity(next.len());
+        for (i, r) in next.iter().enumerate() {
+            let redundant = next
+                .iter()
+                .enumerate()
+                .any(|(j, o)| i != j && o.contains(r) && (o != r || j < i));
+            if !redundant {
+                pruned.push(*r);
+            }
+        }
+        self.free = pruned;
+    }
+}
+
+impl BinPacker for MaxRectsBin {
+    fn insert(&mut self, w: u32, h: u32, rotation: Rotation) -> Option<(Rect<u32>, bool)> {
+        // 余る短い辺が一番短い空き領域を選ぶ(Best Short Side Fit)
+        let mut best: Option<((u32, u32), Rect<u32>, bool)> = None;
+        for f in &self.free {
+            for (ow, oh, rotated) in orientations(w, h, rotation) {
+                if ow <= f.width && oh <= f.height {
+                    let (dw, dh) = (f.width - ow, f.height - oh);
+                    let score = (dw.min(dh), dw.max(dh));
+                    if best.is_none_or(|(b, _, _)| score < b) {
+                        best = Some((score, Rect::new(f.x, f.y, ow, oh), rotated));
+                    }
+                }
+            }
+        }
+        let (_, placed, rotated) = best?;
+        self.split_free(&placed);
+        Some((placed, rotated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Strategy; 3] = [Strategy::Shelf, Strategy::Guillotine, Strategy::MaxRects];
+
+    // 重なりがなく、全部入れ物の中にあることを確かめる
+    fn assert_valid(p: &Packing, sizes: &[(u32, u32)]) {
+        let bin = Rect::with_size(p.bin_width, p.bin_height);
+        for (b, placements) in p.bins.iter().enumerate() {
+            for (i, a) in placements.iter().enumerate() {
+                assert_eq!(a.bin, b);
+                assert!(bin.contains(&a.rect), "{} がはみ出している", a.rect);
+                let (w, h) = sizes[a.item];
+                let expect = if a.rotated { (h, w) } else { (w, h) };
+                assert_eq!((a.rect.width, a.rect.height), expect);
+                for c in &placements[i + 1..] {
+                    assert!(
+                        !a.rect.intersects(&c.rect),
+                        "{} と {} が重なっている",
+                        a.rect,
+                        c.rect
+                    );
+                }
+            }
+        }
+        let mut seen: Vec<usize> = p
+            .placements()
+            .map(|x| x.item)
+            .chain(p.unplaced.iter().copied())
+            .collect();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..sizes.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn perfect_fit_fills_one_bin() {
+        // 4x4 の入れ物に 2x2 を4つ
+        let sizes = [(2, 2); 4];
+        for &s in &ALL {
+            let p = Packer::new(4, 4).strategy(s).pack(&sizes);
+            assert_valid(&p, &sizes);
+            assert_eq!(p.bin_count(), 1, "{}", s);
+            assert_eq!(p.utilisation(), 1.0);
+        }
+    }
+
+    #[test]
+    fn rotation_lets_tall_items_fit() {
+        let sizes = [(2, 10), (2, 10)];
+        for &s in &ALL {
+            let fixed = Packer::new(10, 4).strategy(s).pack(&sizes);
+            assert_eq!(fixed.unplaced, vec![0, 1]);
+            assert_eq!(fixed.bin_count(), 0);
+            let rotated = Packer::new(10, 4)
+                .strategy(s)
+                .rotation(Rotation::Allowed)
+                .pack(&sizes);
+            assert_valid(&rotated, &sizes);
+            assert_eq!(rotated.bin_count(), 1, "{}", s);
+            assert!(rotated.placements().all(|p| p.rotated));
+        }
+    }
+
+    #[test]
+    fn overflow_opens_more_bins_until_limit() {
+        let sizes = [(3, 3); 5];
+        for &s in &ALL {
+            let p = Packer::new(3, 3).strategy(s).pack(&sizes);
+            assert_valid(&p, &sizes);
+            assert_eq!(p.bin_count(), 5);
+            let limited = Packer::new(3, 3).strategy(s).max_bins(Some(2)).pack(&sizes);
+            assert_valid(&limited, &sizes);
+            assert_eq!(limited.bin_count(), 2);
+            assert_eq!(limited.unplaced.len(), 3);
+        }
+    }
+
+    #[test]
+    fn maxrects_packs_at_least_as_tight_as_shelf() {
+        let sizes = [
+            (5, 3),
+            (4, 4),
+            (3, 5),
+            (2, 6),
+            (6, 2),
+            (3, 3),
+            (1, 7),
+            (7, 1),
+        ];
+        let shelf = Packer::new(10, 10).strategy(Strategy::Shelf).pack(&sizes);
+        let max = Packer::new(10, 10)
+            .strategy(Strategy::MaxRects)
+            .pack(&sizes);
+        assert_valid(&shelf, &sizes);
+        assert_valid(&max, &sizes);
+        assert!(max.bin_count() <= shelf.bin_count());
+    }
+
+    #[test]
+    fn mixed_sizes_stay_valid() {
+        // 1〜39 のいろいろな大きさ(入れ物より大きいものも混ぜる)
+        let sizes: Vec<(u32, u32)> = (0..40u32)
+            .map(|i| (i * 7 % 39 + 1, i * 11 % 37 + 1))
+            .collect();
+        for &(w, h) in &[(30, 30), (64, 35), (99, 41)] {
+            for &s in &ALL {
+                for &rotation in &[Rotation::Fixed, Rotation::Allowed] {
+                    let p = Packer::new(w, h)
+                        .strategy(s)
+                        .rotation(rotation)
+                        .pack(&sizes);
+                    assert_valid(&p, &sizes);
+                    assert!(rotation == Rotation::Allowed || p.placements().all(|x| !x.rotated));
+                    assert!(p.bins.iter().all(|b| !b.is_empty()), "空の入れ物がある");
+                    assert!(p.utilisation() > 0.0 && p.utilisation() <= 1.0);
+                    // 入れ物より大きいものだけが置けない
+                    for &i in &p.unplaced {
+                        let item = Rect::with_size(sizes[i].0, sizes[i].1);
+                        assert!(!Rect::with_size(w, h).fits(&item, rotation));
+                    }
+                    let limited = Packer::new(w, h)
+                        .strategy(s)
+                        .rotation(rotation)
+                        .max_bins(Some(1))
+                        .pack(&sizes);
+                    assert_valid(&limited, &sizes);
+                    assert_eq!(limited.bin_count(), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn report_lists_bins_and_unplaced() {
+        let p = Packer::new(4, 4)
+            .strategy(Strategy::Shelf)
+            .pack(&[(4, 2), (5, 1), (2, 2)]);
+        assert_eq!(
+            p.report(),
+            "1枚 (4x4), 使用率 75.0%\n\
+             bin 0: 2個, 75.0%\n  #0 4x2 @ (0, 0)\n  #2 2x2 @ (0, 2)\n\
+             入らなかった: #1\n"
+        );
+        assert_eq!(p.placement(2).map(|p| p.rect), Some(Rect::new(0, 2, 2, 2)));
+        assert!(!p.is_complete());
+    }
+}
@@ -0,0 +1,592 @@
+// ピッグ・ラテンの翻訳機(文ごと, 逆変換つき)
+// ./others/collections.rs の pig_latin::word_to は1単語だけで、先頭の1文字しか動かさず、
+// 空文字列で unwrap が panic し、バイト長を .take() の文字数に使っていた
+// (text::pig_latin_word はその1単語版を直したもの)
+// ここでは文を受け取り、句読点, 空白, 大文字小文字はそのままに単語だけを変える
+//
+// text::pig_latin_word とは規則が違うので2つある。あちらは word_to と同じ答えを1回の確保で出すための書き直しで、
+// 先頭の1文字だけを動かし, y も子音として扱い, 母音のない語も変える(string → tringsay, hmm → mmhay)
+// ここは英語の規則で変換し、逆変換もする(string → ingstray, hmm → hmm)
+//
+//     string        → ingstray        先頭の子音の並びをまとめて後ろに回して ay
+//     apple         → applehay        母音で始まれば hay(text::pig_latin_word と同じ)
+//     yellow        → ellowyay        y は語頭なら子音, それ以外では母音(rhythm → ythmrhay, my → ymay)
+//     queen         → eenquay         qu は1つの子音として動かす
+//     Hello, World! → Ellohay, Orldway!
+//     well-known    → ellway-ownknay  ハイフンでつないだ語は部分ごと
+//
+// 英字の並び(途中の ' と - は語に含める)だけが単語で、それ以外の文字はそのまま通す
+// 母音のない語(hmm, Mr など)は変えない
+//
+// 普通の書き方は元に戻せないことがある(ellohay は hello とも ello とも読める)ので、
+// decode は語ごとに戻し、読み方が1つに決まらない語は Piece::Ambiguous で候補のまま残す(文全体は失敗させない)
+// hyphenate(true) の書き方(string → ing-stray, apple → apple-ay)は語幹と動かした部分を - で分けるので必ず戻せる
+// 大文字小文字は 全部小文字, 先頭だけ大文字, 全部大文字 の形で戻す(McDonald のように混ざった語は形が変わることがある)
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PigLatinError {
+    Empty,                                             // 入力が空
+    NotPigLatin(String),                               // ピッグ・ラテンとして読めない語
+    Ambiguous { word: String, readings: Vec<String> }, // 元の語の候補が2つ以上ある
+}
+
+impl fmt::Display for PigLatinError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PigLatinError::Empty => write!(f, "空の文字列は変換できません"),
+            PigLatinError::NotPigLatin(w) => write!(f, "{} はピッグ・ラテンとして読めません", w),
+            PigLatinError::Ambiguous { word, readings } => {
+                write!(f, "{} は {} のどれとも読めます", word, readings.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PigLatinError {}
+
+/// decode で戻した文(読み方が決まらない語は候補のまま残す)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Decoded {
+    pieces: Vec<Piece>,
+}
+
+/// 戻した文の1区切り(ハイフンでつないだ語は部分ごとに分ける)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Piece {
+    Text(String),                                      // 戻せた語と、語でない文字
+    Ambiguous { word: String, readings: Vec<String> }, // 読み方が決まらない語と候補
+}
+
+impl Decoded {
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    /// 読み方が決まらなかった語と、その候補
+    pub fn ambiguous(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.pieces.iter().filter_map(|p| match p {
+            Piece::Ambiguous { word, readings } => Some((word.as_str(), readings.as_slice())),
+            Piece::Text(_) => None,
+        })
+    }
+
+    /// 全部の語が決まっていれば文に。決まらない語があれば最初のものを Ambiguous で
+    pub fn text(&self) -> Result<String, PigLatinError> {
+        let mut out = String::new();
+        for p in &self.pieces {
+            match p {
+                Piece::Text(s) => out.push_str(s),
+                Piece::Ambiguous { word, readings } => {
+                    return Err(PigLatinError::Ambiguous {
+                        word: word.clone(),
+                        readings: readings.clone(),
+                    })
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    // 続けて書いたものは1つの Text にまとめる
+    fn push(&mut self, s: &str) {
+        match self.pieces.last_mut() {
+            Some(Piece::Text(t)) => t.push_str(s),
+            _ => self.pieces.push(Piece::Text(s.to_string())),
+        }
+    }
+}
+
+/// 決まらない語は {Ello|Hello} のように候補を並べて書く
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for p in &self.pieces {
+            match p {
+                Piece::Text(s) => f.write_str(s)?,
+                Piece::Ambiguous { readings, .. } => write!(f, "{{{}}}", readings.join("|"))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 変換の設定(Translator::new().hyphenate(true) のように組み立てる)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Translator {
+    hyphenate: bool,
+}
+
+/// 普通の書き方で英語の文をピッグ・ラテンに
+pub fn encode(s: &str) -> Result<String, PigLatinError> {
+    Translator::new().encode(s)
+}
+
+/// 普通の書き方のピッグ・ラテンを英語に戻す
+pub fn decode(s: &str) -> Result<Decoded, PigLatinError> {
+    Translator::new().decode(s)
+}
+
+impl Translator {
+    pub fn new() -> Translator {
+        Translator::default()
+    }
+
+    /// 語幹と動かした部分の間に - を入れる(母音で始まる語は -ay)
+    pub fn hyphenate(self, hyphenate: bool) -> Translator {
+        Translator { hyphenate }
+    }
+
+    /// 英語の文をピッグ・ラテンに
+    pub fn encode(&self, s: &str) -> Result<String, PigLatinError> {
+        each_word(s, |w| {
+            Ok(join(split(w).map(|p| self.encode_part(p)).collect()))
+        })
+    }
+
+    /// ピッグ・ラテンの文を語ごとに英語に戻す
+    /// 読み方が決まらない語は候補のまま残し、ピッグ・ラテンとして読めない語があればエラー
+    pub fn decode(&self, s: &str) -> Result<Decoded, PigLatinError> {
+        if s.is_empty() {
+            return Err(PigLatinError::Empty);
+        }
+        let chars: Vec<char> = s.chars().collect();
+        let mut out = Decoded::default();
+        for token in tokens(&chars) {
+            let w = match token {
+                Token::Other(c) => {
+                    out.push(c.encode_utf8(&mut [0; 4]));
+                    continue;
+                }
+                Token::Word(w) => w,
+            };
+            if self.hyphenate {
+                out.push(&self.decode_hyphenated(w)?);
+                continue;
+            }
+            for (i, p) in split(w).enumerate() {
+                if i > 0 {
+                    out.push("-");
+                }
+                let mut readings = self.decode_plain(p, w)?;
+                if readings.len() == 1 {
+                    out.push(&readings.remove(0));
+                } else {
+                    out.pieces.push(Piece::Ambiguous {
+                        word: p.iter().collect(),
+                        readings,
+                    });
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn encode_part(&self, p: &[char]) -> String {
+        let k = match first_vowel(p) {
+            Some(k) => k,
+            None => return p.iter().collect(),
+        };
+        let mut out: String = p[k..].iter().collect();
+        if self.hyphenate {
+            out.push('-');
+        }
+        out.extend(&p[..k]);
+        out.push_str(if k == 0 && !self.hyphenate {
+            "hay"
+        } else {
+            "ay"
+        });
+        shape(p).apply(&out)
+    }
+
+    // 普通の書き方の1部分: 読み方を全部試して、変換し直すと元に戻るものだけ残す(1つ以上)
+    fn decode_plain(&self, p: &[char], word: &[char]) -> Result<Vec<String>, PigLatinError> {
+        if first_vowel(p).is_none() {
+            return Ok(vec![p.iter().collect()]);
+        }
+        let lower = lowercase(p);
+        if !lower.ends_with("ay") {
+            return Err(PigLatinError::NotPigLatin(word.iter().collect()));
+        }
+        let body = &p[..p.len() - 2];
+        let mut candidates: Vec<Vec<char>> = Vec::new();
+        // 母音で始まる語(…hay の h を落とす)
+        if let Some(('h' | 'H', stem)) = body.split_last().map(|(h, s)| (*h, s)) {
+            candidates.push(stem.to_vec());
+        }
+        // 子音の並びが後ろから1文字, 2文字, … だったとき
+        for j in (1..body.len()).rev() {
+            candidates.push(body[j..].iter().chain(&body[..j]).copied().collect());
+        }
+        let to = shape(p);
+        let mut readings: Vec<String> = Vec::new();
+        for c in candidates {
+            let ok = c.first().is_some_and(char::is_ascii_alphabetic)
+                && self.encode_part(&c).to_ascii_lowercase() == lower;
+            let reading = to.apply(&c.iter().collect::<String>());
+            if ok && !readings.contains(&reading) {
+                readings.push(reading);
+            }
+        }
+        if readings.is_empty() {
+            return Err(PigLatinError::NotPigLatin(word.iter().collect()));
+        }
+        Ok(readings)
+    }
+
+    // ハイフン区切りの書き方: 語幹と「子音 + ay」が - で対になっている
+    // 語幹は y で始まることがある(rhythm → ythm-rhay)ので、母音の有無ではなく対として読めるかで見分け、
+    // 読めない部分は母音がなければ変えずに通す
+    fn decode_hyphenated(&self, w: &[char]) -> Result<String, PigLatinError> {
+        let parts: Vec<&[char]> = split(w).collect();
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < parts.len() {
+            if let Some(word) = parts.get(i + 1).and_then(|s| self.pair(parts[i], s)) {
+                out.push(word);
+                i += 2;
+            } else if first_vowel(parts[i]).is_none() {
+                out.push(parts[i].iter().collect());
+                i += 1;
+            } else {
+                return Err(PigLatinError::NotPigLatin(w.iter().collect()));
+            }
+        }
+        Ok(join(out))
+    }
+
+    // stem-suffix を元の語に(変換し直して同じになるときだけ)
+    fn pair(&self, stem: &[char], suffix: &[char]) -> Option<String> {
+        if suffix.len() < 2 || !lowercase(suffix).ends_with("ay") {
+            return None;
+        }
+        let c: Vec<char> = suffix[..suffix.len() - 2]
+            .iter()
+            .chain(stem)
+            .copied()
+            .collect();
+        let both: Vec<char> = stem.iter().chain(&['-']).chain(suffix).copied().collect();
+        if !c[0].is_ascii_alphabetic()
+            || self.encode_part(&c).to_ascii_lowercase() != lowercase(&both)
+        {
+            return None;
+        }
+        Some(shape(&both).apply(&c.iter().collect::<String>()))
+    }
+}
+
+// 単語ごとに f を通し、ほかの文字はそのまま
+fn each_word<F>(s: &str, mut f: F) -> Result<String, PigLatinError>
+where
+    F: FnMut(&[char]) -> Result<String, PigLatinError>,
+{
+    if s.is_empty() {
+        return Err(PigLatinError::Empty);
+    }
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::with_capacity(s.len() + s.len() / 2);
+    for token in tokens(&chars) {
+        match token {
+            Token::Word(w) => out.push_str(&f(w)?),
+            Token::Other(c) => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+enum Token<'a> {
+    Word(&'a [char]), // 英字で始まって英字で終わり, 途中に ' や - をはさんでよい
+    Other(char),
+}
+
+fn tokens(chars: &[char]) -> Vec<Token<'_>> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_alphabetic() {
+            out.push(Token::Other(chars[i]));
+            i += 1;
+            continue;
+        }
+        let mut end = i + 1;
+        let letter = |i: usize| chars.get(i).is_some_and(char::is_ascii_alphabetic);
+        loop {
+            if letter(end) {
+                end += 1;
+            } else if matches!(chars.get(end), Some('\'' | '-')) && letter(end + 1) {
+                end += 2;
+            } else {
+                break;
+            }
+        }
+        out.push(Token::Word(&chars[i..end]));
+        i = end;
+    }
+    out
+}
+
+fn split(w: &[char]) -> impl Iterator<Item = &[char]> {
+    w.split(|&c| c == '-')
+}
+
+fn join(parts: Vec<String>) -> String {
+    parts.join("-")
+}
+
+fn lowercase(p: &[char]) -> String {
+    p.iter().map(char::to_ascii_lowercase).collect()
+}
+
+// 最初の母音の位置(母音がなければ None)
+// a e i o u が母音。ただし q の後の u は qu で子音, y は語頭でなければ母音
+fn first_vowel(p: &[char]) -> Option<usize> {
+    (0..p.len()).find(|&i| match p[i].to_ascii_lowercase() {
+        'a' | 'e' | 'i' | 'o' => true,
+        'u' => i == 0 || !p[i - 1].eq_ignore_ascii_case(&'q'),
+        'y' => i > 0,
+        _ => false,
+    })
+}
+
+// 大文字小文字の形(全部大文字, 先頭だけ大文字, それ以外は文字ごとにそのまま)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Upper,
+    Title,
+    Asis,
+}
+
+fn shape(p: &[char]) -> Shape {
+    let letters: Vec<char> = p
+        .iter()
+        .copied()
+        .filter(char::is_ascii_alphabetic)
+        .collect();
+    if letters.len() >= 2 && letters.iter().all(char::is_ascii_uppercase) {
+        Shape::Upper
+    } else if letters.first().is_some_and(char::is_ascii_uppercase)
+        && letters[1..].iter().all(char::is_ascii_lowercase)
+    {
+        Shape::Title
+    } else {
+        Shape::Asis
+    }
+}
+
+impl Shape {
+    fn apply(self, s: &str) -> String {
+        match self {
+            Shape::Upper => s.to_ascii_uppercase(),
+            Shape::Title => {
+                let mut out = s.to_ascii_lowercase();
+                if let Some(first) = out.get_mut(..1) {
+                    first.make_ascii_uppercase();
+                }
+                out
+            }
+            Shape::Asis => s.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words() {
+        for (en, pig) in &[
+            ("string", "ingstray"),
+            ("hello", "ellohay"),
+            ("apple", "applehay"),
+            ("yellow", "ellowyay"),
+            ("rhythm", "ythmrhay"),
+            ("my", "ymay"),
+            ("queen", "eenquay"),
+            ("square", "aresquay"),
+            ("equal", "equalhay"),
+            ("don't", "on'tday"),
+            ("hmm", "hmm"),
+        ] {
+            assert_eq!(encode(en).unwrap(), *pig, "{}", en);
+        }
+    }
+
+    #[test]
+    fn sentences_keep_punctuation_case_and_spaces() {
+        assert_eq!(encode("Hello, World!").unwrap(), "Ellohay, Orldway!");
+        assert_eq!(
+            encode("  The QUICK brown fox.\n\tI").unwrap(),
+            "  Ethay ICKQUAY ownbray oxfay.\n\tIhay"
+        );
+        assert_eq!(
+            encode("well-known Mr. Smith").unwrap(),
+            "ellway-ownknay Mr. Ithsmay"
+        );
+        assert_eq!(
+            encode("贵樣 123 -- 'quoted'").unwrap(),
+            "贵樣 123 -- 'otedquay'"
+        );
+        assert_eq!(encode("McDonald").unwrap(), "onaldMcDay");
+    }
+
+    #[test]
+    fn hyphenated() {
+        let t = Translator::new().hyphenate(true);
+        assert_eq!(
+            t.encode("String, apple and well-known!").unwrap(),
+            "Ing-stray, apple-ay and-ay ell-way-own-knay!"
+        );
+        assert_eq!(
+            t.decode("Ing-stray, apple-ay and-ay ell-way-own-knay!")
+                .unwrap()
+                .text()
+                .unwrap(),
+            "String, apple and well-known!"
+        );
+        assert_eq!(t.decode("ELLO-HAY hmm").unwrap().to_string(), "HELLO hmm");
+        assert_eq!(
+            t.decode("ing-xyzzy"),
+            Err(PigLatinError::NotPigLatin(String::from("ing-xyzzy")))
+        );
+        assert!(t.decode("ing").is_err());
+    }
+
+    #[test]
+    fn decode_when_unambiguous() {
+        assert_eq!(
+            decode("Ananabay, omatotay ellowyay!").unwrap().text(),
+            Ok(String::from("Banana, tomato yellow!"))
+        );
+        assert_eq!(decode("OMATOTAY").unwrap().text().unwrap(), "TOMATO");
+        assert_eq!(
+            decode("Ellohay").unwrap().text(),
+            Err(PigLatinError::Ambiguous {
+                word: String::from("Ellohay"),
+                readings: vec![String::from("Ello"), String::from("Hello")],
+            })
+        );
+        let d = decode("ingstray").unwrap();
+        let (word, readings) = d.ambiguous().next().unwrap();
+        assert_eq!(word, "ingstray");
+        assert!(readings.contains(&String::from("string")));
+        assert_eq!(
+            decode("hello"),
+            Err(PigLatinError::NotPigLatin(String::from("hello")))
+        );
+    }
+
+    #[test]
+    fn ambiguous_words_do_not_fail_the_sentence() {
+        let d = decode("Ellohay, ananabay-ellohay!").unwrap();
+        assert_eq!(
+            d.pieces(),
+            &[
+                Piece::Ambiguous {
+                    word: String::from("Ellohay"),
+                    readings: vec![String::from("Ello"), String::from("Hello")],
+                },
+                Piece::Text(String::from(", banana-")),
+                Piece::Ambiguous {
+                    word: String::from("ellohay"),
+                    readings: vec![String::from("ello"), String::from("hello")],
+                },
+                Piece::Text(String::from("!")),
+            ]
+        );
+        assert_eq!(d.to_string(), "{Ello|Hello}, banana-{ello|hello}!");
+        // ふつうの文は読み方の決まらない語だらけでも、元の語が必ず候補に入る
+        for en in &[
+            "hello world",
+            "cat sat on the mat",
+            "The quick brown fox jumps over the lazy dog.",
+            "I like to eat apples",
+        ] {
+            let d = decode(&encode(en).unwrap()).unwrap();
+            let words: Vec<&str> = en
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .filter(|w| !w.is_empty())
+                .collect();
+            let mut got = Vec::new();
+            for p in d.pieces() {
+                match p {
+                    Piece::Text(s) => got.extend(
+                        s.split(|c: char| !c.is_ascii_alphabetic())
+                            .filter(|w| !w.is_empty())
+                            .map(|w| vec![w.to_string()]),
+                    ),
+                    Piece::Ambiguous { readings, .. } => got.push(readings.clone()),
+                }
+            }
+            assert_eq!(got.len(), words.len(), "{}", en);
+            for (w, readings) in words.iter().zip(&got) {
+                assert!(
+                    readings.iter().any(|r| r == w),
+                    "{}: {} {:?}",
+                    en,
+                    w,
+                    readings
+                );
+            }
+        }
+    }
+
+    // 往復を確かめる文(小文字, 先頭だけ大文字, 全部大文字, ', -, qu, y, 母音のない語, 英字でない文字)
+    const SENTENCES: &[&str] = &[
+        "string",
+        "Hello, World!",
+        "  The QUICK brown fox.\n\tI",
+        "well-known Mr. Smith -- don't",
+        "rhythm, my yellow queen; SQUARE equal",
+        "贵樣 123 'quoted' x-ray e-mail",
+        "Hmm!  Psst... TSK-tsk",
+        "a I o-u Queue QUAY yay y",
+    ];
+
+    #[test]
+    fn hyphenated_round_trips() {
+        let t = Translator::new().hyphenate(true);
+        for s in SENTENCES {
+            let pig = t.encode(s).unwrap();
+            assert_eq!(
+                t.decode(&pig).unwrap().text().as_deref(),
+                Ok(*s),
+                "{:?} → {:?}",
+                s,
+                pig
+            );
+        }
+    }
+
+    #[test]
+    fn plain_decodes_to_a_candidate_and_only_words_change() {
+        let others =
+            |s: &str| -> String { s.chars().filter(|c| !c.is_ascii_alphabetic()).collect() };
+        for s in SENTENCES {
+            let pig = encode(s).unwrap();
+            assert_eq!(others(s), others(&pig));
+            // 決まらない語を候補の最初で埋めても、英字でない文字は元の文と同じ
+            let d = decode(&pig).unwrap();
+            let first: String = d
+                .pieces()
+                .iter()
+                .map(|p| match p {
+                    Piece::Text(s) => s.as_str(),
+                    Piece::Ambiguous { readings, .. } => readings[0].as_str(),
+                })
+                .collect();
+            assert_eq!(others(s), others(&first), "{:?} → {:?}", s, pig);
+            for (word, readings) in d.ambiguous() {
+                assert!(readings.len() >= 2, "{}", word);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_is_an_error() {
+        assert_eq!(encode(""), Err(PigLatinError::Empty));
+        assert_eq!(decode(""), Err(PigLatinError::Empty));
+        assert_eq!(encode(" ").unwrap(), " ");
+    }
+}
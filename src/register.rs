@@ -0,0 +1,838 @@
+// レジ
+// ./others/enm_mch_iflet.rs の satsu_wake(札 → 円)と 臭い二千円札 の続き
+// いま使われている硬貨6種と紙幣4種を扱い、限られた引き出しの中身から枚数が一番少ないおつりを出す
+// 臭いお金(Nioi::Kusai)や受け付けない金種は断り、閉店時には数えた中身と帳簿の中身を突き合わせる
+
+use crate::money::{Currency, Locale, Money};
+use crate::text;
+use std::fmt;
+
+/// 金種(安い順)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Denomination {
+    IchiEn,
+    GoEn,
+    JuEn,
+    GojuEn,
+    HyakuEn,
+    GohyakuEn,
+    SenYen,
+    NisenYen,
+    GosenYen,
+    IchimanYen,
+}
+
+impl Denomination {
+    /// 全部の金種(安い順)
+    pub const ALL: [Denomination; 10] = [
+        Denomination::IchiEn,
+        Denomination::GoEn,
+        Denomination::JuEn,
+        Denomination::GojuEn,
+        Denomination::HyakuEn,
+        Denomination::GohyakuEn,
+        Denomination::SenYen,
+        Denomination::NisenYen,
+        Denomination::GosenYen,
+        Denomination::IchimanYen,
+    ];
+
+    /// 額面(円)。satsu_wake の硬貨まで広げた版
+    pub fn value(self) -> u64 {
+        match self {
+            Denomination::IchiEn => 1,
+            Denomination::GoEn => 5,
+            Denomination::JuEn => 10,
+            Denomination::GojuEn => 50,
+            Denomination::HyakuEn => 100,
+            Denomination::GohyakuEn => 500,
+            Denomination::SenYen => 1000,
+            Denomination::NisenYen => 2000,
+            Denomination::GosenYen => 5000,
+            Denomination::IchimanYen => 10_000,
+        }
+    }
+
+    /// 額面から金種を探す
+    pub fn from_value(yen: u64) -> Option<Denomination> {
+        Denomination::ALL.iter().copied().find(|d| d.value() == yen)
+    }
+
+    /// 札か(違えば硬貨)
+    pub fn is_note(self) -> bool {
+        self >= Denomination::SenYen
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Denomination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Denomination::SenYen => f.write_str("千円札"),
+            Denomination::NisenYen => f.write_str("二千円札"),
+            Denomination::GosenYen => f.write_str("五千円札"),
+            Denomination::IchimanYen => f.write_str("一万円札"),
+            coin => write!(f, "{}円玉", coin.value()),
+        }
+    }
+}
+
+/// enm_mch_iflet.rs の Nioi
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nioi {
+    Kusai,
+    Kusakunai,
+}
+
+/// 客から受け取る1枚
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Piece {
+    pub denomination: Denomination,
+    pub nioi: Nioi,
+}
+
+impl Piece {
+    /// 臭くない1枚
+    pub fn new(denomination: Denomination) -> Piece {
+        Piece {
+            denomination,
+            nioi: Nioi::Kusakunai,
+        }
+    }
+
+    /// 臭い1枚
+    pub fn kusai(denomination: Denomination) -> Piece {
+        Piece {
+            denomination,
+            nioi: Nioi::Kusai,
+        }
+    }
+}
+
+/// 金種ごとの枚数(引き出しの中身, おつり, 預かり など)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Cash {
+    counts: [u64; 10], // Denomination::ALL の順
+}
+
+impl Cash {
+    pub fn new() -> Cash {
+        Cash::default()
+    }
+
+    /// (金種, 枚数) の並びから作る(同じ金種が何度出てきてもよい)
+    pub fn from_counts(counts: &[(Denomination, u64)]) -> Result<Cash, RegisterError> {
+        let mut cash = Cash::new();
+        for &(d, n) in counts {
+            cash.add(d, n)?;
+        }
+        Ok(cash)
+    }
+
+    /// 受け取った札と硬貨から作る(臭いかどうかは見ない)
+    pub fn from_pieces(pieces: &[Piece]) -> Result<Cash, RegisterError> {
+        let mut cash = Cash::new();
+        for p in pieces {
+            cash.add(p.denomination, 1)?;
+        }
+        Ok(cash)
+    }
+
+    pub fn count(&self, d: Denomination) -> u64 {
+        self.counts[d.index()]
+    }
+
+    /// d を n 枚足す。枚数か合計金額が u64 に収まらなくなるなら Overflow で、何も変えない
+    pub fn add(&mut self, d: Denomination, n: u64) -> Result<(), RegisterError> {
+        let mut counts = self.counts;
+        counts[d.index()] = counts[d.index()]
+            .checked_add(n)
+            .ok_or(RegisterError::Overflow)?;
+        self.set(counts)
+    }
+
+    /// 足りなければ何もせずに false
+    pub fn remove(&mut self, d: Denomination, n: u64) -> bool {
+        match self.counts[d.index()].checked_sub(n) {
+            Some(left) => {
+                self.counts[d.index()] = left;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// other を全部足す。収まらない金種があれば Overflow で、何も変えない
+    pub fn add_all(&mut self, other: &Cash) -> Result<(), RegisterError> {
+        let mut counts = self.counts;
+        for (c, o) in counts.iter_mut().zip(&other.counts) {
+            *c = c.checked_add(*o).ok_or(RegisterError::Overflow)?;
+        }
+        self.set(counts)
+    }
+
+    // 合計金額が u64 に収まるときだけ入れ替える(だから total と pieces は溢れない)
+    fn set(&mut self, counts: [u64; 10]) -> Result<(), RegisterError> {
+        checked_total(&counts).ok_or(RegisterError::Overflow)?;
+        self.counts = counts;
+        Ok(())
+    }
+
+    /// other が全部入っていれば引いて true、足りない金種があれば何もせずに false
+    pub fn remove_all(&mut self, other: &Cash) -> bool {
+        if !self.contains(other) {
+            return false;
+        }
+        for (c, o) in self.counts.iter_mut().zip(&other.counts) {
+            *c -= o;
+        }
+        true
+    }
+
+    /// other の枚数が全部この中にあるか
+    pub fn contains(&self, other: &Cash) -> bool {
+        self.counts.iter().zip(&other.counts).all(|(c, o)| c >= o)
+    }
+
+    /// 合計(円)
+    pub fn total(&self) -> u64 {
+        // 収まらない中身は set で断っているので None にはならない
+        checked_total(&self.counts).unwrap_or(u64::MAX)
+    }
+
+    /// 合計の枚数(どの金種も 1円以上なので合計金額を超えない)
+    pub fn pieces(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces() == 0
+    }
+
+    /// 1枚以上ある金種を高い順に
+    pub fn iter(&self) -> impl Iterator<Item = (Denomination, u64)> + '_ {
+        Denomination::ALL
+            .iter()
+            .rev()
+            .map(move |&d| (d, self.count(d)))
+            .filter(|&(_, n)| n > 0)
+    }
+}
+
+fn checked_total(counts: &[u64; 10]) -> Option<u64> {
+    Denomination::ALL
+        .iter()
+        .zip(counts)
+        .try_fold(0u64, |sum, (d, &n)| {
+            sum.checked_add(d.value().checked_mul(n)?)
+        })
+}
+
+impl fmt::Display for Cash {
+    // 一万円札×2, 500円玉×1 のように(空なら なし)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("なし");
+        }
+        for (i, (d, n)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}×{}", d, n)?;
+        }
+        Ok(())
+    }
+}
+
+// 枚数の少なさを額ごとに表にしたもの(個数に限りのあるナップサック)
+// 同じ金種を 1, 2, 4, ... 枚の束に分けて、束ごとに 0/1 ナップサックとして詰める
+struct Fewest {
+    best: Vec<u64>, // best[a] = 単位 a 個ぶんを作る最少枚数(作れなければ u64::MAX)
+    bundles: Vec<(Denomination, u64, usize)>, // (金種, 枚数, 単位の数)
+    used: Vec<Vec<u64>>, // used[i] の a ビット目 = 束 i で best[a] が縮んだ
+}
+
+impl Fewest {
+    // available のうち denominations の金種だけを使い、unit 円を1単位として max 単位まで
+    fn build(available: &Cash, denominations: &[Denomination], unit: u64, max: usize) -> Fewest {
+        let mut bundles = Vec::new();
+        for &d in denominations {
+            let w = (d.value() / unit) as usize;
+            // max を超える分は使いようがない
+            let mut left = available.count(d).min((max / w) as u64);
+            let mut k = 1;
+            while left > 0 {
+                let take = k.min(left);
+                bundles.push((d, take, w * take as usize));
+                left -= take;
+                k *= 2;
+            }
+        }
+        let mut best = vec![u64::MAX; max + 1];
+        best[0] = 0;
+        let mut used = Vec::with_capacity(bundles.len());
+        for &(_, k, w) in &bundles {
+            let mut bits = vec![0u64; max / 64 + 1];
+            for a in (w..=max).rev() {
+                let from = best[a - w];
+                if from != u64::MAX && from + k < best[a] {
+                    best[a] = from + k;
+                    bits[a / 64] |= 1 << (a % 64);
+                }
+            }
+            used.push(bits);
+        }
+        Fewest {
+            best,
+            bundles,
+            used,
+        }
+    }
+
+    fn cost(&self, a: usize) -> Option<u64> {
+        self.best.get(a).copied().filter(|&c| c != u64::MAX)
+    }
+
+    // best[a] を作る中身(後ろの束から、縮めたものを順に外していく)
+    // (available の一部なので足せなくなることはない)
+    fn take(&self, mut a: usize) -> Option<Cash> {
+        let mut cash = Cash::new();
+        for (i, &(d, k, w)) in self.bundles.iter().enumerate().rev() {
+            if self.used[i][a / 64] >> (a % 64) & 1 == 1 {
+                cash.add(d, k).ok()?;
+                a -= w;
+            }
+        }
+        Some(cash)
+    }
+}
+
+/// available の中から amount 円ちょうどを、枚数が一番少なくなるように選ぶ(作れなければ None)
+///
+/// 手持ちに限りがあると高い順に取るだけではうまくいかない
+/// (6000円を 五千円札×1, 二千円札×3 から出すなら 二千円札×3)ので、
+/// 硬貨の分(1000円未満の端数 + 1000円の倍数)と札の分(1000円単位)に分けてそれぞれ表にし、
+/// 硬貨で払う額をずらしながら一番少ない組み合わせを探す
+pub fn make_change(available: &Cash, amount: u64) -> Option<Cash> {
+    if amount > available.total() {
+        return None;
+    }
+    let (notes, coins): (Vec<Denomination>, Vec<Denomination>) =
+        Denomination::ALL.iter().partition(|d| d.is_note());
+    let coin_total: u64 = coins.iter().map(|&d| d.value() * available.count(d)).sum();
+    let coin_max = coin_total.min(amount);
+    let note_max = amount / 1000;
+    let coin_table = Fewest::build(available, &coins, 1, coin_max as usize);
+    let note_table = Fewest::build(available, &notes, 1000, note_max as usize);
+
+    let mut best: Option<(u64, u64)> = None; // (枚数, 硬貨で払う額)
+    let mut c = amount % 1000;
+    while c <= coin_max {
+        let by_coins = coin_table.cost(c as usize);
+        let by_notes = note_table.cost(((amount - c) / 1000) as usize);
+        if let (Some(x), Some(y)) = (by_coins, by_notes) {
+            if best.is_none_or(|(b, _)| x + y < b) {
+                best = Some((x + y, c));
+            }
+        }
+        c += 1000;
+    }
+    let (_, c) = best?;
+    let mut change = coin_table.take(c as usize)?;
+    change
+        .add_all(&note_table.take(((amount - c) / 1000) as usize)?)
+        .ok()?;
+    Some(change)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    Kusai(Denomination),                         // 臭いので受け取れない
+    Refused(Denomination),                       // この店では受け付けない金種
+    Insufficient { price: u64, paid: u64 },      // 預かりが足りない
+    NoExactChange { change: u64, drawer: Cash }, // 引き出しの中身ではおつりが作れない
+    Overflow,                                    // 枚数か合計金額が u64 に収まらない
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegisterError::Kusai(d) => write!(f, "この{}は臭いので受け取れません", d),
+            RegisterError::Refused(d) => write!(f, "{}は受け付けていません", d),
+            RegisterError::Insufficient { price, paid } => {
+                write!(f, "{}円に対して{}円しか預かっていません", price, paid)
+            }
+            RegisterError::NoExactChange { change, drawer } => write!(
+                f,
+                "おつり{}円をちょうど出せません(引き出し: {})",
+                change, drawer
+            ),
+            RegisterError::Overflow => write!(f, "枚数か合計金額が大きすぎて数えられません"),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+/// 1回の会計
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sale {
+    pub price: u64,
+    pub tendered: Cash, // 預かり
+    pub change: Cash,   // おつり
+}
+
+/// レジ
+///
+/// 預かったお金はいったん引き出しに入れてからおつりを作る(預かった札や硬貨もおつりに使える)
+#[derive(Debug, Clone)]
+pub struct Register {
+    opening: Cash, // 開店時の釣り銭
+    drawer: Cash,  // 帳簿の上の引き出しの中身
+    refused: Vec<Denomination>,
+    sales: Vec<Sale>,
+}
+
+impl Register {
+    /// 釣り銭 float を入れて開店する
+    pub fn open(float: Cash) -> Register {
+        Register {
+            opening: float,
+            drawer: float,
+            refused: Vec::new(),
+            sales: Vec::new(),
+        }
+    }
+
+    /// d を受け付けないことにする(二千円札の入れ場所が無いレジなど)
+    pub fn refusing(mut self, d: Denomination) -> Register {
+        if !self.refused.contains(&d) {
+            self.refused.push(d);
+        }
+        self
+    }
+
+    pub fn drawer(&self) -> &Cash {
+        &self.drawer
+    }
+
+    pub fn sales(&self) -> &[Sale] {
+        &self.sales
+    }
+
+    /// 売上(引き出しの合計 - 釣り銭)
+    pub fn takings(&self) -> u64 {
+        self.drawer.total() - self.opening.total()
+    }
+
+    /// price 円の会計をする
+    /// 断ったときは引き出しも売上もそのまま
+    pub fn sell(&mut self, price: u64, tendered: &[Piece]) -> Result<&Sale, RegisterError> {
+        for p in tendered {
+            if p.nioi == Nioi::Kusai {
+                return Err(RegisterError::Kusai(p.denomination));
+            }
+            if self.refused.contains(&p.denomination) {
+                return Err(RegisterError::Refused(p.denomination));
+            }
+        }
+        let tendered = Cash::from_pieces(tendered)?;
+        let paid = tendered.total();
+        if paid < price {
+            return Err(RegisterError::Insufficient { price, paid });
+        }
+        let mut drawer = self.drawer;
+        drawer.add_all(&tendered)?;
+        let change = make_change(&drawer, paid - price).ok_or(RegisterError::NoExactChange {
+            change: paid - price,
+            drawer,
+        })?;
+        drawer.remove_all(&change);
+        self.drawer = drawer;
+        self.sales.push(Sale {
+            price,
+            tendered,
+            change,
+        });
+        Ok(self.sales.last().unwrap())
+    }
+
+    /// 閉店時に数えた中身 counted と帳簿を突き合わせる
+    pub fn reconcile(&self, counted: &Cash) -> Reconciliation {
+        Reconciliation {
+            opening: self.opening,
+            expected: self.drawer,
+            counted: *counted,
+            sales: self.sales.len(),
+        }
+    }
+}
+
+/// 締めの結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reconciliation {
+    pub opening: Cash,
+    pub expected: Cash, // 帳簿の上の中身
+    pub counted: Cash,  // 数えた中身
+    pub sales: usize,   // 会計の回数
+}
+
+impl Reconciliation {
+    /// d の枚数の差(数えた - 帳簿。多ければ正)
+    pub fn difference(&self, d: Denomination) -> i64 {
+        self.counted.count(d) as i64 - self.expected.count(d) as i64
+    }
+
+    /// 金額の過不足(数えた - 帳簿。多ければ正)
+    pub fn over_short(&self) -> i64 {
+        self.counted.total() as i64 - self.expected.total() as i64
+    }
+
+    /// 枚数まで全部合っているか
+    pub fn is_balanced(&self) -> bool {
+        self.counted == self.expected
+    }
+
+    /// 金種ごとの表と合計
+    pub fn report(&self) -> String {
+        let mut rows = vec![vec![
+            String::from("金種"),
+            String::from("帳簿"),
+            String::from("実数"),
+            String::from("差"),
+        ]];
+        for &d in Denomination::ALL.iter().rev() {
+            let (e, c) = (self.expected.count(d), self.counted.count(d));
+            if e == 0 && c == 0 {
+                continue;
+            }
+            rows.push(vec![
+                d.to_string(),
+                e.to_string(),
+                c.to_string(),
+                signed(self.difference(d)),
+            ]);
+        }
+        let mut out = text::table(&rows);
+        let yen = |n: i64| Money::from_minor(n, Currency::JPY).format(Locale::Ja);
+        let sales = self.expected.total() as i64 - self.opening.total() as i64;
+        out.push_str(&format!(
+            "会計 {} 回, 釣り銭 {}, 売上 {}\n",
+            self.sales,
+            yen(self.opening.total() as i64),
+            yen(sales)
+        ));
+        out.push_str(&match self.over_short() {
+            0 if self.is_balanced() => String::from("過不足なし\n"),
+            0 => String::from("金額は合っていますが金種の枚数が違います\n"),
+            n if n > 0 => format!("{}多い\n", yen(n)),
+            n => format!("{}足りない\n", yen(-n)),
+        });
+        out
+    }
+}
+
+fn signed(n: i64) -> String {
+    if n > 0 {
+        format!("+{}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Denomination::*;
+
+    fn float() -> Cash {
+        Cash::from_counts(&[
+            (SenYen, 5),
+            (GohyakuEn, 4),
+            (HyakuEn, 10),
+            (GojuEn, 4),
+            (JuEn, 10),
+            (GoEn, 4),
+            (IchiEn, 10),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn denominations() {
+        assert_eq!(
+            Denomination::ALL.iter().map(|d| d.value()).sum::<u64>(),
+            18_666
+        );
+        assert_eq!(Denomination::from_value(2000), Some(NisenYen));
+        assert_eq!(Denomination::from_value(3000), None);
+        assert!(NisenYen.is_note() && !GohyakuEn.is_note());
+        assert_eq!(NisenYen.to_string(), "二千円札");
+        assert_eq!(GohyakuEn.to_string(), "500円玉");
+        assert_eq!(float().total(), 8330);
+        assert_eq!(
+            float().to_string(),
+            "千円札×5, 500円玉×4, 100円玉×10, 50円玉×4, 10円玉×10, 5円玉×4, 1円玉×10"
+        );
+    }
+
+    #[test]
+    fn fewest_pieces() {
+        // 1円玉から1万円札まで十分あれば高い順に取るのと同じ
+        let plenty = Cash::from_counts(
+            &Denomination::ALL
+                .iter()
+                .map(|&d| (d, 9))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(
+            make_change(&plenty, 18_666).unwrap(),
+            Cash::from_counts(
+                &Denomination::ALL
+                    .iter()
+                    .map(|&d| (d, 1))
+                    .collect::<Vec<_>>()
+            )
+            .unwrap()
+        );
+        // 高い順に取ると詰む
+        let drawer = Cash::from_counts(&[(GosenYen, 1), (NisenYen, 3)]).unwrap();
+        assert_eq!(
+            make_change(&drawer, 6000).unwrap(),
+            Cash::from_counts(&[(NisenYen, 3)]).unwrap()
+        );
+        let drawer =
+            Cash::from_counts(&[(GohyakuEn, 1), (HyakuEn, 3), (GojuEn, 0), (JuEn, 20)]).unwrap();
+        assert_eq!(
+            make_change(&drawer, 600).unwrap(),
+            Cash::from_counts(&[(GohyakuEn, 1), (HyakuEn, 1)]).unwrap()
+        );
+        // 硬貨と札をまたぐ
+        let drawer = Cash::from_counts(&[(GohyakuEn, 6), (NisenYen, 1)]).unwrap();
+        assert_eq!(
+            make_change(&drawer, 3000).unwrap(),
+            Cash::from_counts(&[(NisenYen, 1), (GohyakuEn, 2)]).unwrap()
+        );
+        assert_eq!(make_change(&drawer, 0), Some(Cash::new()));
+    }
+
+    #[test]
+    fn exact_change_impossible() {
+        // 小銭が無い
+        let drawer = Cash::from_counts(&[(SenYen, 10)]).unwrap();
+        assert_eq!(make_change(&drawer, 500), None);
+        // 合計は足りるのに組めない
+        let drawer = Cash::from_counts(&[(GosenYen, 1), (NisenYen, 2)]).unwrap();
+        assert_eq!(drawer.total(), 9000);
+        assert_eq!(make_change(&drawer, 6000), None);
+        assert_eq!(make_change(&drawer, 8000), None);
+        // 合計が足りない
+        assert_eq!(make_change(&drawer, 10_000), None);
+
+        let mut r = Register::open(Cash::from_counts(&[(SenYen, 3)]).unwrap());
+        let before = *r.drawer();
+        assert_eq!(
+            r.sell(1500, &[Piece::new(NisenYen)]),
+            Err(RegisterError::NoExactChange {
+                change: 500,
+                drawer: Cash::from_counts(&[(SenYen, 3), (NisenYen, 1)]).unwrap(),
+            })
+        );
+        assert_eq!(r.drawer(), &before);
+        assert!(r.sales().is_empty());
+        // ちょうど払ってもらえば売れる
+        r.sell(1500, &[Piece::new(SenYen), Piece::new(GohyakuEn)])
+            .unwrap();
+        // 今度は預かった500円玉でおつりが出せる
+        let sale = r.sell(1500, &[Piece::new(NisenYen)]).unwrap();
+        assert_eq!(sale.change, Cash::from_counts(&[(GohyakuEn, 1)]).unwrap());
+    }
+
+    #[test]
+    fn rejects_kusai_and_refused() {
+        let mut r = Register::open(float()).refusing(NisenYen);
+        assert_eq!(
+            r.sell(120, &[Piece::kusai(SenYen)]),
+            Err(RegisterError::Kusai(SenYen))
+        );
+        assert_eq!(
+            r.sell(120, &[Piece::new(NisenYen)]),
+            Err(RegisterError::Refused(NisenYen))
+        );
+        assert_eq!(
+            r.sell(1200, &[Piece::new(SenYen), Piece::new(HyakuEn)]),
+            Err(RegisterError::Insufficient {
+                price: 1200,
+                paid: 1100
+            })
+        );
+        assert_eq!(
+            RegisterError::Kusai(NisenYen).to_string(),
+            "この二千円札は臭いので受け取れません"
+        );
+        assert_eq!(r.drawer(), &float());
+    }
+
+    #[test]
+    fn day_and_reconciliation() {
+        let mut r = Register::open(float());
+        let sale = r.sell(5234, &[Piece::new(IchimanYen)]).unwrap();
+        assert_eq!(
+            sale.change.to_string(),
+            "千円札×4, 500円玉×1, 100円玉×2, 50円玉×1, 10円玉×1, 5円玉×1, 1円玉×1"
+        );
+        let coins = [GohyakuEn, HyakuEn, HyakuEn, GojuEn, JuEn, GoEn, IchiEn];
+        let coins: Vec<Piece> = coins.iter().map(|&d| Piece::new(d)).collect();
+        let sale = r.sell(766, &coins).unwrap();
+        assert!(sale.change.is_empty());
+        assert_eq!(r.takings(), 6000);
+
+        let rec = r.reconcile(r.drawer());
+        assert!(rec.is_balanced());
+        assert!(rec
+            .report()
+            .ends_with("会計 2 回, 釣り銭 8330円, 売上 6000円\n過不足なし\n"));
+
+        // 100円玉が1枚足りなくて、1円玉が1枚多い
+        let mut counted = *r.drawer();
+        counted.remove(HyakuEn, 1);
+        counted.add(IchiEn, 1).unwrap();
+        let rec = r.reconcile(&counted);
+        assert_eq!(rec.difference(HyakuEn), -1);
+        assert_eq!(rec.difference(IchiEn), 1);
+        assert_eq!(rec.over_short(), -99);
+        assert!(!rec.is_balanced());
+        let report = rec.report();
+        assert_eq!(
+            report,
+            "金種      帳簿  実数  差\n\
+             ────────  ────  ────  ──\n\
+             一万円札     1     1   0\n\
+             千円札       1     1   0\n\
+             500円玉      4     4   0\n\
+             100円玉     10     9  -1\n\
+             50円玉       4     4   0\n\
+             10円玉      10    10   0\n\
+             5円玉        4     4   0\n\
+             1円玉       10    11  +1\n\
+             会計 2 回, 釣り銭 8330円, 売上 6000円\n\
+             99円足りない\n"
+        );
+
+        // 金額だけ合っている
+        let mut counted = *r.drawer();
+        counted.remove(HyakuEn, 1);
+        counted.add(GojuEn, 2).unwrap();
+        assert!(r
+            .reconcile(&counted)
+            .report()
+            .ends_with("金額は合っていますが金種の枚数が違います\n"));
+    }
+
+    #[test]
+    fn counts_and_totals_do_not_overflow() {
+        let mut cash = Cash::from_counts(&[(IchiEn, u64::MAX - 1)]).unwrap();
+        assert_eq!(cash.add(IchiEn, 2), Err(RegisterError::Overflow));
+        // 枚数は収まっても合計金額が収まらない
+        assert_eq!(cash.add(JuEn, 1), Err(RegisterError::Overflow));
+        assert_eq!(cash.count(IchiEn), u64::MAX - 1);
+        assert_eq!(cash.total(), u64::MAX - 1);
+        cash.add(IchiEn, 1).unwrap();
+        assert_eq!(cash.total(), u64::MAX);
+        assert_eq!(
+            Cash::from_counts(&[(IchimanYen, u64::MAX / 10_000 + 1)]),
+            Err(RegisterError::Overflow)
+        );
+        let before = cash;
+        assert_eq!(
+            cash.add_all(&Cash::from_counts(&[(GoEn, 1)]).unwrap()),
+            Err(RegisterError::Overflow)
+        );
+        assert_eq!(cash, before);
+
+        // 預かりを足すと引き出しが溢れるなら断って、引き出しはそのまま
+        let mut r = Register::open(cash);
+        assert_eq!(
+            r.sell(1, &[Piece::new(IchiEn)]).err(),
+            Some(RegisterError::Overflow)
+        );
+        assert_eq!(r.drawer(), &before);
+    }
+
+    // 引き出しの中身ごとの、作れる額とその最少枚数(総当たり)
+    fn brute_force(cash: &Cash) -> std::collections::HashMap<u64, u64> {
+        let mut best = std::collections::HashMap::new();
+        best.insert(0, 0);
+        for &d in Denomination::ALL.iter() {
+            let mut next = std::collections::HashMap::new();
+            for (&total, &pieces) in &best {
+                for n in 0..=cash.count(d) {
+                    let e = next.entry(total + d.value() * n).or_insert(u64::MAX);
+                    *e = (*e).min(pieces + n);
+                }
+            }
+            best = next;
+        }
+        best
+    }
+
+    #[test]
+    fn change_matches_brute_force() {
+        for k in 0..40u64 {
+            // 金種ごとに 0〜3 枚(無い金種が出やすいように半分は 0)
+            let mut cash = Cash::new();
+            for (j, &d) in Denomination::ALL.iter().enumerate() {
+                cash.add(d, ((k * 7 + j as u64 * 5) % 6).saturating_sub(2))
+                    .unwrap();
+            }
+            let best = brute_force(&cash);
+            // 作れる額の一部と、作れそうで作れない額も混ぜた 0 から total + 1 までの額
+            let mut amounts: Vec<u64> = best.keys().copied().filter(|a| a % 7 == k % 7).collect();
+            amounts.extend((0..=30).map(|i| (cash.total() + 1) * i / 30 + i % 3));
+            for amount in amounts {
+                match (make_change(&cash, amount), best.get(&amount)) {
+                    (Some(change), Some(&pieces)) => {
+                        assert_eq!(change.total(), amount, "{} から {}円", cash, amount);
+                        assert!(cash.contains(&change), "{} から {}", cash, change);
+                        assert_eq!(
+                            change.pieces(),
+                            pieces,
+                            "{} から {}円: {}",
+                            cash,
+                            amount,
+                            change
+                        );
+                    }
+                    (None, None) => {}
+                    (got, want) => panic!(
+                        "{} から {}円: {:?} なのに最少は {:?} 枚",
+                        cash, amount, got, want
+                    ),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn large_amounts_stay_cheap() {
+        // 札の分は1000円単位で表を作るので、大きな額でもすぐ終わる
+        let cash = Cash::from_counts(&[
+            (IchimanYen, 100_000),
+            (GosenYen, 3),
+            (NisenYen, 3),
+            (HyakuEn, 50),
+            (IchiEn, 50),
+        ])
+        .unwrap();
+        let change = make_change(&cash, 123_455_930).unwrap();
+        assert_eq!(change.count(IchimanYen), 12_345);
+        assert_eq!(
+            change.to_string(),
+            "一万円札×12345, 五千円札×1, 100円玉×9, 1円玉×30"
+        );
+    }
+}
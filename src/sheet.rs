@@ -0,0 +1,1442 @@
+// 表計算
+// ./others/collections.rs の SpreadsheetCell(Int, Float, Text)を1行の Vec から
+// A1 形式で指せる升目に広げて、式(=A1+B2, SUM, AVERAGE, MIN, MAX, IF)を書けるようにしたもの
+// 升を書き換えると、その升を(間接的にでも)参照している升だけを依存の順に計算し直す
+// 参照が輪になった升は #CYCLE! になる
+//
+// 型の混ぜ方
+//     + - * /   Int どうしは Int(あふれたら Float)。どちらかが Float なら Float
+//               割り算は割り切れれば Int、割り切れなければ Float。0 で割ると #DIV/0!
+//               空の升は 0、数として読める Text("12" など)はその数、読めない Text は #VALUE!
+//     &         どれも文字にしてつなぐ(空の升は "")
+//     比較      数どうしは値で、Text どうしは文字列で比べる。数と Text なら Text の方が大きい
+//               結果は Int の 1 か 0
+//     IF の条件 0 でない数が真で、空の升は偽。Text は #VALUE!
+//     SUM など  範囲(A1:B3)や参照の先の Text と空の升は飛ばす
+//               引数に直接書いた Text は + - * / と同じ読み方
+//     参照だけ  空の升をそのまま指す式(=Z9)の値は 0
+//     エラー    エラーの升を使った式はそのエラーになる(IF の選ばれなかった側は使わない)
+
+use crate::text;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// collections.rs と同じ3つの列挙子(升に直接書ける値)
+/// Int は式で足し合わせてもあふれにくいように i64 にしてある
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpreadsheetCell {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl SpreadsheetCell {
+    /// 数として読めれば Int か Float、読めなければ Text にする("12" → Int, "1.5" → Float, "12 " → Text)
+    pub fn infer(s: &str) -> SpreadsheetCell {
+        match parse_number(s) {
+            Some(Num::Int(n)) => SpreadsheetCell::Int(n),
+            Some(Num::Float(x)) => SpreadsheetCell::Float(x),
+            None => SpreadsheetCell::Text(s.to_string()),
+        }
+    }
+}
+
+/// 式の計算で起きたエラー(升の値として表に出る)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellError {
+    Cycle, // #CYCLE! 参照が輪になっている
+    Div0,  // #DIV/0! 0 で割った
+    Value, // #VALUE! 数として読めない Text を計算に使った
+    Num,   // #NUM!   Float の計算が無限大になった
+}
+
+impl fmt::Display for CellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            CellError::Cycle => "#CYCLE!",
+            CellError::Div0 => "#DIV/0!",
+            CellError::Value => "#VALUE!",
+            CellError::Num => "#NUM!",
+        })
+    }
+}
+
+/// 升の値(計算した後)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Empty,
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Error(CellError),
+}
+
+impl From<SpreadsheetCell> for Value {
+    fn from(cell: SpreadsheetCell) -> Value {
+        match cell {
+            SpreadsheetCell::Int(n) => Value::Int(n),
+            SpreadsheetCell::Float(x) => Value::Float(x),
+            SpreadsheetCell::Text(s) => Value::Text(s),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Empty => Ok(()),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Text(s) => f.write_str(s),
+            Value::Error(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SheetError {
+    BadRef(String), // A1 形式になっていない
+    Formula {
+        input: String,
+        at: usize, // 何文字目か(= も数える)
+        message: String,
+    },
+}
+
+impl fmt::Display for SheetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SheetError::BadRef(s) => write!(f, "{:?} は A1 形式の升の番地ではありません", s),
+            SheetError::Formula { input, at, message } => {
+                write!(f, "式 {:?} の {} 文字目: {}", input, at + 1, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SheetError {}
+
+/// 升の番地(0 始まり)。表示と読み込みは A1 形式
+/// 行, 列 の順に並べるので、並べ替えると表を左上から読む順になる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CellRef {
+    pub row: u32,
+    pub col: u32,
+}
+
+impl CellRef {
+    // 表計算ソフトでよくある大きさ(XFD1048576 まで)
+    pub const MAX_COLS: u32 = 16_384;
+    pub const MAX_ROWS: u32 = 1_048_576;
+
+    pub fn new(col: u32, row: u32) -> CellRef {
+        CellRef { row, col }
+    }
+
+    /// "B3" → 列 1, 行 2。小文字でもよい
+    pub fn parse(s: &str) -> Result<CellRef, SheetError> {
+        let bad = || SheetError::BadRef(s.to_string());
+        let split = s.find(|c: char| !c.is_ascii_alphabetic()).ok_or_else(bad)?;
+        let (letters, digits) = s.split_at(split);
+        if letters.is_empty() || letters.len() > 3 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(bad());
+        }
+        // A..Z, AA..ZZ, AAA.. と続く(0 の無い26進数)
+        let col = letters.bytes().fold(0, |n, b| {
+            n * 26 + (b.to_ascii_uppercase() - b'A') as u32 + 1
+        });
+        let row: u32 = digits.parse().map_err(|_| bad())?;
+        if col > CellRef::MAX_COLS || row == 0 || row > CellRef::MAX_ROWS || digits.starts_with('0')
+        {
+            return Err(bad());
+        }
+        Ok(CellRef::new(col - 1, row - 1))
+    }
+
+    /// 列の名前(0 → A, 25 → Z, 26 → AA)
+    pub fn column_name(col: u32) -> String {
+        let mut n = col + 1;
+        let mut letters = Vec::new();
+        while n > 0 {
+            letters.push(b'A' + ((n - 1) % 26) as u8);
+            n = (n - 1) / 26;
+        }
+        letters.iter().rev().map(|&b| b as char).collect()
+    }
+}
+
+impl FromStr for CellRef {
+    type Err = SheetError;
+    fn from_str(s: &str) -> Result<CellRef, SheetError> {
+        CellRef::parse(s)
+    }
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", CellRef::column_name(self.col), self.row + 1)
+    }
+}
+
+/// 式で使える関数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Sum,
+    Average,
+    Min,
+    Max,
+    If,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "SUM" => Function::Sum,
+            "AVERAGE" => Function::Average,
+            "MIN" => Function::Min,
+            "MAX" => Function::Max,
+            "IF" => Function::If,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    Ref(CellRef),
+    Range(CellRef, CellRef), // 左上, 右下
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    // 参照している升と範囲
+    // 範囲は中の升に広げない(A1:XFD1048576 は 170億升ある)
+    fn references(&self, cells: &mut BTreeSet<CellRef>, areas: &mut Vec<Area>) {
+        match self {
+            Expr::Literal(_) => {}
+            Expr::Ref(r) => {
+                cells.insert(*r);
+            }
+            Expr::Range(a, b) => {
+                if !areas.contains(&(*a, *b)) {
+                    areas.push((*a, *b));
+                }
+            }
+            Expr::Neg(x) => x.references(cells, areas),
+            Expr::Binary(_, a, b) => {
+                a.references(cells, areas);
+                b.references(cells, areas);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.references(cells, areas)),
+        }
+    }
+}
+
+// 範囲(左上, 右下)
+type Area = (CellRef, CellRef);
+
+fn inside(&(a, b): &Area, cell: CellRef) -> bool {
+    (a.row..=b.row).contains(&cell.row) && (a.col..=b.col).contains(&cell.col)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Value),
+    Text(String),
+    Name(String),
+    Op(Op),
+    Open,
+    Close,
+    Comma,
+    Colon,
+}
+
+type Spanned = (usize, Token); // 何文字目から始まるか
+
+fn tokenize(src: &str, offset: usize) -> Result<Vec<Spanned>, (usize, String)> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let at = offset + i;
+        i += 1;
+        let token = match c {
+            ' ' | '\t' => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '&' => Token::Op(Op::Concat),
+            '=' => Token::Op(Op::Eq),
+            '<' | '>' => {
+                let next = chars.get(i).copied();
+                let op = match (c, next) {
+                    ('<', Some('=')) => Op::Le,
+                    ('<', Some('>')) => Op::Ne,
+                    ('>', Some('=')) => Op::Ge,
+                    ('<', _) => Op::Lt,
+                    _ => Op::Gt,
+                };
+                if matches!(op, Op::Le | Op::Ne | Op::Ge) {
+                    i += 1;
+                }
+                Token::Op(op)
+            }
+            // "" と2つ重ねると " そのもの
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.get(i) {
+                        None => return Err((at, "文字列が \" で閉じていません".to_string())),
+                        Some('"') if chars.get(i + 1) == Some(&'"') => {
+                            s.push('"');
+                            i += 2;
+                        }
+                        Some('"') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&c) => {
+                            s.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                Token::Text(s)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                while i < chars.len() {
+                    let d = chars[i];
+                    let exponent_sign = matches!(d, '+' | '-') && matches!(chars[i - 1], 'e' | 'E');
+                    if d.is_ascii_digit() || matches!(d, '.' | 'e' | 'E') || exponent_sign {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let s: String = chars[start..i].iter().collect();
+                match parse_number(&s) {
+                    Some(n) => Token::Number(n.value()),
+                    None => return Err((at, format!("{} は数として読めません", s))),
+                }
+            }
+            c if c.is_ascii_alphabetic() => {
+                while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                    i += 1;
+                }
+                Token::Name(chars[start..i].iter().collect())
+            }
+            c => return Err((at, format!("{:?} は式に使えません", c))),
+        };
+        tokens.push((at, token));
+    }
+    Ok(tokens)
+}
+
+// 再帰下降で読む
+//     比較     := つなぐ (比較演算子 つなぐ)?
+//     つなぐ   := 足し算 ("&" 足し算)*
+//     足し算   := 掛け算 (("+" | "-") 掛け算)*
+//     掛け算   := 単項 (("*" | "/") 単項)*
+//     単項     := ("-" | "+") 単項 | 一番小さい単位
+//     一番小さい単位 := 数 | 文字列 | "(" 比較 ")" | 関数 "(" 引数 ")" | 番地 (":" 番地)?
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    end: usize, // 式の終わりの位置(エラー表示用)
+}
+
+type ParseResult = Result<Expr, (usize, String)>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn at(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |&(at, _)| at)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).map(|(_, t)| t.clone());
+        self.pos += 1;
+        t
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, what: &str) -> Result<(), (usize, String)> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err((self.at(), format!("{} がありません", what)))
+        }
+    }
+
+    fn op(&mut self, ops: &[Op]) -> Option<Op> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn comparison(&mut self) -> ParseResult {
+        let left = self.concat()?;
+        match self.op(&[Op::Eq, Op::Ne, Op::Lt, Op::Le, Op::Gt, Op::Ge]) {
+            Some(op) => Ok(Expr::Binary(op, Box::new(left), Box::new(self.concat()?))),
+            None => Ok(left),
+        }
+    }
+
+    fn concat(&mut self) -> ParseResult {
+        let mut left = self.additive()?;
+        while let Some(op) = self.op(&[Op::Concat]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.additive()?));
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> ParseResult {
+        let mut left = self.term()?;
+        while let Some(op) = self.op(&[Op::Add, Op::Sub]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> ParseResult {
+        let mut left = self.unary()?;
+        while let Some(op) = self.op(&[Op::Mul, Op::Div]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> ParseResult {
+        match self.op(&[Op::Add, Op::Sub]) {
+            Some(Op::Sub) => Ok(Expr::Neg(Box::new(self.unary()?))),
+            Some(_) => self.unary(),
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> ParseResult {
+        let at = self.at();
+        match self.next() {
+            Some(Token::Number(v)) => Ok(Expr::Literal(v)),
+            Some(Token::Text(s)) => Ok(Expr::Literal(Value::Text(s))),
+            Some(Token::Open) => {
+                let e = self.comparison()?;
+                self.expect(&Token::Close, ")")?;
+                Ok(e)
+            }
+            Some(Token::Name(name)) if self.peek() == Some(&Token::Open) => {
+                let f = Function::from_name(&name)
+                    .ok_or_else(|| (at, format!("{} という関数はありません", name)))?;
+                self.pos += 1;
+                let mut args = Vec::new();
+                if !self.eat(&Token::Close) {
+                    loop {
+                        args.push(self.comparison()?);
+                        if self.eat(&Token::Close) {
+                            break;
+                        }
+                        self.expect(&Token::Comma, ", か )")?;
+                    }
+                }
+                let arity_ok = match f {
+                    Function::If => (2..=3).contains(&args.len()),
+                    _ => !args.is_empty(),
+                };
+                if !arity_ok {
+                    return Err((at, format!("{} の引数の数が合いません", name)));
+                }
+                Ok(Expr::Call(f, args))
+            }
+            Some(Token::Name(name)) => {
+                let cell = |at: usize, name: &str| {
+                    CellRef::parse(name)
+                        .map_err(|_| (at, format!("{} は升の番地ではありません", name)))
+                };
+                let a = cell(at, &name)?;
+                if !self.eat(&Token::Colon) {
+                    return Ok(Expr::Ref(a));
+                }
+                let at = self.at();
+                match self.next() {
+                    Some(Token::Name(other)) => {
+                        let b = cell(at, &other)?;
+                        // どちら向きに書いても左上から右下にそろえる
+                        let top_left = CellRef::new(a.col.min(b.col), a.row.min(b.row));
+                        let bottom_right = CellRef::new(a.col.max(b.col), a.row.max(b.row));
+                        Ok(Expr::Range(top_left, bottom_right))
+                    }
+                    _ => Err((at, ": の後に升の番地がありません".to_string())),
+                }
+            }
+            Some(_) => Err((at, "ここに値がありません".to_string())),
+            None => Err((at, "式が途中で終わっています".to_string())),
+        }
+    }
+}
+
+/// 読み込んだ式(書いたままの文字列も持っておく)
+#[derive(Debug, Clone, PartialEq)]
+struct Formula {
+    source: String,
+    expr: Expr,
+}
+
+impl Formula {
+    // source は = から始まる
+    fn parse(source: &str) -> Result<Formula, SheetError> {
+        let error = |(at, message): (usize, String)| SheetError::Formula {
+            input: source.to_string(),
+            at,
+            message,
+        };
+        let body = &source[1..];
+        let tokens = tokenize(body, 1).map_err(error)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: source.chars().count(),
+        };
+        let expr = parser.comparison().map_err(error)?;
+        if parser.pos < parser.tokens.len() {
+            return Err(error((
+                parser.at(),
+                "式の後ろに余計なものがあります".to_string(),
+            )));
+        }
+        Ok(Formula {
+            source: source.to_string(),
+            expr,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Content {
+    Cell(SpreadsheetCell),
+    Formula(Formula),
+}
+
+// 計算の途中の数
+#[derive(Debug, Clone, Copy)]
+enum Num {
+    Int(i64),
+    Float(f64),
+}
+
+impl Num {
+    fn float(self) -> f64 {
+        match self {
+            Num::Int(n) => n as f64,
+            Num::Float(x) => x,
+        }
+    }
+
+    fn value(self) -> Value {
+        match self {
+            Num::Int(n) => Value::Int(n),
+            Num::Float(x) if x.is_finite() => Value::Float(x),
+            Num::Float(_) => Value::Error(CellError::Num),
+        }
+    }
+
+    fn compare(self, other: Num) -> Ordering {
+        match (self, other) {
+            (Num::Int(a), Num::Int(b)) => a.cmp(&b),
+            // 無限大や NaN は value() でエラーにしてあるので比べられる
+            (a, b) => a.float().partial_cmp(&b.float()).unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+// 入力の "12", "-3.5", "1e3" を数として読む。"inf" や "NaN" は数にしない
+fn parse_number(s: &str) -> Option<Num> {
+    let plausible = s.chars().any(|c| c.is_ascii_digit())
+        && s.chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'));
+    if !plausible {
+        return None;
+    }
+    match s.parse::<i64>() {
+        Ok(n) => Some(Num::Int(n)),
+        Err(_) => s
+            .parse::<f64>()
+            .ok()
+            .filter(|x| x.is_finite())
+            .map(Num::Float),
+    }
+}
+
+// + - * / で使うときの数としての読み方
+fn number(v: &Value) -> Result<Num, CellError> {
+    match v {
+        Value::Empty => Ok(Num::Int(0)),
+        Value::Int(n) => Ok(Num::Int(*n)),
+        Value::Float(x) => Ok(Num::Float(*x)),
+        Value::Text(s) => parse_number(s.trim()).ok_or(CellError::Value),
+        Value::Error(e) => Err(*e),
+    }
+}
+
+fn arithmetic(op: Op, a: Num, b: Num) -> Value {
+    if op == Op::Div && b.float() == 0.0 {
+        return Value::Error(CellError::Div0);
+    }
+    if let (Num::Int(x), Num::Int(y)) = (a, b) {
+        let exact = match op {
+            Op::Add => x.checked_add(y),
+            Op::Sub => x.checked_sub(y),
+            Op::Mul => x.checked_mul(y),
+            _ => x
+                .checked_rem(y)
+                .filter(|&r| r == 0)
+                .and_then(|_| x.checked_div(y)),
+        };
+        if let Some(n) = exact {
+            return Value::Int(n);
+        }
+    }
+    let (x, y) = (a.float(), b.float());
+    Num::Float(match op {
+        Op::Add => x + y,
+        Op::Sub => x - y,
+        Op::Mul => x * y,
+        _ => x / y,
+    })
+    .value()
+}
+
+// 比較の順番。数 < Text で、空の升は相手に合わせて 0 か "" として扱う
+fn order(a: &Value, b: &Value) -> Result<Ordering, CellError> {
+    Ok(match (a, b) {
+        (Value::Text(x), Value::Text(y)) => x.cmp(y),
+        (Value::Text(x), Value::Empty) => x.as_str().cmp(""),
+        (Value::Empty, Value::Text(y)) => "".cmp(y.as_str()),
+        (Value::Text(_), _) => Ordering::Greater,
+        (_, Value::Text(_)) => Ordering::Less,
+        _ => number(a)?.compare(number(b)?),
+    })
+}
+
+fn truth(v: &Value) -> Result<bool, CellError> {
+    match v {
+        Value::Empty => Ok(false),
+        Value::Int(n) => Ok(*n != 0),
+        Value::Float(x) => Ok(*x != 0.0),
+        Value::Text(_) => Err(CellError::Value),
+        Value::Error(e) => Err(*e),
+    }
+}
+
+fn sum(numbers: &[Num]) -> Num {
+    let ints: Option<i64> = numbers.iter().try_fold(0i64, |acc, n| match n {
+        Num::Int(n) => acc.checked_add(*n),
+        Num::Float(_) => None,
+    });
+    match ints {
+        Some(n) => Num::Int(n),
+        None => Num::Float(numbers.iter().map(|n| n.float()).sum()),
+    }
+}
+
+// 読み出し用の空の升
+static EMPTY: Value = Value::Empty;
+
+/// 升目1枚
+/// 書いたもの(数, 文字, 式)と計算した値を別々に持ち、式の升については
+/// 参照している升(precedents)と、逆向きの参照されている升(dependents)も持っておく
+/// 範囲(A1:B3)は升に広げずに範囲のまま持ち(areas)、升が変わったときに範囲に入るかを調べる
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    inputs: HashMap<CellRef, Content>,
+    values: HashMap<CellRef, Value>,
+    precedents: HashMap<CellRef, BTreeSet<CellRef>>,
+    dependents: HashMap<CellRef, BTreeSet<CellRef>>,
+    areas: HashMap<CellRef, Vec<Area>>, // 式の升 → その式が使っている範囲
+}
+
+impl Sheet {
+    pub fn new() -> Sheet {
+        Sheet::default()
+    }
+
+    /// 升に書く。= から始まれば式、数として読めれば Int か Float、それ以外は Text
+    /// ' から始めると数に見えても Text になる('012 → "012")。空文字なら消す
+    /// 計算し直した升を計算した順に返す(書いた升自身も入る)
+    pub fn set(&mut self, at: CellRef, input: &str) -> Result<Vec<CellRef>, SheetError> {
+        let content = if input.is_empty() {
+            None
+        } else if input.starts_with('=') {
+            Some(Content::Formula(Formula::parse(input)?))
+        } else if let Some(text) = input.strip_prefix('\'') {
+            Some(Content::Cell(SpreadsheetCell::Text(text.to_string())))
+        } else {
+            // 数かどうかは前後の空白を除いて見るが、Text は書いたまま
+            Some(Content::Cell(match SpreadsheetCell::infer(input.trim()) {
+                SpreadsheetCell::Text(_) => SpreadsheetCell::Text(input.to_string()),
+                cell => cell,
+            }))
+        };
+        Ok(self.put(at, content))
+    }
+
+    /// 値をそのまま書く(Text が = から始まっていても式にはしない)
+    pub fn set_cell(&mut self, at: CellRef, cell: SpreadsheetCell) -> Vec<CellRef> {
+        self.put(at, Some(Content::Cell(cell)))
+    }
+
+    pub fn clear(&mut self, at: CellRef) -> Vec<CellRef> {
+        self.put(at, None)
+    }
+
+    /// 計算した値(何も書いていない升は Empty)
+    pub fn value(&self, at: CellRef) -> &Value {
+        self.values.get(&at).unwrap_or(&EMPTY)
+    }
+
+    /// 書いたもの(set に渡せばもう一度同じ升になる)
+    pub fn input(&self, at: CellRef) -> String {
+        match self.inputs.get(&at) {
+            None => String::new(),
+            Some(Content::Formula(f)) => f.source.clone(),
+            Some(Content::Cell(SpreadsheetCell::Int(n))) => n.to_string(),
+            Some(Content::Cell(SpreadsheetCell::Float(x))) => format!("{:?}", x),
+            Some(Content::Cell(SpreadsheetCell::Text(s))) => {
+                let looks_special =
+                    s.is_empty() || s.starts_with(['=', '\'']) || parse_number(s.trim()).is_some();
+                if looks_special {
+                    format!("'{}", s)
+                } else {
+                    s.clone()
+                }
+            }
+        }
+    }
+
+    /// 式なら書いたままの式(= から)
+    pub fn formula(&self, at: CellRef) -> Option<&str> {
+        match self.inputs.get(&at) {
+            Some(Content::Formula(f)) => Some(&f.source),
+            _ => None,
+        }
+    }
+
+    /// この升の式が参照している升(範囲は中の何か書いてある升だけ)
+    pub fn precedents(&self, at: CellRef) -> Vec<CellRef> {
+        let mut cells = self.precedents.get(&at).cloned().unwrap_or_default();
+        for area in self.areas.get(&at).into_iter().flatten() {
+            cells.extend(self.filled(area));
+        }
+        cells.into_iter().collect()
+    }
+
+    /// この升を直接参照している式の升(範囲に含めている式も)
+    pub fn dependents(&self, at: CellRef) -> Vec<CellRef> {
+        self.dependents_of(at).into_iter().collect()
+    }
+
+    /// 何か書いてある升(左上から)
+    pub fn cells(&self) -> Vec<CellRef> {
+        let mut cells: Vec<CellRef> = self.inputs.keys().copied().collect();
+        cells.sort();
+        cells
+    }
+
+    /// 全部の升を計算し直す(set は必要な升しか計算しないので、普段は要らない)
+    pub fn recalculate_all(&mut self) -> Vec<CellRef> {
+        self.values.clear();
+        let all: BTreeSet<CellRef> = self.inputs.keys().copied().collect();
+        self.recalculate(&all)
+    }
+
+    /// 値を表にする(左端が行番号, 見出しが列の名前)
+    pub fn render(&self) -> String {
+        let (rows, cols) = self
+            .inputs
+            .keys()
+            .fold((0, 0), |(r, c), at| (r.max(at.row + 1), c.max(at.col + 1)));
+        if rows == 0 {
+            return String::new();
+        }
+        let mut table = vec![std::iter::once(String::new())
+            .chain((0..cols).map(CellRef::column_name))
+            .collect::<Vec<_>>()];
+        for row in 0..rows {
+            let mut line = vec![(row + 1).to_string()];
+            line.extend((0..cols).map(|col| self.value(CellRef::new(col, row)).to_string()));
+            table.push(line);
+        }
+        text::table(&table)
+    }
+
+    fn put(&mut self, at: CellRef, content: Option<Content>) -> Vec<CellRef> {
+        // 古い式の参照を外す
+        self.areas.remove(&at);
+        if let Some(old) = self.precedents.remove(&at) {
+            for p in old {
+                if let Some(set) = self.dependents.get_mut(&p) {
+                    set.remove(&at);
+                    if set.is_empty() {
+                        self.dependents.remove(&p);
+                    }
+                }
+            }
+        }
+        match content {
+            Some(Content::Formula(f)) => {
+                let mut refs = BTreeSet::new();
+                let mut areas = Vec::new();
+                f.expr.references(&mut refs, &mut areas);
+                for &p in &refs {
+                    self.dependents.entry(p).or_default().insert(at);
+                }
+                self.precedents.insert(at, refs);
+                if !areas.is_empty() {
+                    self.areas.insert(at, areas);
+                }
+                self.inputs.insert(at, Content::Formula(f));
+            }
+            Some(content) => {
+                self.inputs.insert(at, content);
+            }
+            None => {
+                self.inputs.remove(&at);
+            }
+        }
+        // 書いた升と、そこから dependents をたどって届く升だけを計算し直す
+        let mut affected = BTreeSet::new();
+        let mut queue = vec![at];
+        while let Some(cell) = queue.pop() {
+            if affected.insert(cell) {
+                queue.extend(self.dependents_of(cell));
+            }
+        }
+        self.recalculate(&affected)
+    }
+
+    // cell を参照している式の升(升で指しているものと、範囲に含めているもの)
+    fn dependents_of(&self, cell: CellRef) -> BTreeSet<CellRef> {
+        let mut out = self.dependents.get(&cell).cloned().unwrap_or_default();
+        for (&f, areas) in &self.areas {
+            if areas.iter().any(|area| inside(area, cell)) {
+                out.insert(f);
+            }
+        }
+        out
+    }
+
+    // 範囲の中の何か書いてある升(左上から)
+    // 範囲が書いてある升の数より狭ければ範囲を、広ければ書いてある升の方をなめる
+    fn filled(&self, area: &Area) -> Vec<CellRef> {
+        let (a, b) = *area;
+        let size = (b.row - a.row + 1) as u64 * (b.col - a.col + 1) as u64;
+        if size <= self.inputs.len() as u64 {
+            (a.row..=b.row)
+                .flat_map(|row| (a.col..=b.col).map(move |col| CellRef::new(col, row)))
+                .filter(|c| self.inputs.contains_key(c))
+                .collect()
+        } else {
+            let mut cells: Vec<CellRef> = self
+                .inputs
+                .keys()
+                .copied()
+                .filter(|&c| inside(area, c))
+                .collect();
+            cells.sort();
+            cells
+        }
+    }
+
+    // affected の中を、参照される側が先になる順に計算する
+    // 強連結成分(Tarjan)に分けて、2つ以上の升の成分か自分を参照する升は輪になっているので #CYCLE!
+    // 輪の外の升は輪の値(#CYCLE!)を普通に読むので、使えばエラーが伝わる
+    fn recalculate(&mut self, affected: &BTreeSet<CellRef>) -> Vec<CellRef> {
+        let mut order = Vec::with_capacity(affected.len());
+        for component in self.components(affected).into_iter().rev() {
+            let cycle =
+                component.len() > 1 || self.dependents_of(component[0]).contains(&component[0]);
+            for &cell in &component {
+                let value = if cycle {
+                    Value::Error(CellError::Cycle)
+                } else {
+                    match self.inputs.get(&cell) {
+                        None => Value::Empty,
+                        Some(Content::Cell(c)) => c.clone().into(),
+                        // 空の升をそのまま指す式は 0 にする
+                        Some(Content::Formula(f)) => match self.eval(&f.expr) {
+                            Value::Empty => Value::Int(0),
+                            v => v,
+                        },
+                    }
+                };
+                if value == Value::Empty {
+                    self.values.remove(&cell);
+                } else {
+                    self.values.insert(cell, value);
+                }
+                order.push(cell);
+            }
+        }
+        order
+    }
+
+    // 強連結成分を、参照する側が先になる順に返す
+    // 長い参照の列でもスタックを使い切らないように、再帰せずに自分で積む
+    fn components(&self, nodes: &BTreeSet<CellRef>) -> Vec<Vec<CellRef>> {
+        struct Mark {
+            index: usize,
+            low: usize,
+            on_stack: bool,
+        }
+        let mut marks: HashMap<CellRef, Mark> = HashMap::new();
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let successors = |v: &CellRef| -> Vec<CellRef> {
+            self.dependents_of(*v)
+                .into_iter()
+                .filter(|w| nodes.contains(w))
+                .collect()
+        };
+        for &root in nodes {
+            if marks.contains_key(&root) {
+                continue;
+            }
+            let mut frames = vec![(root, successors(&root), 0)];
+            let index = marks.len();
+            marks.insert(
+                root,
+                Mark {
+                    index,
+                    low: index,
+                    on_stack: true,
+                },
+            );
+            stack.push(root);
+            while let Some((v, next, i)) = frames.last_mut() {
+                let v = *v;
+                if let Some(&w) = next.get(*i) {
+                    *i += 1;
+                    match marks.get(&w) {
+                        None => {
+                            let index = marks.len();
+                            marks.insert(
+                                w,
+                                Mark {
+                                    index,
+                                    low: index,
+                                    on_stack: true,
+                                },
+                            );
+                            stack.push(w);
+                            frames.push((w, successors(&w), 0));
+                        }
+                        Some(m) if m.on_stack => {
+                            let index = m.index;
+                            let mv = marks.get_mut(&v).unwrap();
+                            mv.low = mv.low.min(index);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+                frames.pop();
+                let (index, low) = {
+                    let m = &marks[&v];
+                    (m.index, m.low)
+                };
+                if let Some((parent, _, _)) = frames.last() {
+                    let mp = marks.get_mut(parent).unwrap();
+                    mp.low = mp.low.min(low);
+                }
+                if low == index {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        marks.get_mut(&w).unwrap().on_stack = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    fn eval(&self, expr: &Expr) -> Value {
+        match expr {
+            Expr::Literal(v) => v.clone(),
+            Expr::Ref(r) => self.value(*r).clone(),
+            // 範囲は関数の引数にしか書けない
+            Expr::Range(..) => Value::Error(CellError::Value),
+            Expr::Neg(x) => match number(&self.eval(x)) {
+                Ok(n) => arithmetic(Op::Sub, Num::Int(0), n),
+                Err(e) => Value::Error(e),
+            },
+            Expr::Binary(op, a, b) => {
+                let a = self.eval(a);
+                if let Value::Error(_) = a {
+                    return a;
+                }
+                let b = self.eval(b);
+                if let Value::Error(_) = b {
+                    return b;
+                }
+                let result = match op {
+                    Op::Concat => Ok(Value::Text(format!("{}{}", a, b))),
+                    Op::Add | Op::Sub | Op::Mul | Op::Div => {
+                        number(&a).and_then(|x| Ok(arithmetic(*op, x, number(&b)?)))
+                    }
+                    _ => order(&a, &b).map(|o| {
+                        let yes = match op {
+                            Op::Eq => o == Ordering::Equal,
+                            Op::Ne => o != Ordering::Equal,
+                            Op::Lt => o == Ordering::Less,
+                            Op::Le => o != Ordering::Greater,
+                            Op::Gt => o == Ordering::Greater,
+                            _ => o != Ordering::Less,
+                        };
+                        Value::Int(yes as i64)
+                    }),
+                };
+                result.unwrap_or_else(Value::Error)
+            }
+            Expr::Call(f, args) => self.call(*f, args).unwrap_or_else(Value::Error),
+        }
+    }
+
+    fn call(&self, f: Function, args: &[Expr]) -> Result<Value, CellError> {
+        if f == Function::If {
+            let chosen = if truth(&self.eval(&args[0]))? {
+                args.get(1)
+            } else {
+                args.get(2)
+            };
+            return Ok(chosen.map_or(Value::Int(0), |e| self.eval(e)));
+        }
+        let numbers = self.numbers(args)?;
+        Ok(match f {
+            Function::Sum => sum(&numbers).value(),
+            Function::Average if numbers.is_empty() => Value::Error(CellError::Div0),
+            Function::Average => arithmetic(Op::Div, sum(&numbers), Num::Int(numbers.len() as i64)),
+            _ => {
+                let pick = if f == Function::Min {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                numbers
+                    .into_iter()
+                    .reduce(|best, n| if n.compare(best) == pick { n } else { best })
+                    .map_or(Value::Int(0), Num::value)
+            }
+        })
+    }
+
+    // SUM などの引数を数の並びにする(参照先の Text と空の升は飛ばす)
+    fn numbers(&self, args: &[Expr]) -> Result<Vec<Num>, CellError> {
+        let mut out = Vec::new();
+        for arg in args {
+            let cells: Vec<CellRef> = match arg {
+                Expr::Ref(r) => vec![*r],
+                Expr::Range(a, b) => self.filled(&(*a, *b)),
+                _ => {
+                    out.push(number(&self.eval(arg))?);
+                    continue;
+                }
+            };
+            for cell in cells {
+                match self.value(cell) {
+                    Value::Int(n) => out.push(Num::Int(*n)),
+                    Value::Float(x) => out.push(Num::Float(*x)),
+                    Value::Error(e) => return Err(*e),
+                    Value::Empty | Value::Text(_) => {}
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> CellRef {
+        CellRef::parse(s).unwrap()
+    }
+
+    fn sheet(cells: &[(&str, &str)]) -> Sheet {
+        let mut sheet = Sheet::new();
+        for (a, input) in cells {
+            sheet.set(at(a), input).unwrap();
+        }
+        sheet
+    }
+
+    fn shown(sheet: &Sheet, a: &str) -> String {
+        sheet.value(at(a)).to_string()
+    }
+
+    #[test]
+    fn a1_addresses() {
+        assert_eq!(at("A1"), CellRef::new(0, 0));
+        assert_eq!(at("b3"), CellRef::new(1, 2));
+        assert_eq!(at("Z10"), CellRef::new(25, 9));
+        assert_eq!(at("AA1"), CellRef::new(26, 0));
+        assert_eq!(at("XFD1048576"), CellRef::new(16_383, 1_048_575));
+        for &col in &[0, 25, 26, 51, 52, 701, 702, 16_383] {
+            let r = CellRef::new(col, 4);
+            assert_eq!(at(&r.to_string()), r);
+        }
+        assert_eq!(CellRef::new(27, 0).to_string(), "AB1");
+        for bad in &["", "A", "1", "A0", "A01", "XFE1", "A1048577", "A1B", "Ａ1"] {
+            assert!(CellRef::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn inputs_become_cells() {
+        let s = sheet(&[
+            ("A1", "3"),
+            ("B1", "blue"),
+            ("C1", "10.12"),
+            ("D1", "'012"),
+            ("E1", "-4"),
+        ]);
+        assert_eq!(s.value(at("A1")), &Value::Int(3));
+        assert_eq!(s.value(at("B1")), &Value::Text("blue".to_string()));
+        assert_eq!(s.value(at("C1")), &Value::Float(10.12));
+        assert_eq!(s.value(at("D1")), &Value::Text("012".to_string()));
+        assert_eq!(s.value(at("E1")), &Value::Int(-4));
+        assert_eq!(s.value(at("F1")), &Value::Empty);
+        assert_eq!(s.input(at("D1")), "'012");
+        assert_eq!(s.input(at("C1")), "10.12");
+        assert_eq!(s.input(at("B1")), "blue");
+    }
+
+    #[test]
+    fn arithmetic_and_precedence() {
+        let s = sheet(&[
+            ("A1", "2"),
+            ("B2", "3"),
+            ("C1", "=A1+B2"),
+            ("C2", "=A1+B2*4"),
+            ("C3", "=(A1+B2)*4"),
+            ("C4", "=-A1--B2"),
+            ("C5", "=7/2"),
+            ("C6", "=8/2"),
+            ("C7", "=1/0"),
+            ("C8", "=A1*1.5"),
+            ("C9", "=\"a\"&A1&\"\"\"\""),
+        ]);
+        assert_eq!(s.value(at("C1")), &Value::Int(5));
+        assert_eq!(s.value(at("C2")), &Value::Int(14));
+        assert_eq!(s.value(at("C3")), &Value::Int(20));
+        assert_eq!(s.value(at("C4")), &Value::Int(1));
+        assert_eq!(s.value(at("C5")), &Value::Float(3.5));
+        assert_eq!(s.value(at("C6")), &Value::Int(4));
+        assert_eq!(shown(&s, "C7"), "#DIV/0!");
+        assert_eq!(s.value(at("C8")), &Value::Float(3.0));
+        assert_eq!(shown(&s, "C9"), "a2\"");
+        assert_eq!(s.formula(at("C1")), Some("=A1+B2"));
+        assert_eq!(s.precedents(at("C1")), vec![at("A1"), at("B2")]);
+        assert_eq!(s.dependents(at("A1")).len(), 6);
+    }
+
+    #[test]
+    fn coercion_rules() {
+        let s = sheet(&[
+            ("A1", "'12"),
+            ("A2", "blue"),
+            ("A3", "1.5"),
+            ("B1", "=A1+1"),     // 数に読める Text
+            ("B2", "=A2+1"),     // 読めない Text
+            ("B3", "=A3+1"),     // Float
+            ("B4", "=Z9+1"),     // 空の升は 0
+            ("B5", "=A3&A2&Z9"), // & は文字に
+            ("B6", "=9223372036854775807+1"),
+            ("B7", "=1e308*10"),
+            ("C1", "=A2>A3"), // Text は数より大きい
+            ("C2", "=A3=1.5"),
+            ("C3", "=2=2.0"),
+            ("C4", "=\"abc\"<\"abd\""),
+            ("C5", "=Z9=0"),
+            ("C6", "=Z9=\"\""),
+        ]);
+        assert_eq!(s.value(at("B1")), &Value::Int(13));
+        assert_eq!(shown(&s, "B2"), "#VALUE!");
+        assert_eq!(s.value(at("B3")), &Value::Float(2.5));
+        assert_eq!(s.value(at("B4")), &Value::Int(1));
+        assert_eq!(shown(&s, "B5"), "1.5blue");
+        assert_eq!(
+            s.value(at("B6")),
+            &Value::Float(9_223_372_036_854_775_808.0)
+        );
+        assert_eq!(shown(&s, "B7"), "#NUM!");
+        for c in &["C1", "C2", "C3", "C4", "C5", "C6"] {
+            assert_eq!(s.value(at(c)), &Value::Int(1), "{}", c);
+        }
+    }
+
+    #[test]
+    fn functions() {
+        let s = sheet(&[
+            ("A1", "1"),
+            ("A2", "2"),
+            ("A3", "note"),
+            ("A4", "4.5"),
+            ("B1", "=SUM(A1:A4)"),
+            ("B2", "=average(A1:A2)"),
+            ("B3", "=AVERAGE(A1:A4)"),
+            ("B4", "=MIN(A4:A1, 10)"),
+            ("B5", "=MAX(A1, A2, A3)"),
+            ("B6", "=AVERAGE(Z1:Z3)"),
+            ("B7", "=IF(A1>A2, \"big\", \"small\")"),
+            ("B8", "=IF(A9, 1)"),
+            ("B9", "=SUM(A1:A2, \"3\") * 2"),
+            ("B10", "=IF(1, 5, 1/0)"),
+            ("B11", "=IF(A3, 1, 2)"),
+        ]);
+        assert_eq!(s.value(at("B1")), &Value::Float(7.5));
+        assert_eq!(s.value(at("B2")), &Value::Float(1.5));
+        assert_eq!(s.value(at("B3")), &Value::Float(2.5));
+        assert_eq!(s.value(at("B4")), &Value::Int(1));
+        assert_eq!(s.value(at("B5")), &Value::Int(2));
+        assert_eq!(shown(&s, "B6"), "#DIV/0!");
+        assert_eq!(shown(&s, "B7"), "small");
+        assert_eq!(s.value(at("B8")), &Value::Int(0));
+        assert_eq!(s.value(at("B9")), &Value::Int(12));
+        assert_eq!(s.value(at("B10")), &Value::Int(5));
+        assert_eq!(shown(&s, "B11"), "#VALUE!");
+    }
+
+    #[test]
+    fn bad_formulas_are_refused() {
+        let mut s = sheet(&[("A1", "=1+1")]);
+        for (input, at_char) in &[
+            ("=1+", 3),
+            ("=FOO(1)", 1),
+            ("=IF(1)", 1),
+            ("=SUM()", 1),
+            ("=A1:", 4),
+            ("=(1", 3),
+            ("=1 2", 3),
+            ("=\"abc", 1),
+            ("=A0", 1),
+            ("=#", 1),
+        ] {
+            match s.set(at("A1"), input) {
+                Err(SheetError::Formula { at, .. }) => assert_eq!(at, *at_char, "{}", input),
+                other => panic!("{} → {:?}", input, other),
+            }
+        }
+        // 断ったときは元のまま
+        assert_eq!(s.value(at("A1")), &Value::Int(2));
+        assert_eq!(
+            s.set(at("A1"), "=1+").unwrap_err().to_string(),
+            "式 \"=1+\" の 4 文字目: 式が途中で終わっています"
+        );
+    }
+
+    #[test]
+    fn only_dependents_are_recalculated() {
+        let mut s = sheet(&[
+            ("A1", "1"),
+            ("B1", "=A1*2"),
+            ("C1", "=B1+A1"),
+            ("D1", "5"),
+            ("E1", "=D1"),
+        ]);
+        let order = s.set(at("A1"), "10").unwrap();
+        // B1 は C1 より先に計算される
+        assert_eq!(order, vec![at("A1"), at("B1"), at("C1")]);
+        assert_eq!(s.value(at("C1")), &Value::Int(30));
+        assert_eq!(s.set(at("D1"), "6").unwrap(), vec![at("D1"), at("E1")]);
+        // 式を書き換えたら古い参照は外れる
+        s.set(at("C1"), "=D1").unwrap();
+        assert_eq!(s.set(at("A1"), "0").unwrap(), vec![at("A1"), at("B1")]);
+        assert_eq!(s.dependents(at("A1")), vec![at("B1")]);
+        // 消すと参照していた升は空として計算し直す
+        let order = s.clear(at("D1"));
+        assert_eq!(order[0], at("D1"));
+        assert_eq!(order.len(), 3);
+        assert_eq!(s.value(at("E1")), &Value::Int(0));
+    }
+
+    #[test]
+    fn cycles_are_marked_and_recover() {
+        let mut s = sheet(&[
+            ("A1", "=B1+1"),
+            ("B1", "=A1+1"),
+            ("C1", "=A1*2"),
+            ("D1", "=IF(1, 7, A1)"),
+            ("E1", "=E1"),
+        ]);
+        for c in &["A1", "B1", "C1", "E1"] {
+            assert_eq!(shown(&s, c), "#CYCLE!", "{}", c);
+        }
+        assert_eq!(s.value(at("D1")), &Value::Int(7));
+        // 輪を切れば元に戻る
+        s.set(at("B1"), "5").unwrap();
+        assert_eq!(s.value(at("A1")), &Value::Int(6));
+        assert_eq!(s.value(at("C1")), &Value::Int(12));
+        s.set(at("E1"), "=SUM(A1:C1)").unwrap();
+        assert_eq!(s.value(at("E1")), &Value::Int(23));
+        // 範囲に自分が入っていても輪
+        s.set(at("E1"), "=SUM(A1:E1)").unwrap();
+        assert_eq!(shown(&s, "E1"), "#CYCLE!");
+    }
+
+    #[test]
+    fn incremental_matches_full_recalculation() {
+        // 範囲, 輪, 消去, 書き換えを混ぜて、set のたびに全部計算し直したものと比べる
+        let script = [
+            ("A1", "3"),
+            ("B2", "=SUM(A1:C3)"),
+            ("C3", "=A1*2"),
+            ("A2", "=B2&\"x\""),
+            ("C1", "=AVERAGE(A1:A3, 1.5)"),
+            ("A3", "=C1"),
+            ("B1", "=IF(A1>2, B2, 0)"),
+            ("A1", "text"),
+            ("B3", "=MAX(B1:B2)+B3"),
+            ("A1", "'7"),
+            ("C3", ""),
+            ("B3", "=MIN(A1:C1)"),
+            ("B2", "=A1/0"),
+            ("B2", "5"),
+            ("A1", ""),
+        ];
+        let mut s = Sheet::new();
+        for (a, input) in &script {
+            let order = s.set(at(a), input).unwrap();
+            assert!(order.contains(&at(a)));
+            let mut full = s.clone();
+            full.recalculate_all();
+            for c in s.cells().into_iter().chain(full.cells()) {
+                assert_eq!(
+                    s.value(c),
+                    full.value(c),
+                    "{} に {} を書いた後の {}",
+                    a,
+                    input,
+                    c
+                );
+            }
+        }
+        // 書いてあるものを逆の順に書き直しても同じになる
+        let mut again = Sheet::new();
+        for c in s.cells().into_iter().rev() {
+            again.set(c, &s.input(c)).unwrap();
+        }
+        for c in s.cells() {
+            assert_eq!(s.value(c), again.value(c), "{}", c);
+            assert_eq!(s.input(c), again.input(c));
+        }
+    }
+
+    #[test]
+    fn whole_column_ranges_stay_cheap() {
+        // 範囲を升に広げていたら 100万升や 170億升をなめることになる
+        let mut s = sheet(&[
+            ("A1", "1"),
+            ("A1048576", "2"),
+            ("B1", "=SUM(A1:A1048576)"),
+            ("C1", "=SUM(A1:XFD1048576)"),
+        ]);
+        assert_eq!(s.value(at("B1")), &Value::Int(3));
+        assert_eq!(s.precedents(at("B1")), vec![at("A1"), at("A1048576")]);
+        // 全体の範囲には自分も入っているので輪
+        assert_eq!(shown(&s, "C1"), "#CYCLE!");
+        s.clear(at("C1"));
+        // 範囲の中で変わった升からたどれる
+        assert_eq!(s.dependents(at("A500000")), vec![at("B1")]);
+        assert_eq!(
+            s.set(at("A500000"), "4").unwrap(),
+            vec![at("A500000"), at("B1")]
+        );
+        assert_eq!(s.value(at("B1")), &Value::Int(7));
+        assert_eq!(s.set(at("B2"), "9").unwrap(), vec![at("B2")]);
+        s.set(at("D1"), "=MAX(A1:A1048576)+B1").unwrap();
+        assert_eq!(s.value(at("D1")), &Value::Int(11));
+    }
+
+    #[test]
+    fn long_chains_do_not_overflow() {
+        let mut s = Sheet::new();
+        s.set(at("A1"), "1").unwrap();
+        for row in 1..5000 {
+            let formula = format!("=A{}+1", row);
+            s.set(CellRef::new(0, row), &formula).unwrap();
+        }
+        assert_eq!(s.value(at("A5000")), &Value::Int(5000));
+        assert_eq!(s.set(at("A1"), "10").unwrap().len(), 5000);
+        assert_eq!(s.value(at("A5000")), &Value::Int(5009));
+        s.set(at("A1"), "=A5000").unwrap();
+        assert_eq!(shown(&s, "A2500"), "#CYCLE!");
+    }
+
+    #[test]
+    fn render_as_table() {
+        let s = sheet(&[
+            ("A1", "3"),
+            ("B1", "blue"),
+            ("C1", "10.12"),
+            ("A2", "=A1*2"),
+            ("C2", "=1/0"),
+        ]);
+        assert_eq!(
+            s.render(),
+            "   A  B     C\n\
+             ─  ─  ────  ───────\n\
+             1  3  blue  10.12\n\
+             2  6        #DIV/0!\n"
+        );
+        assert_eq!(Sheet::new().render(), "");
+    }
+}
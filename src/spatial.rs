@@ -0,0 +1,470 @@
+// 長方形の空間索引(四分木)
+// 「この範囲に重なる長方形はどれか」を全部と比べずに答えるためのもの
+// 節は担当する領域を4つに分けた子を持ち、長方形は自分をまるごと含む一番深い節に置く
+// 節の境界は座標の型によらず f64 で持つ(整数座標だと半分に割れないことがあるので)
+// 根の領域からはみ出す長方形も入れられる(根にそのまま置く)
+
+use crate::geometry::{Coord, Point, Rect};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// 1つの節に置ける数。これを超えたら4つに分ける
+const NODE_CAPACITY: usize = 8;
+// これより深くは分けない(同じ場所に大量に重なっていても無限に分けないように)
+const MAX_DEPTH: usize = 16;
+
+/// 索引に入れたものの番号。消しても他のものの番号は変わらない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntryId(pub usize);
+
+// f64 で持つ節の境界 [x0, x1) x [y0, y1)
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+}
+
+impl Bounds {
+    fn of<T: Coord>(r: &Rect<T>) -> Bounds {
+        Bounds {
+            x0: r.left().to_f64(),
+            y0: r.top().to_f64(),
+            x1: r.right().to_f64(),
+            y1: r.bottom().to_f64(),
+        }
+    }
+
+    fn contains(&self, o: &Bounds) -> bool {
+        self.x0 <= o.x0 && self.y0 <= o.y0 && o.x1 <= self.x1 && o.y1 <= self.y1
+    }
+
+    // 枝刈り用なので辺で接するだけでも重なっているとみなす
+    fn touches(&self, o: &Bounds) -> bool {
+        self.x0 <= o.x1 && o.x0 <= self.x1 && self.y0 <= o.y1 && o.y0 <= self.y1
+    }
+
+    fn quadrants(&self) -> [Bounds; 4] {
+        let mx = (self.x0 + self.x1) / 2.0;
+        let my = (self.y0 + self.y1) / 2.0;
+        [
+            Bounds {
+                x1: mx,
+                y1: my,
+                ..*self
+            },
+            Bounds {
+                x0: mx,
+                y1: my,
+                ..*self
+            },
+            Bounds {
+                x1: mx,
+                y0: my,
+                ..*self
+            },
+            Bounds {
+                x0: mx,
+                y0: my,
+                ..*self
+            },
+        ]
+    }
+
+    fn distance_squared(&self, x: f64, y: f64) -> f64 {
+        let dx = (self.x0 - x).max(0.0).max(x - self.x1);
+        let dy = (self.y0 - y).max(0.0).max(y - self.y1);
+        dx * dx + dy * dy
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    bounds: Bounds,
+    depth: usize,
+    items: Vec<usize>,
+    children: Option<Box<[Node; 4]>>,
+}
+
+impl Node {
+    fn new(bounds: Bounds, depth: usize) -> Node {
+        Node {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    // b をまるごと含む子
+    fn child_for(&mut self, b: &Bounds) -> Option<&mut Node> {
+        self.children
+            .as_mut()?
+            .iter_mut()
+            .find(|c| c.bounds.contains(b))
+    }
+}
+
+/// 長方形とそれに付けた値の四分木
+#[derive(Debug)]
+pub struct QuadTree<T, V> {
+    root: Node,
+    entries: Vec<Option<(Rect<T>, V)>>,
+    len: usize,
+}
+
+impl<T: Coord, V> QuadTree<T, V> {
+    /// bounds を根の領域にした空の木
+    /// 入れる長方形がだいたい収まる範囲を渡すとよく分かれる
+    pub fn new(bounds: Rect<T>) -> QuadTree<T, V> {
+        QuadTree {
+            root: Node::new(Bounds::of(&bounds), 0),
+            entries: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, id: EntryId) -> Option<(&Rect<T>, &V)> {
+        self.entries.get(id.0)?.as_ref().map(|(r, v)| (r, v))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntryId, &Rect<T>, &V)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| e.as_ref().map(|(r, v)| (EntryId(i), r, v)))
+    }
+
+    pub fn insert(&mut self, rect: Rect<T>, value: V) -> EntryId {
+        let index = self.entries.len();
+        let b = Bounds::of(&rect);
+        self.entries.push(Some((rect, value)));
+        self.len += 1;
+        let node = descend(&mut self.root, &b);
+        node.items.push(index);
+        if node.children.is_none() && node.items.len() > NODE_CAPACITY && node.depth < MAX_DEPTH {
+            split(node, &self.entries);
+        }
+        EntryId(index)
+    }
+
+    /// 取り除いて長方形と値を返す。もうなければ None
+    pub fn remove(&mut self, id: EntryId) -> Option<(Rect<T>, V)> {
+        let b = Bounds::of(&self.entries.get(id.0)?.as_ref()?.0);
+        let mut node = &mut self.root;
+        loop {
+            if let Some(pos) = node.items.iter().position(|&i| i == id.0) {
+                node.items.swap_remove(pos);
+                break;
+            }
+            node = node.child_for(&b)?;
+        }
+        self.len -= 1;
+        self.entries[id.0].take()
+    }
+
+    /// region と重なる(面積のある重なりを持つ)ものの番号を小さい順に返す
+    pub fn query(&self, region: &Rect<T>) -> Vec<EntryId> {
+        let b = Bounds::of(region);
+        let mut found = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            for &i in &node.items {
+                if self.rect(i).intersects(region) {
+                    found.push(EntryId(i));
+                }
+            }
+            if let Some(children) = &node.children {
+                stack.extend(children.iter().filter(|c| c.bounds.touches(&b)));
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    /// p を含むものの番号を小さい順に返す
+    pub fn query_point(&self, p: Point<T>) -> Vec<EntryId> {
+        let (x, y) = (p.x.to_f64(), p.y.to_f64());
+        let mut found = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            found.extend(
+                node.items
+                    .iter()
+                    .filter(|&&i| self.rect(i).contains_point(p))
+                    .map(|&i| EntryId(i)),
+            );
+            if let Some(children) = &node.children {
+                stack.extend(
+                    children
+                        .iter()
+                        .filter(|c| c.bounds.distance_squared(x, y) == 0.0),
+                );
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    /// p に一番近いもの(中に含まれていれば距離 0)と距離の2乗
+    /// 同じ距離なら番号の小さい方
+    pub fn nearest(&self, p: Point<T>) -> Option<(EntryId, f64)> {
+        let (x, y) = (p.x.to_f64(), p.y.to_f64());
+        // 節と長方形を点からの距離の近い順に取り出す(最良優先探索)
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate {
+            distance: self.root.bounds.distance_squared(x, y),
+            kind: Kind::Node(&self.root),
+        });
+        let mut best: Option<(EntryId, f64)> = None;
+        while let Some(Candidate { distance, kind }) = heap.pop() {
+            if best.is_some_and(|(_, d)| distance > d) {
+                break;
+            }
+            match kind {
+                Kind::Item(i) => {
+                    if best.is_none_or(|(b, d)| distance < d || i < b.0) {
+                        best = Some((EntryId(i), distance));
+                    }
+                }
+                Kind::Node(node) => {
+                    for &i in &node.items {
+                        heap.push(Candidate {
+                            distance: self.rect(i).distance_squared_to(p),
+                            kind: Kind::Item(i),
+                        });
+                    }
+                    if let Some(children) = &node.children {
+                        for c in children.iter() {
+                            heap.push(Candidate {
+                                distance: c.bounds.distance_squared(x, y),
+                                kind: Kind::Node(c),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// 重なっている組を全部(番号の小さい方を先にして)返す
+    /// レイアウトの検査で全部の組を比べる代わりに使う
+    pub fn overlapping_pairs(&self) -> Vec<(EntryId, EntryId)> {
+        let mut pairs = Vec::new();
+        for (id, rect, _) in self.iter() {
+            pairs.extend(
+                self.query(rect)
+                    .into_iter()
+                    .filter(|&other| other > id)
+                    .map(|other| (id, other)),
+            );
+        }
+        pairs
+    }
+
+    fn rect(&self, index: usize) -> &Rect<T> {
+        &self.entries[index]
+            .as_ref()
+            .expect("木にある番号は消されていない")
+            .0
+    }
+}
+
+// b をまるごと含む子があれば一番深いところまで降りていく
+fn descend<'a>(node: &'a mut Node, b: &Bounds) -> &'a mut Node {
+    let child = node
+        .children
+        .as_ref()
+        .and_then(|c| c.iter().position(|c| c.bounds.contains(b)));
+    match child {
+        Some(i) => descend(&mut node.children.as_mut().unwrap()[i], b),
+        None => node,
+    }
+}
+
+// 節を4つに分けて、子にまるごと入るものを移す
+fn split<T: Coord, V>(node: &mut Node, entries: &[Option<(Rect<T>, V)>]) {
+    let [a, b, c, d] = node.bounds.quadrants();
+    let depth = node.depth + 1;
+    node.children = Some(Box::new([
+        Node::new(a, depth),
+        Node::new(b, depth),
+        Node::new(c, depth),
+        Node::new(d, depth),
+    ]));
+    let items = std::mem::take(&mut node.items);
+    for i in items {
+        let rect = &entries[i].as_ref().expect("木にある番号は消されていない").0;
+        match node.child_for(&Bounds::of(rect)) {
+            Some(child) => child.items.push(i),
+            None => node.items.push(i),
+        }
+    }
+}
+
+enum Kind<'a> {
+    Node(&'a Node),
+    Item(usize),
+}
+
+// BinaryHeap は大きい順に出すので、距離の比較を逆にしておく
+struct Candidate<'a> {
+    distance: f64,
+    kind: Kind<'a>,
+}
+
+impl PartialEq for Candidate<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate<'_> {}
+
+impl PartialOrd for Candidate<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> QuadTree<i32, &'static str> {
+        let mut t = QuadTree::new(Rect::new(0, 0, 100, 100));
+        t.insert(Rect::new(0, 0, 10, 10), "a");
+        t.insert(Rect::new(20, 20, 10, 10), "b");
+        t.insert(Rect::new(25, 25, 50, 5), "c");
+        t.insert(Rect::new(90, 90, 10, 10), "d");
+        t
+    }
+
+    #[test]
+    fn query_finds_overlaps() {
+        let t = grid();
+        assert_eq!(
+            t.query(&Rect::new(5, 5, 20, 20)),
+            vec![EntryId(0), EntryId(1)]
+        );
+        assert_eq!(t.query(&Rect::new(60, 0, 10, 100)), vec![EntryId(2)]);
+        assert_eq!(t.query(&Rect::new(10, 10, 10, 10)), vec![]); // 辺で接するだけ
+        assert_eq!(
+            t.query_point(Point::new(27, 27)),
+            vec![EntryId(1), EntryId(2)]
+        );
+        assert_eq!(t.get(EntryId(3)), Some((&Rect::new(90, 90, 10, 10), &"d")));
+    }
+
+    #[test]
+    fn remove_and_nearest() {
+        let mut t = grid();
+        assert_eq!(t.nearest(Point::new(12, 12)), Some((EntryId(0), 8.0)));
+        assert_eq!(t.nearest(Point::new(80, 80)), Some((EntryId(3), 200.0)));
+        assert_eq!(t.remove(EntryId(0)), Some((Rect::new(0, 0, 10, 10), "a")));
+        assert_eq!(t.remove(EntryId(0)), None);
+        assert_eq!(t.len(), 3);
+        assert_eq!(t.nearest(Point::new(12, 12)), Some((EntryId(1), 128.0)));
+        assert_eq!(t.overlapping_pairs(), vec![(EntryId(1), EntryId(2))]);
+    }
+
+    #[test]
+    fn many_items_split_and_outside_root() {
+        let mut t = QuadTree::new(Rect::new(0.0, 0.0, 64.0, 64.0));
+        for i in 0..64 {
+            let (x, y) = ((i % 8) as f64 * 8.0, (i / 8) as f64 * 8.0);
+            t.insert(Rect::new(x, y, 4.0, 4.0), i);
+        }
+        assert!(t.root.children.is_some());
+        // 根の外にあるもの
+        let outside = t.insert(Rect::new(200.0, 200.0, 1.0, 1.0), 64);
+        assert_eq!(t.query(&Rect::new(190.0, 190.0, 20.0, 20.0)), vec![outside]);
+        assert_eq!(
+            t.nearest(Point::new(300.0, 300.0)).map(|n| n.0),
+            Some(outside)
+        );
+        assert_eq!(t.query(&Rect::new(7.0, 7.0, 2.0, 2.0)), vec![EntryId(9)]);
+        for i in 0..65 {
+            assert!(t.remove(EntryId(i)).is_some());
+        }
+        assert!(t.is_empty());
+        assert_eq!(t.nearest(Point::new(1.0, 1.0)), None);
+    }
+
+    // 根(0..256)の外にはみ出すものも混ぜた k 番目の長方形
+    fn scattered(k: i64) -> Rect<i64> {
+        Rect::new(
+            k * 97 % 320 - 32,
+            k * 61 % 320 - 32,
+            k * 13 % 40,
+            k * 29 % 40,
+        )
+    }
+
+    #[test]
+    fn matches_brute_force_under_inserts_and_removes() {
+        let mut tree = QuadTree::new(Rect::new(0, 0, 256, 256));
+        let mut alive: Vec<Option<Rect<i64>>> = Vec::new();
+        for k in 0..400 {
+            // 4回に1回、前に入れたものを消す(消したものをもう一度消すこともある)
+            if k % 4 == 3 {
+                let i = (k * 7 % alive.len() as i64) as usize;
+                assert_eq!(tree.remove(EntryId(i)).map(|(r, _)| r), alive[i].take());
+            } else {
+                let r = scattered(k);
+                assert_eq!(tree.insert(r, ()), EntryId(alive.len()));
+                alive.push(Some(r));
+            }
+            if k % 50 != 49 {
+                continue;
+            }
+            assert_eq!(tree.len(), alive.iter().flatten().count());
+            let living = || {
+                alive
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, r)| r.map(|r| (EntryId(i), r)))
+            };
+            for q in 0..20 {
+                let region = scattered(k + q * 17 + 5);
+                let expected: Vec<EntryId> = living()
+                    .filter(|(_, r)| r.intersects(&region))
+                    .map(|(id, _)| id)
+                    .collect();
+                assert_eq!(tree.query(&region), expected);
+
+                let p = Point::new(q * 37 % 350 - 50, (k + q * 53) % 350 - 50);
+                let inside: Vec<EntryId> = living()
+                    .filter(|(_, r)| r.contains_point(p))
+                    .map(|(id, _)| id)
+                    .collect();
+                assert_eq!(tree.query_point(p), inside);
+
+                let mut nearest: Option<(EntryId, f64)> = None;
+                for (id, r) in living() {
+                    let d = r.distance_squared_to(p);
+                    if nearest.is_none_or(|(_, b)| d < b) {
+                        nearest = Some((id, d));
+                    }
+                }
+                assert_eq!(tree.nearest(p), nearest);
+            }
+        }
+    }
+}
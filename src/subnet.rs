@@ -0,0 +1,558 @@
+// CIDR のサブネット計算
+// ./ip.rs のアドレスに接頭辞長を付けたもの(192.168.1.10/24, 2001:db8::/32 など)
+// ネットワークアドレス, ブロードキャスト, ネットマスク, ホストの範囲と数を求め、
+// 分割(/24 を /26 4つに)と集約(隣り合うものを1つに)と所属の判定をする
+// 中では IPv4 も IPv6 も u128 の数として計算する(IPv4 は下位32bit だけを使う)
+
+use crate::ip::{IpAddress, IpError, Ipv4, Ipv6, Scope};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubnetError {
+    Address(IpError),
+    MissingPrefix(String),
+    InvalidPrefix { input: String, max: u8 },
+}
+
+impl fmt::Display for SubnetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubnetError::Address(e) => e.fmt(f),
+            SubnetError::MissingPrefix(s) => {
+                write!(f, "`{}` に接頭辞長(/24 など)がありません", s)
+            }
+            SubnetError::InvalidPrefix { input, max } => {
+                write!(f, "接頭辞長 `{}` は 0 ~ {} の数ではありません", input, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SubnetError {}
+
+impl From<IpError> for SubnetError {
+    fn from(e: IpError) -> SubnetError {
+        SubnetError::Address(e)
+    }
+}
+
+/// アドレスと接頭辞長の組
+/// アドレスは渡されたまま持つ(192.168.1.10/24 のようにホスト部が 0 でなくてもよい)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IpNet {
+    addr: IpAddress,
+    prefix: u8,
+}
+
+impl IpNet {
+    pub fn new(addr: IpAddress, prefix: u8) -> Result<IpNet, SubnetError> {
+        let max = max_prefix(&addr);
+        if prefix > max {
+            return Err(SubnetError::InvalidPrefix {
+                input: prefix.to_string(),
+                max,
+            });
+        }
+        Ok(IpNet { addr, prefix })
+    }
+
+    /// `アドレス/接頭辞長` を読む
+    pub fn parse(s: &str) -> Result<IpNet, SubnetError> {
+        let (addr, prefix) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| SubnetError::MissingPrefix(String::from(s)))?;
+        let addr = IpAddress::parse(addr)?;
+        let max = max_prefix(&addr);
+        let invalid = || SubnetError::InvalidPrefix {
+            input: String::from(prefix),
+            max,
+        };
+        if prefix.is_empty() || !prefix.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+        IpNet::new(addr, prefix).map_err(|_| invalid())
+    }
+
+    pub fn address(&self) -> IpAddress {
+        self.addr
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    pub fn max_prefix(&self) -> u8 {
+        max_prefix(&self.addr)
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        matches!(self.addr, IpAddress::V4(_))
+    }
+
+    /// ホスト部を 0 にしたもの(192.168.1.10/24 -> 192.168.1.0/24)
+    pub fn trunc(&self) -> IpNet {
+        IpNet {
+            addr: self.network(),
+            prefix: self.prefix,
+        }
+    }
+
+    pub fn network(&self) -> IpAddress {
+        self.make(self.first_raw())
+    }
+
+    /// 範囲の最後のアドレス
+    pub fn last(&self) -> IpAddress {
+        self.make(self.last_raw())
+    }
+
+    pub fn netmask(&self) -> IpAddress {
+        self.make(self.mask())
+    }
+
+    /// ホスト部のマスク(ワイルドカードマスク)
+    pub fn hostmask(&self) -> IpAddress {
+        self.make(!self.mask() & self.all_ones())
+    }
+
+    /// IPv4 で /30 以下のときのブロードキャストアドレス
+    /// /31(RFC 3021 の2点間リンク)と /32, IPv6 にはない
+    pub fn broadcast(&self) -> Option<IpAddress> {
+        if self.is_ipv4() && self.prefix <= 30 {
+            Some(self.last())
+        } else {
+            None
+        }
+    }
+
+    /// ホストに使える最初と最後のアドレス
+    /// IPv4 の /30 以下ではネットワークアドレスとブロードキャストを除く。IPv6 は全部使える
+    pub fn hosts(&self) -> (IpAddress, IpAddress) {
+        if self.broadcast().is_some() {
+            (
+                self.make(self.first_raw() + 1),
+                self.make(self.last_raw() - 1),
+            )
+        } else {
+            (self.network(), self.last())
+        }
+    }
+
+    /// 範囲にあるアドレスの数(IPv6 の /0 は u128 に収まらないので u128::MAX にする)
+    pub fn address_count(&self) -> u128 {
+        let host_bits = (self.max_prefix() - self.prefix) as u32;
+        1u128.checked_shl(host_bits).unwrap_or(u128::MAX)
+    }
+
+    /// ホストに使えるアドレスの数
+    pub fn host_count(&self) -> u128 {
+        if self.broadcast().is_some() {
+            self.address_count() - 2
+        } else {
+            self.address_count()
+        }
+    }
+
+    /// 範囲の全部のアドレスが同じ種類ならその種類
+    /// 0.0.0.0/0 や 10.0.0.0/7 のように種類の違う区域をまたぐなら None
+    pub fn scope(&self) -> Option<Scope> {
+        let blocks = if self.is_ipv4() { V4_SCOPES } else { V6_SCOPES };
+        // 区域も CIDR なので、この範囲と重なる区域はこの範囲を含むか、この範囲に含まれるかのどちらか
+        // 含まれる(接頭辞がこちらより長い)ものが1つでもあれば種類が混ざる
+        let mixed = blocks
+            .iter()
+            .any(|&(n, p)| p > self.prefix && n & self.mask() == self.first_raw());
+        if mixed {
+            None
+        } else {
+            Some(self.network().scope())
+        }
+    }
+
+    /// addr がこの範囲にあるか(IPv4 と IPv6 は別物として扱う)
+    pub fn contains(&self, addr: &IpAddress) -> bool {
+        same_family(&self.addr, addr) && raw(addr) & self.mask() == self.first_raw()
+    }
+
+    /// other がまるごとこの範囲にあるか
+    pub fn contains_net(&self, other: &IpNet) -> bool {
+        other.prefix >= self.prefix && self.contains(&other.addr)
+    }
+
+    /// 1つ短い接頭辞の(この範囲を含む)ネットワーク。/0 なら None
+    pub fn supernet(&self) -> Option<IpNet> {
+        let prefix = self.prefix.checked_sub(1)?;
+        Some(
+            IpNet {
+                addr: self.addr,
+                prefix,
+            }
+            .trunc(),
+        )
+    }
+
+    /// new_prefix の大きさに分割したものを順に返す(/24 を 26 で分けると /26 が4つ)
+    pub fn subnets(&self, new_prefix: u8) -> Result<Subnets, SubnetError> {
+        if new_prefix < self.prefix || new_prefix > self.max_prefix() {
+            return Err(SubnetError::InvalidPrefix {
+                input: new_prefix.to_string(),
+                max: self.max_prefix(),
+            });
+        }
+        let step = 1u128.checked_shl((self.max_prefix() - new_prefix) as u32);
+        Ok(Subnets {
+            v4: self.is_ipv4(),
+            prefix: new_prefix,
+            next: Some(self.first_raw()),
+            last: self.last_raw(),
+            step,
+        })
+    }
+
+    fn all_ones(&self) -> u128 {
+        if self.is_ipv4() {
+            u32::MAX as u128
+        } else {
+            u128::MAX
+        }
+    }
+
+    fn mask(&self) -> u128 {
+        let host_bits = (self.max_prefix() - self.prefix) as u32;
+        let host = 1u128.checked_shl(host_bits).map_or(u128::MAX, |n| n - 1);
+        !host & self.all_ones()
+    }
+
+    fn first_raw(&self) -> u128 {
+        raw(&self.addr) & self.mask()
+    }
+
+    fn last_raw(&self) -> u128 {
+        self.first_raw() | (!self.mask() & self.all_ones())
+    }
+
+    fn make(&self, n: u128) -> IpAddress {
+        make(self.is_ipv4(), n)
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = SubnetError;
+
+    fn from_str(s: &str) -> Result<IpNet, SubnetError> {
+        IpNet::parse(s)
+    }
+}
+
+/// IpNet::subnets の結果
+#[derive(Debug, Clone)]
+pub struct Subnets {
+    v4: bool,
+    prefix: u8,
+    next: Option<u128>,
+    last: u128,
+    step: Option<u128>, // None は分割しない(/0 のまま)とき
+}
+
+impl Iterator for Subnets {
+    type Item = IpNet;
+
+    fn next(&mut self) -> Option<IpNet> {
+        let current = self.next.filter(|&n| n <= self.last)?;
+        self.next = self.step.and_then(|s| current.checked_add(s));
+        Some(IpNet {
+            addr: make(self.v4, current),
+            prefix: self.prefix,
+        })
+    }
+}
+
+/// 重なっているものや隣り合っているものをまとめて、同じ範囲を表す一番少ない CIDR の並びにする
+/// IPv4 を先に、それぞれアドレスの小さい順に並べて返す
+pub fn aggregate(nets: &[IpNet]) -> Vec<IpNet> {
+    let mut out = Vec::new();
+    for &v4 in &[true, false] {
+        let mut ranges: Vec<(u128, u128)> = nets
+            .iter()
+            .filter(|n| n.is_ipv4() == v4)
+            .map(|n| (n.first_raw(), n.last_raw()))
+            .collect();
+        ranges.sort_unstable();
+        let mut merged: Vec<(u128, u128)> = Vec::new();
+        for (first, last) in ranges {
+            match merged.last_mut() {
+                // 重なるか、すぐ隣(前の最後 + 1 が次の最初)ならつなぐ
+                Some(prev) if prev.1 == u128::MAX || first <= prev.1 + 1 => {
+                    prev.1 = prev.1.max(last)
+                }
+                _ => merged.push((first, last)),
+            }
+        }
+        for (first, last) in merged {
+            out.extend(range_to_nets(v4, first, last));
+        }
+    }
+    out
+}
+
+/// first から last まで(両端を含む)をちょうど覆う CIDR の並び(first > last なら空)
+pub fn range_to_nets(v4: bool, mut first: u128, last: u128) -> Vec<IpNet> {
+    let bits: u32 = if v4 { 32 } else { 128 };
+    let mut out = Vec::new();
+    if first > last {
+        return out;
+    }
+    loop {
+        // first から始められる一番大きいブロック(first の末尾の 0 の数まで)を、last を越えない大きさまで縮める
+        let mut size_bits = first.trailing_zeros().min(bits);
+        while size_bits > 0 && block_last(first, size_bits) > last {
+            size_bits -= 1;
+        }
+        out.push(IpNet {
+            addr: make(v4, first),
+            prefix: (bits - size_bits) as u8,
+        });
+        let end = block_last(first, size_bits);
+        if end >= last {
+            break;
+        }
+        first = end + 1;
+    }
+    out
+}
+
+// first から 2^size_bits 個のブロックの最後
+fn block_last(first: u128, size_bits: u32) -> u128 {
+    match 1u128.checked_shl(size_bits) {
+        Some(n) => first.saturating_add(n - 1),
+        None => u128::MAX,
+    }
+}
+
+// Global でない種類の区域(ip の is_* と同じ範囲, u128 の数と接頭辞長)
+const V4_SCOPES: &[(u128, u8)] = &[
+    (0, 32),           // 0.0.0.0
+    (0x7f00_0000, 8),  // 127.0.0.0/8
+    (0x0a00_0000, 8),  // 10.0.0.0/8
+    (0xac10_0000, 12), // 172.16.0.0/12
+    (0xc0a8_0000, 16), // 192.168.0.0/16
+    (0xa9fe_0000, 16), // 169.254.0.0/16
+    (0xe000_0000, 4),  // 224.0.0.0/4
+    (0xffff_ffff, 32), // 255.255.255.255
+    (0xc000_0200, 24), // 192.0.2.0/24
+    (0xc633_6400, 24), // 198.51.100.0/24
+    (0xcb00_7100, 24), // 203.0.113.0/24
+];
+const V6_SCOPES: &[(u128, u8)] = &[
+    (0, 128),                // ::
+    (1, 128),                // ::1
+    (0xfc << 120, 7),        // fc00::/7
+    (0xfe80 << 112, 10),     // fe80::/10
+    (0xff << 120, 8),        // ff00::/8
+    (0x2001_0db8 << 96, 32), // 2001:db8::/32
+];
+
+fn max_prefix(addr: &IpAddress) -> u8 {
+    match addr {
+        IpAddress::V4(_) => 32,
+        IpAddress::V6(_) => 128,
+    }
+}
+
+fn same_family(a: &IpAddress, b: &IpAddress) -> bool {
+    matches!(
+        (a, b),
+        (IpAddress::V4(_), IpAddress::V4(_)) | (IpAddress::V6(_), IpAddress::V6(_))
+    )
+}
+
+fn raw(addr: &IpAddress) -> u128 {
+    match addr {
+        IpAddress::V4(a) => a.to_u32() as u128,
+        IpAddress::V6(a) => a.to_u128(),
+    }
+}
+
+fn make(v4: bool, n: u128) -> IpAddress {
+    if v4 {
+        IpAddress::V4(Ipv4::from_u32(n as u32))
+    } else {
+        IpAddress::V6(Ipv6::from_u128(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(s: &str) -> IpNet {
+        IpNet::parse(s).unwrap()
+    }
+
+    fn addr(s: &str) -> IpAddress {
+        IpAddress::parse(s).unwrap()
+    }
+
+    #[test]
+    fn v4_basics() {
+        let n = net("192.168.1.10/24");
+        assert_eq!(n.network(), addr("192.168.1.0"));
+        assert_eq!(n.netmask(), addr("255.255.255.0"));
+        assert_eq!(n.hostmask(), addr("0.0.0.255"));
+        assert_eq!(n.broadcast(), Some(addr("192.168.1.255")));
+        assert_eq!(n.hosts(), (addr("192.168.1.1"), addr("192.168.1.254")));
+        assert_eq!(n.host_count(), 254);
+        assert_eq!(n.trunc().to_string(), "192.168.1.0/24");
+        assert!(n.contains(&addr("192.168.1.200")));
+        assert!(!n.contains(&addr("192.168.2.1")));
+        assert!(!n.contains(&addr("::ffff:192.168.1.1")));
+    }
+
+    #[test]
+    fn v4_edges() {
+        let p2p = net("10.0.0.0/31");
+        assert_eq!(p2p.broadcast(), None);
+        assert_eq!(p2p.host_count(), 2);
+        assert_eq!(p2p.hosts(), (addr("10.0.0.0"), addr("10.0.0.1")));
+        let single = net("10.0.0.7/32");
+        assert_eq!(single.host_count(), 1);
+        assert_eq!(single.hosts(), (addr("10.0.0.7"), addr("10.0.0.7")));
+        let all = net("0.0.0.0/0");
+        assert_eq!(all.netmask(), addr("0.0.0.0"));
+        assert_eq!(all.address_count(), 1 << 32);
+        assert!(all.contains(&addr("255.255.255.255")));
+    }
+
+    #[test]
+    fn v6_basics() {
+        let n = net("2001:db8:abcd:12::1/64");
+        assert_eq!(n.network(), addr("2001:db8:abcd:12::"));
+        assert_eq!(n.last(), addr("2001:db8:abcd:12:ffff:ffff:ffff:ffff"));
+        assert_eq!(n.netmask(), addr("ffff:ffff:ffff:ffff::"));
+        assert_eq!(n.broadcast(), None);
+        assert_eq!(n.host_count(), 1 << 64);
+        assert_eq!(net("::/0").address_count(), u128::MAX);
+        assert!(net("2001:db8::/32").contains_net(&n));
+        assert!(!n.contains_net(&net("2001:db8::/32")));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            IpNet::parse("10.0.0.0"),
+            Err(SubnetError::MissingPrefix(String::from("10.0.0.0")))
+        );
+        assert_eq!(
+            IpNet::parse("10.0.0.0/33").unwrap_err().to_string(),
+            "接頭辞長 `33` は 0 ~ 32 の数ではありません"
+        );
+        assert!(IpNet::parse("::/129").is_err());
+        assert!(IpNet::parse("10.0.0.0/+8").is_err());
+        assert!(matches!(
+            IpNet::parse("10.0.0/8"),
+            Err(SubnetError::Address(_))
+        ));
+    }
+
+    #[test]
+    fn split_and_supernet() {
+        let parts: Vec<String> = net("192.168.0.0/24")
+            .subnets(26)
+            .unwrap()
+            .map(|n| n.to_string())
+            .collect();
+        assert_eq!(
+            parts,
+            [
+                "192.168.0.0/26",
+                "192.168.0.64/26",
+                "192.168.0.128/26",
+                "192.168.0.192/26"
+            ]
+        );
+        assert_eq!(net("255.255.255.0/24").subnets(25).unwrap().count(), 2);
+        assert_eq!(net("::/0").subnets(0).unwrap().count(), 1);
+        assert_eq!(net("ffff::/16").subnets(18).unwrap().count(), 4);
+        assert!(net("10.0.0.0/8").subnets(7).is_err());
+        assert_eq!(
+            net("192.168.1.0/24").supernet(),
+            Some(net("192.168.0.0/23"))
+        );
+        assert_eq!(net("0.0.0.0/0").supernet(), None);
+    }
+
+    #[test]
+    fn aggregation() {
+        let nets = [
+            net("10.0.1.0/24"),
+            net("10.0.0.0/24"),
+            net("10.0.2.0/25"),
+            net("10.0.2.128/25"),
+            net("10.0.3.5/32"),
+            net("10.0.0.0/23"), // 重なっている
+            net("2001:db8::/33"),
+            net("2001:db8:8000::/33"),
+        ];
+        let merged: Vec<String> = aggregate(&nets).iter().map(|n| n.to_string()).collect();
+        assert_eq!(
+            merged,
+            ["10.0.0.0/23", "10.0.2.0/24", "10.0.3.5/32", "2001:db8::/32"]
+        );
+        // 揃っていない範囲は複数に分かれる
+        let r: Vec<String> = range_to_nets(true, 1, 6)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        assert_eq!(r, ["0.0.0.1/32", "0.0.0.2/31", "0.0.0.4/31", "0.0.0.6/32"]);
+        // 逆向きの範囲は空
+        assert_eq!(range_to_nets(true, 7, 6), []);
+        assert_eq!(range_to_nets(false, u128::MAX, 0), []);
+        assert_eq!(range_to_nets(true, 6, 6), [net("0.0.0.6/32")]);
+        assert_eq!(aggregate(&[net("::/1"), net("8000::/1")]), [net("::/0")]);
+        assert_eq!(
+            aggregate(&[net("0.0.0.0/1"), net("128.0.0.0/1")]),
+            [net("0.0.0.0/0")]
+        );
+    }
+
+    #[test]
+    fn scope_of_whole_blocks() {
+        assert_eq!(net("10.1.0.0/16").scope(), Some(Scope::Private));
+        assert_eq!(net("127.0.0.0/8").scope(), Some(Scope::Loopback));
+        assert_eq!(net("8.8.8.0/24").scope(), Some(Scope::Global));
+        assert_eq!(net("0.0.0.0/32").scope(), Some(Scope::Unspecified));
+        assert_eq!(net("fe80::/64").scope(), Some(Scope::LinkLocal));
+        // 種類の違う区域をまたぐもの(ネットワークアドレスや両端が同じ種類でも)
+        assert_eq!(net("0.0.0.0/0").scope(), None);
+        assert_eq!(net("0.0.0.0/31").scope(), None);
+        assert_eq!(net("10.0.0.0/7").scope(), None);
+        assert_eq!(net("8.0.0.0/6").scope(), None);
+        assert_eq!(net("::/0").scope(), None);
+        assert_eq!(net("2001::/16").scope(), None);
+        // 表の区域は ip の種類と食い違わない
+        for &(n, p) in V4_SCOPES {
+            let block = IpNet::new(make(true, n), p).unwrap();
+            assert_ne!(block.scope(), Some(Scope::Global), "{}", block);
+            assert!(block.scope().is_some(), "{}", block);
+            assert_ne!(block.last().scope(), Scope::Global, "{}", block);
+        }
+        for &(n, p) in V6_SCOPES {
+            let block = IpNet::new(make(false, n), p).unwrap();
+            assert!(
+                block.scope().is_some_and(|s| s != Scope::Global),
+                "{}",
+                block
+            );
+            assert_ne!(block.last().scope(), Scope::Global, "{}", block);
+        }
+    }
+}
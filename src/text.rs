@@ -0,0 +1,293 @@
+// 文字列まわりの小道具
+// 入力を変える必要がなければ借用のまま(Cow::Borrowed)返し、変えたときだけ String を確保(Cow::Owned)する
+// 何百万語も流すバッチ処理で 変わらない単語のために毎回 String を作らないようにするため
+//
+// Cow(Clone on Write) は「借用かもしれないし所有かもしれない」値を表す std の列挙型
+//     enum Cow<'a, B> { Borrowed(&'a B), Owned(<B as ToOwned>::Owned) }
+// &str として使う分には Deref が効くのでどちらかを気にする必要はない
+
+use std::borrow::Cow;
+
+/// 最初の単語(先頭の空白を飛ばして次の空白まで)
+/// ./others/ownership.rs の first_word2 と同じく元の文字列のスライスを返すので確保はしない
+pub fn first_word(s: &str) -> &str {
+    let s = s.trim_start();
+    &s[..s.find(char::is_whitespace).unwrap_or(s.len())]
+}
+
+/// 小文字にする。大文字が1つもなければ借用のまま
+pub fn lowercase(s: &str) -> Cow<'_, str> {
+    if s.chars().any(|c| !c.to_lowercase().eq(std::iter::once(c))) {
+        Cow::Owned(s.to_lowercase())
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// 大文字にする。小文字が1つもなければ借用のまま
+pub fn uppercase(s: &str) -> Cow<'_, str> {
+    if s.chars().any(|c| !c.to_uppercase().eq(std::iter::once(c))) {
+        Cow::Owned(s.to_uppercase())
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// 先頭の1文字だけ大文字にする(hello -> Hello)。既に大文字なら借用のまま
+pub fn capitalize(s: &str) -> Cow<'_, str> {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) if !first.to_uppercase().eq(std::iter::once(first)) => {
+            let mut out = String::with_capacity(s.len() + 2);
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+            Cow::Owned(out)
+        }
+        _ => Cow::Borrowed(s),
+    }
+}
+
+/// 前後の空白を削って、連続する空白(タブや改行を含む)を半角空白1つにまとめる
+/// 既にそうなっていれば借用のまま
+pub fn collapse_whitespace(s: &str) -> Cow<'_, str> {
+    let trimmed = s.trim();
+    let mut prev_space = false;
+    let tidy = trimmed.chars().all(|c| {
+        let ok = if c.is_whitespace() {
+            c == ' ' && !prev_space
+        } else {
+            true
+        };
+        prev_space = c.is_whitespace();
+        ok
+    });
+    if tidy {
+        return Cow::Borrowed(trimmed);
+    }
+    let mut out = String::with_capacity(trimmed.len());
+    for word in trimmed.split_whitespace() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    Cow::Owned(out)
+}
+
+/// 1単語のピッグ・ラテン変換(./others/collections.rs の pig_latin::word_to の書き直し)
+/// 母音で始まれば末尾に hay、子音で始まれば先頭の1文字を後ろに回して ay を付ける
+/// 英字で始まらない単語(空文字列, 数字, 漢字など)は変換せず借用のまま返す
+/// 元の word_to は空文字列で unwrap がパニックし、バイト長を文字数として take に渡していた
+/// 子音の並び(string → ingstray)や文ごとの変換, 逆変換は pig_latin にある
+/// (word_to と同じ答えを1回の確保で出すためのものなので、pig_latin の規則には合わせない)
+pub fn pig_latin_word(word: &str) -> Cow<'_, str> {
+    let first = match word.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => c,
+        _ => return Cow::Borrowed(word),
+    };
+    let mut out = String::with_capacity(word.len() + 3);
+    if "AaIiUuEeOo".contains(first) {
+        out.push_str(word);
+        out.push_str("hay");
+    } else {
+        out.push_str(&word[first.len_utf8()..]); // 先頭は ASCII なので1バイト
+        out.push(first);
+        out.push_str("ay");
+    }
+    Cow::Owned(out)
+}
+
+/// 名指しで挨拶する(adder の greeting と greeting_ii をまとめたもの)
+/// 名前が空なら決まり文句をそのまま返すので確保しない
+pub fn greeting(name: &str) -> Cow<'static, str> {
+    const PLAIN: &str = "こんちゃ!";
+    let name = name.trim();
+    if name.is_empty() {
+        return Cow::Borrowed(PLAIN);
+    }
+    let mut out = String::with_capacity("こんちゃ ".len() + name.len() + 1);
+    out.push_str("こんちゃ ");
+    out.push_str(name);
+    out.push('!');
+    Cow::Owned(out)
+}
+
+/// 文字列を等幅端末に表示したときの幅(全角文字は2つ分)
+pub fn display_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// 1文字の表示幅
+pub fn char_width(c: char) -> usize {
+    if is_wide(c) {
+        2
+    } else {
+        1
+    }
+}
+
+/// 全角(東アジアの幅広)文字か
+pub fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD)
+}
+
+/// 表を等幅の文字で組む(1行目が見出しで、その下に罫線を引く)
+/// 列の幅は全角文字を2つ分として揃える。中身が全部数(1,234 や 12.5% など)の列は右に寄せる
+pub fn table<S: AsRef<str>>(rows: &[Vec<S>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    fn cell<S: AsRef<str>>(row: &[S], c: usize) -> &str {
+        row.get(c).map_or("", |s| s.as_ref())
+    }
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| {
+            rows.iter()
+                .map(|r| display_width(cell(r, c)))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let numeric: Vec<bool> = (0..columns)
+        .map(|c| rows.len() > 1 && rows[1..].iter().all(|r| is_number(cell(r, c))))
+        .collect();
+    let mut out = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        let mut line = String::new();
+        for c in 0..columns {
+            let s = cell(row, c);
+            let pad = " ".repeat(widths[c] - display_width(s));
+            if c > 0 {
+                line.push_str("  ");
+            }
+            if numeric[c] && i > 0 {
+                line.push_str(&pad);
+                line.push_str(s);
+            } else {
+                line.push_str(s);
+                line.push_str(&pad);
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+        if i == 0 {
+            let rule: Vec<String> = widths.iter().map(|&w| "─".repeat(w)).collect();
+            out.push_str(&rule.join("  "));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+// 表で右に寄せる数か(空, 1,234, -5, 12.5% など。小数点が2つ以上あるもの(IP アドレスなど)は数としない)
+fn is_number(s: &str) -> bool {
+    s.is_empty()
+        || (s.chars().any(|c| c.is_ascii_digit())
+            && s.chars().all(|c| c.is_ascii_digit() || ",.-+%".contains(c))
+            && s.matches('.').count() <= 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_word_is_a_slice() {
+        assert_eq!(first_word("Page not found"), "Page");
+        assert_eq!(first_word("  AB ab"), "AB");
+        assert_eq!(first_word("single"), "single");
+        assert_eq!(first_word(""), "");
+    }
+
+    #[test]
+    fn case_helpers_borrow_when_unchanged() {
+        let lower = lowercase("hello world");
+        assert!(matches!(lower, Cow::Borrowed(_)));
+        let changed = lowercase("Hello");
+        assert!(!matches!(changed, Cow::Borrowed(_)));
+        assert_eq!(changed, "hello");
+
+        assert!(matches!(uppercase("ABC 123"), Cow::Borrowed(_)));
+        assert_eq!(uppercase("abc"), "ABC");
+
+        assert!(matches!(capitalize("Rust"), Cow::Borrowed(_)));
+        assert!(matches!(capitalize(""), Cow::Borrowed(_)));
+        assert!(matches!(capitalize("贵樣"), Cow::Borrowed(_)));
+        assert_eq!(capitalize("rust"), "Rust");
+    }
+
+    #[test]
+    fn collapse_whitespace_borrows_tidy_input() {
+        assert!(matches!(
+            collapse_whitespace("hello world"),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            collapse_whitespace("  hello world  "),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(collapse_whitespace("hello \t  world\n"), "hello world");
+        assert!(!matches!(collapse_whitespace("a\tb"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn pig_latin_word_matches_notebook() {
+        assert_eq!(pig_latin_word("hello"), "ellohay");
+        assert_eq!(pig_latin_word("japan"), "apanjay");
+        assert_eq!(pig_latin_word("enum"), "enumhay");
+        assert_eq!(pig_latin_word("open"), "openhay");
+    }
+
+    #[test]
+    fn pig_latin_word_leaves_non_words_alone() {
+        for word in &["", "123", "贵樣", "!?"] {
+            let out = pig_latin_word(word);
+            assert!(matches!(out, Cow::Borrowed(_)));
+            assert_eq!(out, *word);
+        }
+    }
+
+    #[test]
+    fn greeting_without_name_is_static() {
+        assert!(matches!(greeting(""), Cow::Borrowed(_)));
+        assert_eq!(greeting(" "), "こんちゃ!");
+        assert_eq!(greeting("もるか"), "こんちゃ もるか!");
+    }
+
+    #[test]
+    fn display_width_counts_wide_chars_twice() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("贵樣"), 4);
+        assert_eq!(display_width("ｱｲｳ"), 3); // 半角カナは1つ分
+        assert_eq!(display_width("ＡＢ"), 4);
+    }
+
+    #[test]
+    fn table_aligns_wide_text_and_numbers() {
+        let rows = vec![
+            vec!["名前", "数"],
+            vec!["りんご", "1,200"],
+            vec!["pear", "35"],
+        ];
+        assert_eq!(
+            table(&rows),
+            "名前    数\n──────  ─────\nりんご  1,200\npear       35\n"
+        );
+        assert_eq!(table::<&str>(&[]), "");
+        // IP アドレスは数ではないので左寄せのまま
+        let ips = vec![vec!["addr"], vec!["10.0.0.1"], vec!["192.168.0.1"]];
+        assert_eq!(table(&ips), "addr\n───────────\n10.0.0.1\n192.168.0.1\n");
+    }
+}
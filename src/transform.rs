@@ -0,0 +1,578 @@
+// 文字列の変換器とそのパイプライン
+// pig_latin のような「文字列を受け取って文字列を返す」変換を Transformer トレイトにそろえ、
+// Pipeline で順につなぐ(自分で Transformer を実装した型も混ぜられる)
+//
+//     let p = Pipeline::parse("pig-latin,rot13")?;   // 名前で組み立てる(transform --with と同じ書き方)
+//     let p = Pipeline::new().then(Upper).then(Rot13); // 型で組み立てる
+//     p.apply("Hello, World!")
+//
+// 組み込みの変換は BUILTINS の名前で呼べる
+// text の小道具と同じく、変えなくてよい入力は借用のまま(Cow::Borrowed)返して確保しない
+
+use crate::pig_latin::Translator;
+use crate::text;
+use std::borrow::Cow;
+use std::fmt;
+
+/// 文字列の変換
+pub trait Transformer {
+    /// Pipeline::parse や transform --with で使う名前
+    fn name(&self) -> &str;
+    /// s を変換する(変えなくてよければ借用のまま返す。trim のように s の一部を借用で返してもよい)
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransformError {
+    Unknown(String), // そういう名前の変換はない
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransformError::Unknown(name) => {
+                let names: Vec<&str> = BUILTINS.iter().map(|(n, _)| *n).collect();
+                write!(
+                    f,
+                    "{:?} という変換はありません(使えるのは {})",
+                    name,
+                    names.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransformError {}
+
+/// 組み込みの変換の名前と説明
+pub const BUILTINS: &[(&str, &str)] = &[
+    ("pig-latin", "ピッグ・ラテン(Hello → Ellohay)"),
+    ("rot13", "英字を13文字ずらす(2回で元に戻る)"),
+    ("leet", "リートスピーク(leet → l337)"),
+    ("reverse-words", "単語の順番を逆に(空白はそのまま)"),
+    ("upper", "大文字に"),
+    ("lower", "小文字に"),
+    ("title", "単語の先頭だけ大文字に"),
+    ("full-width", "半角を全角に(英数字, 記号, 空白, カタカナ)"),
+    ("half-width", "全角を半角に(full-width の逆)"),
+];
+
+/// 名前から組み込みの変換を作る
+pub fn by_name(name: &str) -> Result<Box<dyn Transformer>, TransformError> {
+    Ok(match name {
+        "pig-latin" => Box::new(PigLatin::default()),
+        "rot13" => Box::new(Rot13),
+        "leet" => Box::new(Leet),
+        "reverse-words" => Box::new(ReverseWords),
+        "upper" => Box::new(Upper),
+        "lower" => Box::new(Lower),
+        "title" => Box::new(Title),
+        "full-width" => Box::new(FullWidth),
+        "half-width" => Box::new(HalfWidth),
+        _ => return Err(TransformError::Unknown(name.to_string())),
+    })
+}
+
+/// 変換を順につないだもの(何もつながなければそのまま返す)
+/// Pipeline 自身も Transformer なので、入れ子にもできる
+#[derive(Default)]
+pub struct Pipeline {
+    steps: Vec<Box<dyn Transformer>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// "pig-latin,rot13" のようにカンマ区切りの名前から(前後の空白は無視)
+    pub fn parse(names: &str) -> Result<Pipeline, TransformError> {
+        let mut p = Pipeline::new();
+        for name in names.split(',') {
+            p.push(by_name(name.trim())?);
+        }
+        Ok(p)
+    }
+
+    /// 最後に t をつなぐ
+    pub fn then<T: Transformer + 'static>(mut self, t: T) -> Pipeline {
+        self.push(Box::new(t));
+        self
+    }
+
+    pub fn push(&mut self, t: Box<dyn Transformer>) {
+        self.steps.push(t);
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl Transformer for Pipeline {
+    fn name(&self) -> &str {
+        "pipeline"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let mut current = Cow::Borrowed(s);
+        for t in &self.steps {
+            // 変わったときだけ置き換える(変わらなければ前の段の借用か String をそのまま使う)
+            // 借用でも入力そのもの(同じ先頭で同じ長さ)でなければ変わっているので写す
+            let changed = match t.apply(&current) {
+                Cow::Owned(changed) => Some(changed),
+                Cow::Borrowed(out) if !same(out, &current) => Some(out.to_string()),
+                Cow::Borrowed(_) => None,
+            };
+            if let Some(changed) = changed {
+                current = Cow::Owned(changed);
+            }
+        }
+        current
+    }
+}
+
+fn same(a: &str, b: &str) -> bool {
+    a.as_ptr() == b.as_ptr() && a.len() == b.len()
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self.steps.iter().map(|t| t.name()).collect();
+        write!(f, "Pipeline({})", names.join(" → "))
+    }
+}
+
+/// ピッグ・ラテン(pig_latin::Translator の encode)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PigLatin(pub Translator);
+
+impl Transformer for PigLatin {
+    fn name(&self) -> &str {
+        "pig-latin"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if !s.bytes().any(|b| b.is_ascii_alphabetic()) {
+            return Cow::Borrowed(s);
+        }
+        // 英字が1つでもあれば空ではないので失敗しない
+        Cow::Owned(self.0.encode(s).unwrap_or_else(|_| s.to_string()))
+    }
+}
+
+/// 英字を13文字ずらす(ほかの文字はそのまま)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rot13;
+
+impl Transformer for Rot13 {
+    fn name(&self) -> &str {
+        "rot13"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        map_chars(s, |c| match c {
+            'a'..='z' => Some((b'a' + (c as u8 - b'a' + 13) % 26) as char),
+            'A'..='Z' => Some((b'A' + (c as u8 - b'A' + 13) % 26) as char),
+            _ => None,
+        })
+    }
+}
+
+/// リートスピーク(a e i o s t を似た形の数字に)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Leet;
+
+impl Transformer for Leet {
+    fn name(&self) -> &str {
+        "leet"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        map_chars(s, |c| match c.to_ascii_lowercase() {
+            'a' => Some('4'),
+            'e' => Some('3'),
+            'i' => Some('1'),
+            'o' => Some('0'),
+            's' => Some('5'),
+            't' => Some('7'),
+            _ => None,
+        })
+    }
+}
+
+/// 単語(空白で区切ったもの)の順番を逆に。空白の並びは元の位置のまま
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReverseWords;
+
+impl Transformer for ReverseWords {
+    fn name(&self) -> &str {
+        "reverse-words"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        if words.len() < 2 {
+            return Cow::Borrowed(s);
+        }
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        for word in words.iter().rev() {
+            // 元の文の次の単語の手前までの空白を写して、単語は後ろから入れる
+            let start = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            out.push_str(&rest[..start]);
+            out.push_str(word);
+            rest = &rest[start..];
+            rest = &rest[rest.find(char::is_whitespace).unwrap_or(rest.len())..];
+        }
+        out.push_str(rest);
+        Cow::Owned(out)
+    }
+}
+
+/// 大文字に(text::uppercase)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Upper;
+
+impl Transformer for Upper {
+    fn name(&self) -> &str {
+        "upper"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        text::uppercase(s)
+    }
+}
+
+/// 小文字に(text::lowercase)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lower;
+
+impl Transformer for Lower {
+    fn name(&self) -> &str {
+        "lower"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        text::lowercase(s)
+    }
+}
+
+/// 単語の先頭だけ大文字にして残りは小文字に(hELLO world → Hello World)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Title;
+
+impl Transformer for Title {
+    fn name(&self) -> &str {
+        "title"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let mut out = String::with_capacity(s.len());
+        let mut start = true;
+        for c in s.chars() {
+            if start {
+                out.extend(c.to_uppercase());
+            } else {
+                out.extend(c.to_lowercase());
+            }
+            start = c.is_whitespace();
+        }
+        if out == s {
+            Cow::Borrowed(s)
+        } else {
+            Cow::Owned(out)
+        }
+    }
+}
+
+// 半角カタカナ(U+FF61 ｡ 〜 U+FF9F ﾟ)に順に対応する全角の文字
+const KANA: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+// 濁点(ﾞ)と半濁点(ﾟ)が付けられる文字。濁った字は元の字の次(パ行は次の次)の符号位置にある
+const VOICED: &str = "カキクケコサシスセソタチツテトハヒフヘホ";
+const SEMI_VOICED: &str = "ハヒフヘホ";
+
+fn kana_to_full(c: char) -> Option<char> {
+    KANA.chars().nth((c as u32).checked_sub(0xFF61)? as usize)
+}
+
+fn kana_to_half(c: char) -> Option<char> {
+    let i = KANA.chars().position(|k| k == c)?;
+    char::from_u32(0xFF61 + i as u32)
+}
+
+/// 半角を全角に(ASCII の英数字と記号, 空白, カタカナ)
+/// 半角カタカナの後の ﾞ ﾟ は前の字とまとめて1文字にする(ｶﾞ → ガ, ﾊﾟ → パ, ｳﾞ → ヴ)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FullWidth;
+
+impl Transformer for FullWidth {
+    fn name(&self) -> &str {
+        "full-width"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if s.chars()
+            .all(|c| !matches!(c, ' '..='~' | '\u{FF61}'..='\u{FF9F}'))
+        {
+            return Cow::Borrowed(s);
+        }
+        let mut out = String::with_capacity(s.len() * 3);
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            let full = match c {
+                ' ' => '\u{3000}',
+                '!'..='~' => char::from_u32(c as u32 + 0xFEE0).unwrap_or(c),
+                _ => match kana_to_full(c) {
+                    Some(k) => k,
+                    None => {
+                        out.push(c);
+                        continue;
+                    }
+                },
+            };
+            let mark = chars.peek().copied();
+            let joined = match mark {
+                Some('ﾞ') if full == 'ウ' => Some('ヴ'),
+                Some('ﾞ') if VOICED.contains(full) => char::from_u32(full as u32 + 1),
+                Some('ﾟ') if SEMI_VOICED.contains(full) => char::from_u32(full as u32 + 2),
+                _ => None,
+            };
+            match joined {
+                Some(j) => {
+                    out.push(j);
+                    chars.next();
+                }
+                None => out.push(full),
+            }
+        }
+        Cow::Owned(out)
+    }
+}
+
+/// 全角を半角に(FullWidth の逆, 濁った字は ﾞ ﾟ を分けて2文字にする)
+/// ひらがなや漢字のように半角のない文字はそのまま
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HalfWidth;
+
+impl Transformer for HalfWidth {
+    fn name(&self) -> &str {
+        "half-width"
+    }
+
+    fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let mut out = String::with_capacity(s.len());
+        let mut changed = false;
+        for c in s.chars() {
+            match c {
+                '\u{3000}' => out.push(' '),
+                '\u{FF01}'..='\u{FF5E}' => out.push(char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)),
+                'ヴ' => out.push_str("ｳﾞ"),
+                _ => {
+                    let base = |d: u32| char::from_u32((c as u32).checked_sub(d)?);
+                    if let Some(h) = kana_to_half(c) {
+                        out.push(h);
+                    } else if let Some(h) = base(1)
+                        .filter(|b| VOICED.contains(*b))
+                        .and_then(kana_to_half)
+                    {
+                        out.push(h);
+                        out.push('ﾞ');
+                    } else if let Some(h) = base(2)
+                        .filter(|b| SEMI_VOICED.contains(*b))
+                        .and_then(kana_to_half)
+                    {
+                        out.push(h);
+                        out.push('ﾟ');
+                    } else {
+                        // 半角のない文字
+                        out.push(c);
+                        continue;
+                    }
+                }
+            }
+            changed = true;
+        }
+        if changed {
+            Cow::Owned(out)
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+}
+
+// f が Some を返した文字だけ置き換える(1つも置き換えなければ借用のまま)
+fn map_chars(s: &str, f: impl Fn(char) -> Option<char>) -> Cow<'_, str> {
+    let first = match s.char_indices().find(|&(_, c)| f(c).is_some()) {
+        Some((i, _)) => i,
+        None => return Cow::Borrowed(s),
+    };
+    let mut out = String::with_capacity(s.len());
+    out.push_str(&s[..first]);
+    out.extend(s[first..].chars().map(|c| f(c).unwrap_or(c)));
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(t: &dyn Transformer, s: &str) -> String {
+        t.apply(s).into_owned()
+    }
+
+    #[test]
+    fn builtins_by_name() {
+        for (name, _) in BUILTINS {
+            assert_eq!(by_name(name).unwrap().name(), *name);
+        }
+        assert_eq!(
+            by_name("rot14").err(),
+            Some(TransformError::Unknown(String::from("rot14")))
+        );
+    }
+
+    #[test]
+    fn each_builtin() {
+        assert_eq!(
+            run(&PigLatin::default(), "Hello, World!"),
+            "Ellohay, Orldway!"
+        );
+        assert_eq!(run(&Rot13, "Hello, World! 123"), "Uryyb, Jbeyq! 123");
+        assert_eq!(run(&Leet, "Leet Speak"), "L337 5p34k");
+        assert_eq!(
+            run(&ReverseWords, "  one two\tthree  "),
+            "  three two\tone  "
+        );
+        assert_eq!(run(&Upper, "abc Ä"), "ABC Ä");
+        assert_eq!(run(&Lower, "ABC Ä"), "abc ä");
+        assert_eq!(run(&Title, "hELLO  wide\tworld"), "Hello  Wide\tWorld");
+    }
+
+    #[test]
+    fn width() {
+        assert_eq!(run(&FullWidth, "Rust 2018!"), "Ｒｕｓｔ\u{3000}２０１８！");
+        assert_eq!(
+            run(&FullWidth, "ｶﾞｯﾂﾎﾟｰｽﾞ ｳﾞｨ ﾞ"),
+            "ガッツポーズ\u{3000}ヴィ\u{3000}゛"
+        );
+        assert_eq!(
+            run(&HalfWidth, "ガッツポーズ\u{3000}ヴィ！ひらがな"),
+            "ｶﾞｯﾂﾎﾟｰｽﾞ ｳﾞｨ!ひらがな"
+        );
+        assert_eq!(run(&HalfWidth, "「パン」。"), "｢ﾊﾟﾝ｣｡");
+        // 全角にしてから半角に戻すと元どおり
+        let s = "ﾃﾞｨｽﾌﾟﾚｲ ~ABC@xyz{}";
+        assert_eq!(run(&HalfWidth, &run(&FullWidth, s)), s);
+    }
+
+    #[test]
+    fn unchanged_input_is_borrowed() {
+        for t in BUILTINS.iter().map(|(n, _)| by_name(n).unwrap()) {
+            // 英字も半角も全角の英数字もない1語
+            assert!(
+                matches!(t.apply("贵樣ひらがな"), Cow::Borrowed(_)),
+                "{}",
+                t.name()
+            );
+            assert!(matches!(t.apply(""), Cow::Borrowed(_)), "{}", t.name());
+        }
+        assert!(matches!(Rot13.apply("123"), Cow::Borrowed(_)));
+        assert!(matches!(HalfWidth.apply("ひらがな"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn pipelines() {
+        let p = Pipeline::parse("pig-latin, rot13").unwrap();
+        assert_eq!(p.len(), 2);
+        assert_eq!(run(&p, "Hello"), run(&Rot13, "Ellohay"));
+        assert_eq!(format!("{:?}", p), "Pipeline(pig-latin → rot13)");
+        // rot13 を2回通すと元に戻る
+        let twice = Pipeline::new().then(Rot13).then(Rot13);
+        assert_eq!(run(&twice, "Round trip"), "Round trip");
+        // 入れ子にもできる
+        let nested = Pipeline::new().then(Upper).then(twice);
+        assert_eq!(run(&nested, "abc"), "ABC");
+        assert!(matches!(Pipeline::new().apply("x"), Cow::Borrowed(_)));
+        assert!(Pipeline::parse("upper,,lower").is_err());
+    }
+
+    // 入力の一部を借用で返す変換
+    struct Trim;
+
+    impl Transformer for Trim {
+        fn name(&self) -> &str {
+            "trim"
+        }
+
+        fn apply<'a>(&self, s: &'a str) -> Cow<'a, str> {
+            Cow::Borrowed(s.trim())
+        }
+    }
+
+    #[test]
+    fn borrowed_parts_count_as_changes() {
+        let p = Pipeline::new().then(Trim).then(Upper);
+        assert_eq!(run(&p, "  abc  "), "ABC");
+        let p = Pipeline::new().then(Upper).then(Trim).then(Rot13);
+        assert_eq!(run(&p, " abc "), "NOP");
+        let p = Pipeline::new().then(Trim);
+        assert_eq!(run(&p, " x "), "x");
+        assert!(matches!(p.apply("x"), Cow::Borrowed(_)));
+    }
+
+    // ASCII と半角カタカナ(ﾞ ﾟ だけが続くものも)を混ぜた文
+    const HALF_WIDTH: &[&str] = &[
+        "",
+        "Hello, World! 123",
+        "ｶﾞｯﾂﾎﾟｰｽﾞ ｳﾞｨ ﾞﾟ｡｢ｱ｣",
+        "~!@#$%^&*()_+{}|:\"<>? `-=[]\\;',./",
+        "ﾃﾞｨｽﾌﾟﾚｲ ~ABC@xyz{} ﾟﾟﾞ leet SPEAK",
+    ];
+
+    #[test]
+    fn pipeline_is_steps_in_order() {
+        let names: Vec<&str> = BUILTINS.iter().map(|(n, _)| *n).collect();
+        for s in HALF_WIDTH {
+            for (i, first) in names.iter().enumerate() {
+                // 全部の2段と、そこに後ろから3段目を足したもの
+                for second in &names {
+                    let third = names[names.len() - 1 - i];
+                    for steps in &[vec![*first, *second], vec![*first, *second, third]] {
+                        let p = Pipeline::parse(&steps.join(",")).unwrap();
+                        let mut expected = s.to_string();
+                        for name in steps {
+                            expected = run(by_name(name).unwrap().as_ref(), &expected);
+                        }
+                        assert_eq!(run(&p, s), expected, "{:?} {:?}", steps, s);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let twice = Pipeline::new().then(Rot13).then(Rot13);
+        for s in HALF_WIDTH {
+            assert_eq!(run(&twice, s), *s);
+            let full = run(&FullWidth, s);
+            // 全角にした後は ASCII も半角カタカナも残らない
+            assert!(
+                !full
+                    .chars()
+                    .any(|c| matches!(c, ' '..='~' | '\u{FF61}'..='\u{FF9F}')),
+                "{:?}",
+                full
+            );
+            assert_eq!(run(&HalfWidth, &full), *s);
+        }
+    }
+}
@@ -0,0 +1,713 @@
+// Message の通信用の書き方
+// ../message.rs の Message を道具どうしでやりとりするための2つの書き方と、
+// 少しずつ届くバイト列から Message を取り出す読み手
+//
+// バイナリ(1件 = 1フレーム, 数は全部ビッグエンディアン):
+//   [本体の長さ u32][版 u8][タグ u8][中身]
+//   タグ 0 Quit         中身なし
+//   タグ 1 Move         x i32, y i32
+//   タグ 2 Write        残り全部が UTF-8 の文字列
+//   タグ 3 ChangeColor  r i32, g i32, b i32
+//
+// テキスト(1件 = 1行):
+//   QUIT
+//   MOVE 3 -4
+//   WRITE "hi"          (\" \\ \n \r \t \u{..} でエスケープ)
+//   COLOR 255 0 0
+//   VERSION 1           (これより後の行の版。書かなければ 1)
+//   空行と # で始まる行は読み飛ばす
+//
+// どちらもタグごとに「どの版から有るか」を持っていて、読み手の知らない版やその版に無いタグは断る
+
+use crate::message::{Message, MessageKind};
+use std::fmt;
+
+/// いまの版
+pub const VERSION: u8 = 1;
+
+/// バイナリ1フレームの本体の長さの上限
+pub const MAX_FRAME: usize = 1 << 20;
+
+/// テキスト1行の長さ(バイト)の上限
+pub const MAX_LINE: usize = 1 << 20;
+
+// (種類, バイナリのタグ, テキストのタグ, 入った版)
+const TAGS: [(MessageKind, u8, &str, u8); 4] = [
+    (MessageKind::Quit, 0, "QUIT", 1),
+    (MessageKind::Move, 1, "MOVE", 1),
+    (MessageKind::Write, 2, "WRITE", 1),
+    (MessageKind::ChangeColor, 3, "COLOR", 1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    UnsupportedVersion(u32),
+    UnknownTag { version: u8, tag: String },
+    BadPayload { tag: String, reason: String }, // 中身の長さ, 数, 文字列の書き方がおかしい
+    FrameTooLarge(usize),
+    LineTooLong(usize),
+    Syntax { line: usize, message: String }, // テキストの行番号は1始まり
+    Truncated(usize),                        // 終わったのに読みかけのバイトが残っている
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WireError::UnsupportedVersion(v) => {
+                write!(f, "版 {} は読めません(読めるのは {} まで)", v, VERSION)
+            }
+            WireError::UnknownTag { version, tag } => {
+                write!(f, "版 {} にタグ {} はありません", version, tag)
+            }
+            WireError::BadPayload { tag, reason } => {
+                write!(f, "{} の中身がおかしい: {}", tag, reason)
+            }
+            WireError::FrameTooLarge(n) => {
+                write!(f, "フレームが {} バイトあります(上限 {})", n, MAX_FRAME)
+            }
+            WireError::LineTooLong(n) => {
+                write!(f, "行が {} バイトを超えました(上限 {})", n, MAX_LINE)
+            }
+            WireError::Syntax { line, message } => write!(f, "{}行目: {}", line, message),
+            WireError::Truncated(n) => write!(f, "読みかけの {} バイトが残っています", n),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+fn tag_of(kind: MessageKind) -> (u8, &'static str) {
+    let (_, byte, name, _) = TAGS.iter().find(|t| t.0 == kind).unwrap();
+    (*byte, name)
+}
+
+// 版 version で使えるタグを探す
+fn find_tag<F>(version: u8, pred: F) -> Option<MessageKind>
+where
+    F: Fn(u8, &str) -> bool,
+{
+    TAGS.iter()
+        .find(|(_, byte, name, since)| pred(*byte, name) && *since <= version)
+        .map(|t| t.0)
+}
+
+fn check_version(version: u32) -> Result<u8, WireError> {
+    if version == 0 || version > VERSION as u32 {
+        Err(WireError::UnsupportedVersion(version))
+    } else {
+        Ok(version as u8)
+    }
+}
+
+// ---- バイナリ ----
+
+/// 1件をフレームにして out の後ろに足す
+/// 本体が MAX_FRAME を超える(長すぎる Write)なら読み手が断るので、何も足さずに Err
+pub fn encode_binary_into(message: &Message, out: &mut Vec<u8>) -> Result<(), WireError> {
+    if let Message::Write(s) = message {
+        let len = 2 + s.len(); // 版とタグの分
+        if len > MAX_FRAME {
+            return Err(WireError::FrameTooLarge(len));
+        }
+    }
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]); // 長さは後で埋める
+    out.push(VERSION);
+    out.push(tag_of(message.kind()).0);
+    match message {
+        Message::Quit => {}
+        Message::Move { x, y } => {
+            out.extend_from_slice(&x.to_be_bytes());
+            out.extend_from_slice(&y.to_be_bytes());
+        }
+        Message::Write(s) => out.extend_from_slice(s.as_bytes()),
+        Message::ChangeColor(r, g, b) => {
+            for v in &[r, g, b] {
+                out.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+    }
+    let len = (out.len() - start - 4) as u32; // MAX_FRAME 以下なので u32 に収まる
+    out[start..start + 4].copy_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+/// 1件をフレームにする
+pub fn encode_binary(message: &Message) -> Result<Vec<u8>, WireError> {
+    let mut out = Vec::new();
+    encode_binary_into(message, &mut out)?;
+    Ok(out)
+}
+
+// フレームの本体(版から後ろ)を Message にする
+fn decode_body(body: &[u8]) -> Result<Message, WireError> {
+    let version = check_version(body[0] as u32)?;
+    let tag = match body.get(1) {
+        Some(t) => *t,
+        None => {
+            return Err(WireError::BadPayload {
+                tag: String::from("?"),
+                reason: String::from("タグがありません"),
+            })
+        }
+    };
+    let kind = find_tag(version, |b, _| b == tag).ok_or_else(|| WireError::UnknownTag {
+        version,
+        tag: tag.to_string(),
+    })?;
+    let payload = &body[2..];
+    let name = tag_of(kind).1;
+    let ints = |n: usize| -> Result<Vec<i32>, WireError> {
+        if payload.len() != n * 4 {
+            return Err(WireError::BadPayload {
+                tag: String::from(name),
+                reason: format!("{} バイトのはずが {} バイト", n * 4, payload.len()),
+            });
+        }
+        Ok(payload
+            .chunks(4)
+            .map(|c| i32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+            .collect())
+    };
+    Ok(match kind {
+        MessageKind::Quit => {
+            ints(0)?;
+            Message::Quit
+        }
+        MessageKind::Move => {
+            let v = ints(2)?;
+            Message::Move { x: v[0], y: v[1] }
+        }
+        MessageKind::Write => match std::str::from_utf8(payload) {
+            Ok(s) => Message::Write(String::from(s)),
+            Err(e) => {
+                return Err(WireError::BadPayload {
+                    tag: String::from(name),
+                    reason: format!("UTF-8 ではありません({})", e),
+                })
+            }
+        },
+        MessageKind::ChangeColor => {
+            let v = ints(3)?;
+            Message::ChangeColor(v[0], v[1], v[2])
+        }
+    })
+}
+
+/// バイナリの読み手
+///
+/// feed で届いた分を渡し、next_message で取り出せるだけ取り出す。
+/// おかしなフレームは Err を1回返して読み捨てるので、その後も読み続けられる
+#[derive(Debug, Clone, Default)]
+pub struct BinaryDecoder {
+    buf: Vec<u8>,
+    pos: usize,  // buf のうち読み終えたところ
+    skip: usize, // 大きすぎるフレームの残りで、まだ届いていない分
+}
+
+impl BinaryDecoder {
+    pub fn new() -> BinaryDecoder {
+        BinaryDecoder::default()
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let dropped = self.skip.min(bytes.len());
+        self.skip -= dropped;
+        let bytes = &bytes[dropped..];
+        if self.pos > 0 && self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+        } else if self.pos > 4096 && self.pos * 2 > self.buf.len() {
+            // 読み終えた分が溜まったら詰める
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// 次の1件(まだ揃っていなければ Ok(None))
+    pub fn next_message(&mut self) -> Result<Option<Message>, WireError> {
+        let rest = &self.buf[self.pos..];
+        if rest.len() < 4 {
+            return Ok(None);
+        }
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if len > MAX_FRAME {
+            // 本体は受け取らずに捨てる
+            let here = (rest.len() - 4).min(len);
+            self.pos += 4 + here;
+            self.skip = len - here;
+            return Err(WireError::FrameTooLarge(len));
+        }
+        if len == 0 {
+            self.pos += 4;
+            return Err(WireError::BadPayload {
+                tag: String::from("?"),
+                reason: String::from("本体が空です"),
+            });
+        }
+        if rest.len() < 4 + len {
+            return Ok(None);
+        }
+        let result = decode_body(&rest[4..4 + len]);
+        self.pos += 4 + len;
+        result.map(Some)
+    }
+
+    /// 読みかけのバイト数
+    pub fn pending(&self) -> usize {
+        self.buf.len() - self.pos + self.skip
+    }
+
+    /// 流れが終わったときに呼ぶ(読みかけが残っていたら Err)
+    pub fn finish(&self) -> Result<(), WireError> {
+        match self.pending() {
+            0 => Ok(()),
+            n => Err(WireError::Truncated(n)),
+        }
+    }
+}
+
+// ---- テキスト ----
+
+/// 1件を1行(改行つき)にする
+pub fn encode_text(message: &Message) -> String {
+    let name = tag_of(message.kind()).1;
+    match message {
+        Message::Quit => format!("{}\n", name),
+        Message::Move { x, y } => format!("{} {} {}\n", name, x, y),
+        Message::Write(s) => format!("{} {}\n", name, quote(s)),
+        Message::ChangeColor(r, g, b) => format!("{} {} {} {}\n", name, r, g, b),
+    }
+}
+
+/// 版の行(流れの頭に置く)
+pub fn text_header() -> String {
+    format!("VERSION {}\n", VERSION)
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// quote の逆。s は " で始まり " で終わる
+fn unquote(s: &str) -> Result<String, String> {
+    if s.len() < 2 || !s.starts_with('"') || !s.ends_with('"') {
+        return Err(String::from("文字列は \"...\" で囲んでください"));
+    }
+    let mut out = String::new();
+    let mut chars = s[1..s.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Err(String::from("文字列の途中に \" があります")),
+            '\\' => match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some('u') => {
+                    // \u{1f600} の形。} まで読む
+                    let mut hex = String::new();
+                    let mut closed = false;
+                    if chars.next() == Some('{') {
+                        for c in chars.by_ref() {
+                            if c == '}' {
+                                closed = true;
+                                break;
+                            }
+                            hex.push(c);
+                        }
+                    }
+                    let c = Some(&hex)
+                        .filter(|h| closed && !h.is_empty() && h.len() <= 6)
+                        .and_then(|h| u32::from_str_radix(h, 16).ok())
+                        .and_then(std::char::from_u32)
+                        .ok_or_else(|| String::from("\\u{..} が文字になりません"))?;
+                    out.push(c);
+                }
+                Some(c) => return Err(format!("\\{} というエスケープはありません", c)),
+                None => return Err(String::from("\\ で終わっています")),
+            },
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+fn parse_ints(name: &str, args: &str, n: usize) -> Result<Vec<i32>, WireError> {
+    let v: Vec<&str> = args.split_whitespace().collect();
+    if v.len() != n {
+        return Err(WireError::BadPayload {
+            tag: String::from(name),
+            reason: format!("数が {} 個のはずが {} 個", n, v.len()),
+        });
+    }
+    v.iter()
+        .map(|s| {
+            s.parse().map_err(|_| WireError::BadPayload {
+                tag: String::from(name),
+                reason: format!("{} は i32 ではありません", s),
+            })
+        })
+        .collect()
+}
+
+/// テキスト1行(改行なし)を版 version で読む
+pub fn decode_text_line(line: &str, version: u8) -> Result<Message, WireError> {
+    let version = check_version(version as u32)?;
+    let line = line.trim();
+    let (name, args) = match line.find(' ') {
+        Some(i) => (&line[..i], line[i + 1..].trim()),
+        None => (line, ""),
+    };
+    let kind = find_tag(version, |_, n| n == name).ok_or_else(|| WireError::UnknownTag {
+        version,
+        tag: String::from(name),
+    })?;
+    Ok(match kind {
+        MessageKind::Quit => {
+            parse_ints(name, args, 0)?;
+            Message::Quit
+        }
+        MessageKind::Move => {
+            let v = parse_ints(name, args, 2)?;
+            Message::Move { x: v[0], y: v[1] }
+        }
+        MessageKind::Write => {
+            Message::Write(unquote(args).map_err(|reason| WireError::BadPayload {
+                tag: String::from(name),
+                reason,
+            })?)
+        }
+        MessageKind::ChangeColor => {
+            let v = parse_ints(name, args, 3)?;
+            Message::ChangeColor(v[0], v[1], v[2])
+        }
+    })
+}
+
+/// テキストの読み手
+///
+/// 行の途中や文字の途中で切れて届いても、改行まで待ってから読む。
+/// おかしな行は Err(Syntax) を1回返して読み捨てる
+#[derive(Debug, Clone)]
+pub struct TextDecoder {
+    buf: Vec<u8>,
+    pos: usize,     // buf のうち読み終えたところ
+    scanned: usize, // buf のここまでは改行が無いと分かっている(毎回頭から探し直さない)
+    line: usize,    // 読み終えた行数
+    version: u8,    // VERSION 行で変わる
+    overflow: bool, // 長すぎる行の残りを捨てている
+}
+
+impl Default for TextDecoder {
+    fn default() -> TextDecoder {
+        TextDecoder {
+            buf: Vec::new(),
+            pos: 0,
+            scanned: 0,
+            line: 0,
+            version: 1,
+            overflow: false,
+        }
+    }
+}
+
+impl TextDecoder {
+    pub fn new() -> TextDecoder {
+        TextDecoder::default()
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        if self.pos > 0 && self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+            self.scanned = 0;
+        } else if self.pos > 4096 && self.pos * 2 > self.buf.len() {
+            // 読み終えた分が溜まったら詰める(BinaryDecoder と同じ)
+            self.buf.drain(..self.pos);
+            self.scanned -= self.pos;
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// 次の1件(まだ1行揃っていなければ Ok(None))
+    pub fn next_message(&mut self) -> Result<Option<Message>, WireError> {
+        loop {
+            let from = self.scanned.max(self.pos);
+            let end = match self.buf[from..].iter().position(|&b| b == b'\n') {
+                Some(i) => from + i,
+                None if self.buf.len() - self.pos > MAX_LINE => {
+                    // 改行が来るまで捨て続ける
+                    self.buf.clear();
+                    self.pos = 0;
+                    self.scanned = 0;
+                    if !self.overflow {
+                        self.overflow = true;
+                        return Err(WireError::LineTooLong(MAX_LINE));
+                    }
+                    return Ok(None);
+                }
+                None => {
+                    self.scanned = self.buf.len();
+                    return Ok(None);
+                }
+            };
+            let start = std::mem::replace(&mut self.pos, end + 1);
+            self.scanned = self.pos;
+            self.line += 1;
+            if std::mem::replace(&mut self.overflow, false) {
+                continue;
+            }
+            if end - start > MAX_LINE {
+                return Err(WireError::LineTooLong(MAX_LINE));
+            }
+            let line = self.line;
+            let syntax = |message: String| WireError::Syntax { line, message };
+            let text = match std::str::from_utf8(&self.buf[start..end]) {
+                Ok(s) => s.trim_end_matches('\r'),
+                Err(_) => return Err(syntax(String::from("UTF-8 ではありません"))),
+            };
+            let trimmed = text.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if let Some(v) = trimmed.strip_prefix("VERSION ") {
+                let v: u32 = v
+                    .trim()
+                    .parse()
+                    .map_err(|_| syntax(format!("{} は版の番号ではありません", v.trim())))?;
+                self.version = check_version(v).map_err(|e| syntax(e.to_string()))?;
+                continue;
+            }
+            return decode_text_line(text, self.version)
+                .map(Some)
+                .map_err(|e| syntax(e.to_string()));
+        }
+    }
+
+    /// 流れが終わったときに呼ぶ(改行の無い最後の行は読んで返す)
+    pub fn finish(&mut self) -> Result<Option<Message>, WireError> {
+        if self.pos == self.buf.len() || self.overflow {
+            self.buf.clear();
+            self.pos = 0;
+            self.scanned = 0;
+            return Ok(None);
+        }
+        self.buf.push(b'\n');
+        self.next_message()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> Vec<Message> {
+        vec![
+            Message::Quit,
+            Message::Move { x: 3, y: -4 },
+            Message::Write(String::from("hi")),
+            Message::Write(String::from("改行\nと \"引用\" と \\ と \u{7}")),
+            Message::ChangeColor(255, 0, i32::MIN),
+        ]
+    }
+
+    #[test]
+    fn binary_layout() {
+        assert_eq!(
+            encode_binary(&Message::Move { x: 3, y: -4 }).unwrap(),
+            vec![0, 0, 0, 10, 1, 1, 0, 0, 0, 3, 0xff, 0xff, 0xff, 0xfc]
+        );
+        assert_eq!(
+            encode_binary(&Message::Quit).unwrap(),
+            vec![0, 0, 0, 2, 1, 0]
+        );
+        assert_eq!(
+            encode_binary(&Message::Write(String::from("hi"))).unwrap(),
+            vec![0, 0, 0, 4, 1, 2, b'h', b'i']
+        );
+    }
+
+    #[test]
+    fn binary_byte_by_byte() {
+        let mut bytes = Vec::new();
+        for m in all() {
+            encode_binary_into(&m, &mut bytes).unwrap();
+        }
+        let mut d = BinaryDecoder::new();
+        let mut got = Vec::new();
+        for b in bytes {
+            d.feed(&[b]);
+            while let Some(m) = d.next_message().unwrap() {
+                got.push(m);
+            }
+        }
+        assert_eq!(got, all());
+        assert_eq!(d.finish(), Ok(()));
+        d.feed(&[0, 0]);
+        assert_eq!(d.finish(), Err(WireError::Truncated(2)));
+    }
+
+    #[test]
+    fn binary_errors_skip_the_frame() {
+        let mut d = BinaryDecoder::new();
+        d.feed(&[0, 0, 0, 2, 2, 0]); // 版 2
+        d.feed(&[0, 0, 0, 2, 1, 9]); // タグ 9
+        d.feed(&[0, 0, 0, 3, 1, 1, 0]); // Move が短い
+        d.feed(&[0, 0, 0, 4, 1, 2, 0xff, 0xfe]); // UTF-8 でない
+        d.feed(&[0xff, 0xff, 0xff, 0xff, 1, 2]); // 大きすぎる
+        assert_eq!(d.next_message(), Err(WireError::UnsupportedVersion(2)));
+        assert_eq!(
+            d.next_message(),
+            Err(WireError::UnknownTag {
+                version: 1,
+                tag: String::from("9")
+            })
+        );
+        assert!(matches!(
+            d.next_message(),
+            Err(WireError::BadPayload { .. })
+        ));
+        assert!(matches!(
+            d.next_message(),
+            Err(WireError::BadPayload { .. })
+        ));
+        assert_eq!(d.next_message(), Err(WireError::FrameTooLarge(0xffff_ffff)));
+        // 大きすぎるフレームの残りは捨てて、次のフレームから読める
+        d.feed(&vec![7; 1000]);
+        assert_eq!(d.pending(), 0xffff_ffff - 1002);
+        let mut d = BinaryDecoder::new();
+        d.feed(&[0, 0x10, 0, 1, 1, 2]);
+        assert_eq!(d.next_message(), Err(WireError::FrameTooLarge(0x10_0001)));
+        d.feed(&vec![b'a'; 0x10_0001 - 2]);
+        d.feed(&encode_binary(&Message::Quit).unwrap());
+        assert_eq!(d.next_message(), Ok(Some(Message::Quit)));
+        assert_eq!(d.finish(), Ok(()));
+    }
+
+    #[test]
+    fn too_large_write_is_not_encoded() {
+        let fits = Message::Write("a".repeat(MAX_FRAME - 2));
+        let mut out = vec![9];
+        encode_binary_into(&fits, &mut out).unwrap();
+        assert_eq!(out.len(), 1 + 4 + MAX_FRAME);
+        let mut d = BinaryDecoder::new();
+        d.feed(&out[1..]);
+        assert_eq!(d.next_message(), Ok(Some(fits)));
+        let over = Message::Write("a".repeat(MAX_FRAME - 1));
+        assert_eq!(
+            encode_binary_into(&over, &mut out),
+            Err(WireError::FrameTooLarge(MAX_FRAME + 1))
+        );
+        assert_eq!(out.len(), 1 + 4 + MAX_FRAME); // 何も足していない
+    }
+
+    #[test]
+    fn text_lines() {
+        let lines: Vec<String> = all().iter().map(encode_text).collect();
+        assert_eq!(lines[0], "QUIT\n");
+        assert_eq!(lines[1], "MOVE 3 -4\n");
+        assert_eq!(lines[2], "WRITE \"hi\"\n");
+        assert_eq!(
+            lines[3],
+            "WRITE \"改行\\nと \\\"引用\\\" と \\\\ と \\u{7}\"\n"
+        );
+        assert_eq!(lines[4], "COLOR 255 0 -2147483648\n");
+        for (line, m) in lines.iter().zip(all()) {
+            assert_eq!(decode_text_line(line.trim_end(), VERSION), Ok(m));
+        }
+    }
+
+    #[test]
+    fn text_stream_in_pieces() {
+        let src = "VERSION 1\r\n# 見本\r\n\r\nMOVE  3   -4\r\nWRITE \"日本語\"\r\nQUIT";
+        let mut d = TextDecoder::new();
+        let mut got = Vec::new();
+        // 文字の途中でも切れるように1バイトずつ
+        for b in src.bytes() {
+            d.feed(&[b]);
+            while let Some(m) = d.next_message().unwrap() {
+                got.push(m);
+            }
+        }
+        got.extend(d.finish().unwrap());
+        assert_eq!(
+            got,
+            vec![
+                Message::Move { x: 3, y: -4 },
+                Message::Write(String::from("日本語")),
+                Message::Quit,
+            ]
+        );
+    }
+
+    #[test]
+    fn text_many_lines_in_one_feed() {
+        let mut src = String::new();
+        for i in 0..100_000 {
+            src.push_str(&encode_text(&Message::Move { x: i, y: -i }));
+        }
+        let mut d = TextDecoder::new();
+        d.feed(src.as_bytes());
+        d.feed(b"WRITE \"ab"); // 行の途中で切れている
+        for i in 0..100_000 {
+            assert_eq!(d.next_message(), Ok(Some(Message::Move { x: i, y: -i })));
+        }
+        assert_eq!(d.next_message(), Ok(None));
+        assert_eq!(d.scanned, d.buf.len()); // 探し終えたところは覚えている
+                                            // 次に届いたときに読み終えた分は詰める
+        d.feed(b"c\"\n");
+        assert_eq!(d.buf, b"WRITE \"abc\"\n");
+        assert_eq!(
+            d.next_message(),
+            Ok(Some(Message::Write(String::from("abc"))))
+        );
+        assert_eq!(d.finish(), Ok(None));
+    }
+
+    #[test]
+    fn text_errors() {
+        let mut d = TextDecoder::new();
+        d.feed(b"JUMP 1\nMOVE 1\nCOLOR 1 2 x\nWRITE hi\nWRITE \"\\q\"\nVERSION 9\n");
+        d.feed(b"WRITE \"\\u{41\"\nQUIT\n");
+        let errs: Vec<String> = (0..7)
+            .map(|_| d.next_message().unwrap_err().to_string())
+            .collect();
+        assert_eq!(
+            errs,
+            vec![
+                "1行目: 版 1 にタグ JUMP はありません",
+                "2行目: MOVE の中身がおかしい: 数が 2 個のはずが 1 個",
+                "3行目: COLOR の中身がおかしい: x は i32 ではありません",
+                "4行目: WRITE の中身がおかしい: 文字列は \"...\" で囲んでください",
+                "5行目: WRITE の中身がおかしい: \\q というエスケープはありません",
+                "6行目: 版 9 は読めません(読めるのは 1 まで)",
+                "7行目: WRITE の中身がおかしい: \\u{..} が文字になりません",
+            ]
+        );
+        assert_eq!(d.version(), 1);
+        assert_eq!(d.next_message(), Ok(Some(Message::Quit)));
+        assert_eq!(d.next_message(), Ok(None));
+    }
+}
@@ -0,0 +1,851 @@
+// 単語の出現回数を数える
+// ./others/collections.rs の hash_map では1つの文を split_whitespace で区切って entry().or_insert(0) で数えた
+// ここでは何行でも何ファイルでも流し込めるようにして、
+// 大文字小文字をそろえる(既定), ストップワード(数えない語)を除く, 連続する n 語(n-gram)を数える, ができる
+//
+// 単語は文字か数字の並び(途中の ' は含める: don't)。句読点や記号は区切り
+// 日本語は単語の間に空白がないので、ひらがな, カタカナ, 漢字の並びは2文字ずつずらして数える(文字 bigram)
+//     東京都に住む → 東京 京都 都に に住 住む
+// n-gram はストップワードを除いた後の並びで作り、end_document を呼ぶまでは add をまたいでつながる
+//
+// 集計(Counts)は 表, CSV, JSON で書き出せて、CSV と JSON は読み戻して merge できる
+// (ファイルを分けて別々に数えた結果を後で足し合わせるため。足し合わせるのは --top で切る前の全部の集計にする)
+//
+//     CSV:  term,count      JSON: {
+//           world,2               "total": 3,
+//           hello,1               "counts": {
+//                                   "world": 2,
+//                                   "hello": 1
+//                                 }
+//                               }
+
+use crate::csv::{CsvError, Dialect};
+use crate::sheet::SpreadsheetCell;
+use crate::text;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// よく使われる英語のストップワード
+pub const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been",
+    "but", "by", "can", "could", "did", "do", "does", "for", "from", "had", "has", "have", "he",
+    "her", "his", "how", "i", "if", "in", "into", "is", "it", "its", "just", "me", "my", "no",
+    "not", "of", "on", "one", "or", "our", "out", "she", "so", "some", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "to", "up", "us", "was", "we",
+    "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WordFreqError {
+    Csv(CsvError),
+    BadRow(usize),                             // term,count の形でない CSV の行
+    Json { at: usize, message: &'static str }, // at は何文字目か(0 始まり)
+    Overflow(String),                          // 足すと回数が u64 に収まらない語
+}
+
+impl fmt::Display for WordFreqError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WordFreqError::Csv(e) => write!(f, "{}", e),
+            WordFreqError::BadRow(line) => {
+                write!(f, "{}行目: 語と回数(term,count)の2欄ではありません", line)
+            }
+            WordFreqError::Json { at, message } => {
+                write!(f, "JSON の {}文字目: {}", at + 1, message)
+            }
+            WordFreqError::Overflow(term) => {
+                write!(f, "{} の回数が {} を超えます", term, u64::MAX)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WordFreqError {}
+
+impl From<CsvError> for WordFreqError {
+    fn from(e: CsvError) -> WordFreqError {
+        WordFreqError::Csv(e)
+    }
+}
+
+/// 書き出しの形
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("{} という形はありません(table, csv, json)", s)),
+        }
+    }
+}
+
+/// 語ごとの回数
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Counts {
+    map: HashMap<String, u64>,
+}
+
+impl Counts {
+    pub fn new() -> Counts {
+        Counts::default()
+    }
+
+    /// term の回数に n を足す(u64 を超えるなら Overflow で、回数は変えない)
+    pub fn add(&mut self, term: &str, n: u64) -> Result<(), WordFreqError> {
+        // 見たことのある語なら String を作らない
+        match self.map.get_mut(term) {
+            Some(count) => {
+                *count = count
+                    .checked_add(n)
+                    .ok_or_else(|| WordFreqError::Overflow(term.to_string()))?
+            }
+            None => {
+                self.map.insert(term.to_string(), n);
+            }
+        }
+        Ok(())
+    }
+
+    // Counter が1つずつ数える分(1 ずつ足して u64 を超えることはないので詰まったら止める)
+    fn bump(&mut self, term: &str) {
+        match self.map.get_mut(term) {
+            Some(count) => *count = count.saturating_add(1),
+            None => {
+                self.map.insert(term.to_string(), 1);
+            }
+        }
+    }
+
+    pub fn get(&self, term: &str) -> u64 {
+        self.map.get(term).copied().unwrap_or(0)
+    }
+
+    /// 異なり語数
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// 延べ語数(u64 を超える分は u64::MAX で止める)
+    pub fn total(&self) -> u64 {
+        self.map.values().fold(0, |sum, &n| sum.saturating_add(n))
+    }
+
+    /// other の回数を足す(小さい方を大きい方に入れる)
+    /// どれかの語が u64 を超えるなら Overflow で、self は変えない
+    pub fn merge(&mut self, mut other: Counts) -> Result<(), WordFreqError> {
+        let (small, large) = if other.map.len() > self.map.len() {
+            (&*self, &other)
+        } else {
+            (&other, &*self)
+        };
+        for (term, &n) in &small.map {
+            if large.get(term).checked_add(n).is_none() {
+                return Err(WordFreqError::Overflow(term.clone()));
+            }
+        }
+        if other.map.len() > self.map.len() {
+            std::mem::swap(self, &mut other);
+        }
+        for (term, n) in other.map {
+            *self.map.entry(term).or_insert(0) += n;
+        }
+        Ok(())
+    }
+
+    /// 多い順(同じ回数なら語の順)に上から k 個(None なら全部)
+    /// 全部は並べ替えず、選択で上位 k 個を先に取り出してからそれだけ並べる
+    pub fn top(&self, k: Option<usize>) -> Vec<(&str, u64)> {
+        let mut v: Vec<(&str, u64)> = self.map.iter().map(|(t, &n)| (t.as_str(), n)).collect();
+        let order = |a: &(&str, u64), b: &(&str, u64)| b.1.cmp(&a.1).then(a.0.cmp(b.0));
+        if let Some(k) = k.filter(|&k| k < v.len()) {
+            if k == 0 {
+                return Vec::new();
+            }
+            v.select_nth_unstable_by(k - 1, order);
+            v.truncate(k);
+        }
+        v.sort_unstable_by(order);
+        v
+    }
+
+    /// format の形で上から k 個を書き出す(割合と total は切る前の全部に対して)
+    pub fn render(&self, format: Format, k: Option<usize>) -> String {
+        match format {
+            Format::Table => self.table(k),
+            Format::Csv => self.csv(k),
+            Format::Json => self.json(k),
+        }
+    }
+
+    fn table(&self, k: Option<usize>) -> String {
+        let total = self.total();
+        let mut rows = vec![vec![
+            String::from("順位"),
+            String::from("語"),
+            String::from("回数"),
+            String::from("割合"),
+        ]];
+        for (i, (term, n)) in self.top(k).into_iter().enumerate() {
+            rows.push(vec![
+                (i + 1).to_string(),
+                term.to_string(),
+                n.to_string(),
+                format!("{:.2}%", n as f64 * 100.0 / total as f64),
+            ]);
+        }
+        let mut out = text::table(&rows);
+        out.push_str(&format!("延べ {} 語, 異なり {} 語\n", total, self.len()));
+        out
+    }
+
+    fn csv(&self, k: Option<usize>) -> String {
+        let mut rows = vec![vec![
+            SpreadsheetCell::Text(String::from("term")),
+            SpreadsheetCell::Text(String::from("count")),
+        ]];
+        for (term, n) in self.top(k) {
+            rows.push(vec![
+                SpreadsheetCell::Text(term.to_string()),
+                // i64 に収まらない回数は文字で書く(読むときは u64 として読む)
+                i64::try_from(n)
+                    .map(SpreadsheetCell::Int)
+                    .unwrap_or_else(|_| SpreadsheetCell::Text(n.to_string())),
+            ]);
+        }
+        Dialect::new().format(&rows)
+    }
+
+    fn json(&self, k: Option<usize>) -> String {
+        let top = self.top(k);
+        let mut out = format!("{{\n  \"total\": {},\n  \"counts\": {{", self.total());
+        for (i, (term, n)) in top.iter().enumerate() {
+            out.push_str(if i == 0 { "\n    " } else { ",\n    " });
+            push_json_string(&mut out, term);
+            out.push_str(&format!(": {}", n));
+        }
+        out.push_str(if top.is_empty() {
+            "}\n}\n"
+        } else {
+            "\n  }\n}\n"
+        });
+        out
+    }
+
+    /// CSV か JSON の集計を読む(最初の空白でない文字が { なら JSON)
+    pub fn parse(s: &str) -> Result<Counts, WordFreqError> {
+        if s.trim_start().starts_with('{') {
+            Counts::from_json(s)
+        } else {
+            Counts::from_csv(s)
+        }
+    }
+
+    /// term,count の CSV を読む(1行目の見出しはあってもなくてもよい, 同じ語が何度出てきたら足す)
+    pub fn from_csv(s: &str) -> Result<Counts, WordFreqError> {
+        let mut counts = Counts::new();
+        let mut reader = Dialect::new().infer_types(false).reader(s.as_bytes());
+        while let Some(row) = reader.next() {
+            let row = row?;
+            let line = reader.line();
+            let (term, n) = match row.as_slice() {
+                [] => continue,
+                [SpreadsheetCell::Text(t), SpreadsheetCell::Text(n)] => (t, n),
+                _ => return Err(WordFreqError::BadRow(line)),
+            };
+            match n.parse::<u64>() {
+                Ok(n) => counts.add(term, n)?,
+                Err(_) if line == 1 && term == "term" && n == "count" => continue,
+                Err(_) => return Err(WordFreqError::BadRow(line)),
+            }
+        }
+        Ok(counts)
+    }
+
+    /// json で書いた形を読む(counts の中身だけ使い, ほかのキーの数は読み飛ばす)
+    pub fn from_json(s: &str) -> Result<Counts, WordFreqError> {
+        let mut p = Json {
+            chars: s.chars().collect(),
+            at: 0,
+        };
+        let mut counts = Counts::new();
+        let mut found = false;
+        p.object(|p, key| {
+            if key == "counts" {
+                found = true;
+                p.object(|p, term| {
+                    let n = p.number()?;
+                    counts.add(&term, n)
+                })
+            } else {
+                p.number().map(|_| ())
+            }
+        })?;
+        p.space();
+        if p.at < p.chars.len() {
+            return Err(p.error("集計の後に余計な文字があります"));
+        }
+        if !found {
+            return Err(p.error("counts がありません"));
+        }
+        Ok(counts)
+    }
+}
+
+// JSON の文字列にする(" と \ と制御文字をエスケープ)
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// 集計の JSON を読むだけの小さな読み手(オブジェクト, 文字列, 0 以上の整数だけ)
+struct Json {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl Json {
+    fn error(&self, message: &'static str) -> WordFreqError {
+        WordFreqError::Json {
+            at: self.at,
+            message,
+        }
+    }
+
+    fn space(&mut self) {
+        while self.chars.get(self.at).is_some_and(|c| c.is_whitespace()) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char, message: &'static str) -> Result<(), WordFreqError> {
+        self.space();
+        if self.chars.get(self.at) == Some(&c) {
+            self.at += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.space();
+        let ok = self.chars.get(self.at) == Some(&c);
+        if ok {
+            self.at += 1;
+        }
+        ok
+    }
+
+    // { "キー": 値, ... } のキーごとに value を呼ぶ(value が値を読む)
+    fn object<F>(&mut self, mut value: F) -> Result<(), WordFreqError>
+    where
+        F: FnMut(&mut Json, String) -> Result<(), WordFreqError>,
+    {
+        self.expect('{', "{ がありません")?;
+        if self.eat('}') {
+            return Ok(());
+        }
+        loop {
+            let key = self.string()?;
+            self.expect(':', ": がありません")?;
+            value(self, key)?;
+            if self.eat('}') {
+                return Ok(());
+            }
+            self.expect(',', ", か } がありません")?;
+        }
+    }
+
+    fn number(&mut self) -> Result<u64, WordFreqError> {
+        self.space();
+        let start = self.at;
+        while self.chars.get(self.at).is_some_and(char::is_ascii_digit) {
+            self.at += 1;
+        }
+        let digits: String = self.chars[start..self.at].iter().collect();
+        if digits.len() > 1 && digits.starts_with('0') {
+            self.at = start;
+            return Err(self.error("数の頭に 0 は付けられません"));
+        }
+        digits.parse().map_err(|_| {
+            self.at = start;
+            self.error("0 以上の整数がありません")
+        })
+    }
+
+    fn string(&mut self) -> Result<String, WordFreqError> {
+        self.expect('"', "\" がありません")?;
+        let mut out = String::new();
+        loop {
+            let c = match self.chars.get(self.at) {
+                Some(&c) => c,
+                None => return Err(self.error("文字列が閉じられていません")),
+            };
+            self.at += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => out.push(self.escape()?),
+                c if (c as u32) < 0x20 => {
+                    self.at -= 1;
+                    return Err(self.error("文字列の中に制御文字があります"));
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    // \ の次から
+    fn escape(&mut self) -> Result<char, WordFreqError> {
+        let c = self.chars.get(self.at).copied();
+        self.at += 1;
+        Ok(match c {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                let hi = self.hex4()?;
+                // サロゲートペアは 😀 のように2つ続く
+                let code = if (0xD800..0xDC00).contains(&hi) {
+                    if self.chars.get(self.at..self.at + 2) != Some(&['\\', 'u']) {
+                        return Err(self.error("サロゲートペアの後半がありません"));
+                    }
+                    self.at += 2;
+                    let lo = self.hex4()?;
+                    if !(0xDC00..0xE000).contains(&lo) {
+                        return Err(self.error("サロゲートペアの後半がありません"));
+                    }
+                    0x10000 + ((hi - 0xD800) << 10) + (lo - 0xDC00)
+                } else {
+                    hi
+                };
+                char::from_u32(code).ok_or_else(|| self.error("文字にならない \\u です"))?
+            }
+            _ => {
+                self.at -= 1;
+                return Err(self.error("知らないエスケープです"));
+            }
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, WordFreqError> {
+        let digits: String = self.chars.iter().skip(self.at).take(4).collect();
+        match u32::from_str_radix(&digits, 16) {
+            Ok(n) if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+                self.at += 4;
+                Ok(n)
+            }
+            _ => Err(self.error("\\u の後に16進数4桁がありません")),
+        }
+    }
+}
+
+/// 文章を流し込んで数えるもの
+/// Counter::new().fold_case(false).stopwords(ENGLISH_STOPWORDS).ngram(2) のように組み立てる
+#[derive(Debug, Clone)]
+pub struct Counter {
+    fold_case: bool,
+    stopwords: HashSet<String>,
+    n: usize,
+    window: VecDeque<String>, // n-gram 用に直前の n - 1 語を覚えておく
+    counts: Counts,
+}
+
+impl Default for Counter {
+    fn default() -> Counter {
+        Counter {
+            fold_case: true,
+            stopwords: HashSet::new(),
+            n: 1,
+            window: VecDeque::new(),
+            counts: Counts::new(),
+        }
+    }
+}
+
+impl Counter {
+    pub fn new() -> Counter {
+        Counter::default()
+    }
+
+    /// 大文字小文字をそろえるか(既定はそろえる: The と the は同じ語)
+    pub fn fold_case(self, fold_case: bool) -> Counter {
+        Counter { fold_case, ..self }
+    }
+
+    /// 数えない語を足す(fold_case(false) でも大文字小文字は問わない: the を足せば The も数えない)
+    pub fn stopwords<I, S>(mut self, words: I) -> Counter
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for w in words {
+            let w = w.as_ref().trim();
+            if !w.is_empty() {
+                self.stopwords.insert(w.to_lowercase());
+            }
+        }
+        self
+    }
+
+    /// 連続する n 語をまとめて1つとして数える(1 なら単語ごと)
+    pub fn ngram(self, n: usize) -> Counter {
+        assert!(n >= 1, "n-gram の n は 1 以上");
+        Counter { n, ..self }
+    }
+
+    /// 文章を足す(n-gram は前の add の続きとしてつながる)
+    pub fn add(&mut self, text: &str) {
+        let mut tokens = Vec::new();
+        tokenize(text, &mut tokens);
+        for token in tokens {
+            let token = if self.fold_case {
+                token.to_lowercase()
+            } else {
+                token
+            };
+            let stop = if self.fold_case {
+                self.stopwords.contains(&token)
+            } else {
+                !self.stopwords.is_empty() && self.stopwords.contains(&token.to_lowercase())
+            };
+            if stop {
+                continue;
+            }
+            if self.n == 1 {
+                self.counts.bump(&token);
+                continue;
+            }
+            if self.window.len() == self.n {
+                self.window.pop_front();
+            }
+            self.window.push_back(token);
+            if self.window.len() == self.n {
+                let gram = self
+                    .window
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.counts.bump(&gram);
+            }
+        }
+    }
+
+    /// 文書の区切り(この後の add は前の文書と n-gram でつながらない)
+    pub fn end_document(&mut self) {
+        self.window.clear();
+    }
+
+    pub fn counts(&self) -> &Counts {
+        &self.counts
+    }
+
+    /// ここまでの集計を取り出して空に戻す(設定はそのまま)
+    pub fn take(&mut self) -> Counts {
+        self.end_document();
+        std::mem::take(&mut self.counts)
+    }
+}
+
+// 日本語(ひらがな, カタカナ, 漢字)の文字か
+fn is_japanese(c: char) -> bool {
+    matches!(c,
+        '\u{3005}' // 々
+        | '\u{3040}'..='\u{30FF}' // ひらがな, カタカナ(ー も含む)
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF66}'..='\u{FF9F}') // 半角カタカナ
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() && !is_japanese(c)
+}
+
+// 単語と日本語の文字 bigram に分ける
+fn tokenize(text: &str, out: &mut Vec<String>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        if is_japanese(chars[i]) {
+            while i < chars.len() && is_japanese(chars[i]) {
+                i += 1;
+            }
+            let run = &chars[start..i];
+            if run.len() == 1 {
+                out.push(run[0].to_string());
+            }
+            out.extend(run.windows(2).map(|w| w.iter().collect::<String>()));
+        } else if is_word(chars[i]) {
+            i += 1;
+            loop {
+                if i < chars.len() && is_word(chars[i]) {
+                    i += 1;
+                } else if i + 1 < chars.len()
+                    && matches!(chars[i], '\'' | '’')
+                    && is_word(chars[i + 1])
+                {
+                    i += 2;
+                } else {
+                    break;
+                }
+            }
+            out.push(chars[start..i].iter().collect());
+        } else {
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(counter: Counter, text: &str) -> Counts {
+        let mut c = counter;
+        c.add(text);
+        c.take()
+    }
+
+    // collections.rs の使用例と同じ答え
+    #[test]
+    fn notebook_example() {
+        let c = count(Counter::new(), "hello world wonderful world");
+        assert_eq!(
+            c.top(None),
+            vec![("world", 2), ("hello", 1), ("wonderful", 1)]
+        );
+        assert_eq!((c.total(), c.len()), (4, 3));
+    }
+
+    #[test]
+    fn words_case_and_stopwords() {
+        let text = "The cat and THE hat. Don't stop—the cat's back!";
+        let c = count(Counter::new().stopwords(ENGLISH_STOPWORDS), text);
+        assert_eq!(c.get("the"), 0);
+        assert_eq!(c.get("cat"), 1);
+        assert_eq!(c.get("cat's"), 1);
+        assert_eq!(c.get("don't"), 1);
+        assert_eq!(c.get("stop"), 1);
+        let c = count(Counter::new().fold_case(false).stopwords(["The"]), text);
+        assert_eq!((c.get("The"), c.get("THE"), c.get("the")), (0, 0, 0));
+        assert_eq!((c.get("Don't"), c.get("don't")), (1, 0));
+    }
+
+    #[test]
+    fn japanese_bigrams() {
+        let c = count(Counter::new(), "東京都に住む。猫");
+        assert_eq!(
+            c.top(None),
+            vec![
+                ("に住", 1),
+                ("京都", 1),
+                ("住む", 1),
+                ("東京", 1),
+                ("猫", 1),
+                ("都に", 1)
+            ]
+        );
+        // 英字と混ざっていてもそれぞれ
+        let c = count(Counter::new(), "Rustの本");
+        assert_eq!((c.get("rust"), c.get("の本")), (1, 1));
+    }
+
+    #[test]
+    fn ngrams_span_adds_until_end_document() {
+        let mut c = Counter::new().ngram(2).stopwords(["a"]);
+        c.add("to be or");
+        c.add("not to be, a cat");
+        c.end_document();
+        c.add("cat");
+        let counts = c.take();
+        assert_eq!(counts.get("to be"), 2);
+        assert_eq!(counts.get("or not"), 1);
+        assert_eq!(counts.get("be cat"), 1);
+        assert_eq!(counts.get("cat cat"), 0);
+        assert_eq!(counts.total(), 6);
+    }
+
+    #[test]
+    fn top_k_and_merge() {
+        let mut a = count(Counter::new(), "b b b a a c d d");
+        assert_eq!(a.top(Some(2)), vec![("b", 3), ("a", 2)]);
+        assert_eq!(a.top(Some(3)), vec![("b", 3), ("a", 2), ("d", 2)]);
+        assert_eq!(a.top(Some(0)), vec![]);
+        assert_eq!(a.top(Some(99)).len(), 4);
+        a.merge(count(Counter::new(), "c c c c e")).unwrap();
+        assert_eq!(a.top(Some(2)), vec![("c", 5), ("b", 3)]);
+        assert_eq!(a.total(), 13);
+    }
+
+    #[test]
+    fn csv_and_json_round_trip() {
+        let c = count(
+            Counter::new(),
+            "say \"hi\", 2024 times\\tabs 東京 naïve, say",
+        );
+        for format in [Format::Csv, Format::Json] {
+            let written = c.render(format, None);
+            assert_eq!(Counts::parse(&written), Ok(c.clone()), "{}", written);
+        }
+        assert_eq!(
+            c.render(Format::Json, Some(1)),
+            "{\n  \"total\": 8,\n  \"counts\": {\n    \"say\": 2\n  }\n}\n"
+        );
+        assert_eq!(c.render(Format::Csv, Some(1)), "term,count\r\nsay,2\r\n");
+        assert_eq!(
+            Counts::new().render(Format::Json, None),
+            "{\n  \"total\": 0,\n  \"counts\": {}\n}\n"
+        );
+    }
+
+    #[test]
+    fn reading_counts() {
+        let c = Counts::from_json(r#"{"counts": {"aé😀": 2, "b": 0}, "total": 2}"#).unwrap();
+        assert_eq!(c.get("aé😀"), 2);
+        assert_eq!(
+            Counts::from_json(r#"{"counts": {"a": -1}}"#),
+            Err(WordFreqError::Json {
+                at: 17,
+                message: "0 以上の整数がありません"
+            })
+        );
+        assert!(Counts::from_json(r#"{"total": 1}"#).is_err());
+        assert!(Counts::from_json(r#"{"counts": {}} x"#).is_err());
+        // 見出しなしでも読めて、同じ語は足す
+        let c = Counts::from_csv("a,1\n\"b,c\",2\na,3\n").unwrap();
+        assert_eq!((c.get("a"), c.get("b,c")), (4, 2));
+        assert_eq!(
+            Counts::from_csv("term,count\na,x\n"),
+            Err(WordFreqError::BadRow(2))
+        );
+        assert_eq!(Counts::from_csv("a,1,2\n"), Err(WordFreqError::BadRow(1)));
+    }
+
+    #[test]
+    fn counts_do_not_overflow() {
+        let mut a = Counts::new();
+        a.add("x", u64::MAX - 1).unwrap();
+        assert_eq!(
+            a.add("x", 2),
+            Err(WordFreqError::Overflow(String::from("x")))
+        );
+        assert_eq!(a.get("x"), u64::MAX - 1);
+        a.add("x", 1).unwrap();
+        a.add("y", 5).unwrap();
+        assert_eq!(a.total(), u64::MAX);
+
+        // 足せない語が1つでもあれば何も足さない(小さい方が self でも other でも)
+        let before = a.clone();
+        let mut b = Counts::new();
+        b.add("y", 1).unwrap();
+        b.add("x", 1).unwrap();
+        assert_eq!(
+            a.merge(b.clone()),
+            Err(WordFreqError::Overflow(String::from("x")))
+        );
+        assert_eq!(a, before);
+        let mut c = Counts::new();
+        c.add("x", 3).unwrap();
+        assert!(c.merge(a.clone()).is_err());
+        assert_eq!(c.get("x"), 3);
+        assert_eq!(
+            Counts::from_csv("x,18446744073709551615\nx,1\n"),
+            Err(WordFreqError::Overflow(String::from("x")))
+        );
+
+        // i64 を超える回数も CSV と JSON で読み戻せる
+        for format in [Format::Csv, Format::Json] {
+            let written = a.render(format, None);
+            assert_eq!(Counts::parse(&written), Ok(a.clone()), "{}", written);
+        }
+        assert!(a.render(Format::Csv, None).contains("18446744073709551615"));
+    }
+
+    const WORDS: &[&str] = &[
+        "the", "Cat", "sat", "on", "mat", "don't", "ねこ", "猫", "x1", "a,b",
+    ];
+
+    // WORDS を i から step ずつ飛ばして n 語並べた文書
+    fn document(i: usize, step: usize, n: usize, sep: &str) -> String {
+        (0..n)
+            .map(|k| WORDS[(i + k * step) % WORDS.len()])
+            .collect::<Vec<_>>()
+            .join(sep)
+    }
+
+    #[test]
+    fn counting_split_documents_then_merging_is_counting_all() {
+        let docs = [
+            document(0, 1, 25, " "),
+            document(3, 7, 0, " "),
+            document(5, 3, 12, "\n"),
+            document(1, 1, 1, " "),
+            document(2, 4, 29, "\n"),
+        ];
+        for n in 1..4 {
+            for k in 1..=docs.len() {
+                let mut whole = Counter::new().ngram(n);
+                let mut merged = Counts::new();
+                for doc in &docs[..k] {
+                    whole.add(doc);
+                    whole.end_document();
+                    let mut one = Counter::new().ngram(n);
+                    one.add(doc);
+                    merged.merge(one.take()).unwrap();
+                }
+                assert_eq!(merged.top(None), whole.counts().top(None), "{} {}", n, k);
+                assert_eq!(merged.total(), whole.counts().total());
+            }
+        }
+    }
+
+    #[test]
+    fn written_counts_read_back() {
+        for (i, fold_case) in [(0, true), (4, false), (7, true)] {
+            for n in 1..3 {
+                let mut counter = Counter::new().fold_case(fold_case).ngram(n);
+                counter.add(&document(i, 3, 40, " "));
+                let counts = counter.take();
+                for format in [Format::Csv, Format::Json] {
+                    let back = Counts::parse(&counts.render(format, None)).unwrap();
+                    assert_eq!(back.top(None), counts.top(None), "{:?}", format);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// adder の Rectangle のテストと structure.rs の MthRect を geometry::Rect で書き直したもの
+// assert_can_hold! は落ちたときに2つの形の絵をメッセージに付ける
+
+use rust_etude_dev::geometry::{Rect, Rotation};
+use rust_etude_dev::{assert_can_hold, assert_cannot_hold};
+
+// adder の tests::larger_can_hold_smaller と同じ大きさ
+#[test]
+fn larger_can_hold_smaller() {
+    let larger = Rect::with_size(8, 7);
+    let smaller = Rect::with_size(5, 1);
+    assert_can_hold!(larger, smaller);
+}
+
+// adder の tests::smaller_cannot_hold_larger と同じ大きさ
+#[test]
+fn smaller_cannot_hold_larger() {
+    let larger = Rect::with_size(8, 7);
+    let smaller = Rect::with_size(5, 1);
+    assert_cannot_hold!(smaller, larger);
+}
+
+// MthRect::square(16, 12) で作ったものと method() の3つ
+#[test]
+fn mth_rect_examples() {
+    let sq = Rect::with_size(16, 12);
+    assert_can_hold!(sq, Rect::with_size(12, 8));
+    let meth_rect1 = Rect::with_size(30, 50);
+    assert_can_hold!(meth_rect1, Rect::with_size(10, 40));
+    assert_cannot_hold!(meth_rect1, Rect::with_size(60, 45));
+    assert_can_hold!(meth_rect1, Rect::with_size(45, 20), Rotation::Allowed);
+}
+
+// adder の can_hold_ii(比較を逆にしたバグ版)の失敗が絵付きで出ることを確かめる
+#[test]
+fn failure_message_has_a_picture() {
+    let larger = Rect::with_size(8, 7);
+    let smaller = Rect::with_size(5, 1);
+    let err = std::panic::catch_unwind(|| assert_can_hold!(smaller, larger))
+        .expect_err("5x1 に 8x7 は収まらない");
+    let message = err.downcast_ref::<String>().expect("メッセージは String");
+    assert_eq!(
+        message,
+        "5x1 @ (0, 0) は 8x7 @ (0, 0) を収められません\n\
+         ┌self 5x1─┬─────┐\n\
+         ├─────────┘     │\n\
+         │other 8x7      │\n\
+         │               │\n\
+         │               │\n\
+         │               │\n\
+         │               │\n\
+         └───────────────┘\n"
+    );
+}
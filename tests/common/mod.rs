@@ -0,0 +1,60 @@
+// 結合テストで確保の回数を数える道具(adder/tests と同じく common/mod.rs に置くのでテスト出力に区域は出ない)
+#![allow(dead_code)] // テストファイルごとに使う道具が違う
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// 確保の回数を数えるグローバルアロケータ
+// 中身は System にそのまま任せて、確保(alloc, alloc_zeroed, realloc)の回数だけスレッドごとに数える
+// テストは並列に走るのでスレッドローカルにしないと他のテストの確保まで数えてしまう
+pub struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn bump() {
+    // スレッド終了処理中は thread_local にアクセスできないことがあるので try_with
+    let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        bump();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        bump();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        bump();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// f を呼んで、戻り値とその間にこのスレッドで起きた確保の回数を返す
+pub fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let value = f();
+    let after = ALLOCATIONS.with(Cell::get);
+    (value, after - before)
+}
+
+/// f の呼び出し1回あたりの確保回数が expected であることを確かめる
+/// 戻り値は確かめた後に捨てる(解放は数えない)
+#[track_caller]
+pub fn assert_allocations<T>(expected: usize, f: impl FnOnce() -> T) -> T {
+    let (value, count) = count_allocations(f);
+    assert_eq!(count, expected, "確保回数が想定と違います");
+    value
+}
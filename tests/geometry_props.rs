@@ -0,0 +1,125 @@
+// geometry の長方形の性質テスト
+// 乱数で作った長方形について、交差や和などが満たすべき関係を確かめる
+
+mod property;
+
+use property::{check_property, Rng};
+use rust_etude_dev::geometry::{Point, Rect, Rotation};
+
+fn int_rect(rng: &mut Rng) -> Rect<i64> {
+    Rect::new(
+        rng.range(-50, 50),
+        rng.range(-50, 50),
+        rng.range(0, 40),
+        rng.range(0, 40),
+    )
+}
+
+fn float_rect(rng: &mut Rng) -> Rect<f64> {
+    Rect::new(
+        rng.unit() * 100.0 - 50.0,
+        rng.unit() * 100.0 - 50.0,
+        rng.unit() * 40.0,
+        rng.unit() * 40.0,
+    )
+}
+
+// 格子点を数えて面積を出す(i64 版の答え合わせ用)
+fn brute_overlap(a: &Rect<i64>, b: &Rect<i64>) -> i64 {
+    let mut n = 0;
+    for x in a.left()..a.right() {
+        for y in a.top()..a.bottom() {
+            if b.contains_point(Point::new(x, y)) {
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+#[test]
+fn intersection_is_commutative_and_inside_both() {
+    check_property(500, |rng| {
+        let (a, b) = (int_rect(rng), int_rect(rng));
+        assert_eq!(a.intersection(&b), b.intersection(&a));
+        if let Some(i) = a.intersection(&b) {
+            assert!(a.contains(&i) && b.contains(&i));
+            assert!(!i.is_empty());
+        }
+    });
+}
+
+#[test]
+fn overlap_area_matches_counting_points() {
+    check_property(300, |rng| {
+        let (a, b) = (int_rect(rng), int_rect(rng));
+        assert_eq!(a.overlap_area(&b), brute_overlap(&a, &b));
+    });
+}
+
+#[test]
+fn union_contains_both_and_is_tight() {
+    check_property(500, |rng| {
+        let (a, b) = (int_rect(rng), int_rect(rng));
+        let u = a.union(&b);
+        assert!(u.contains(&a) && u.contains(&b));
+        assert_eq!(u, b.union(&a));
+        assert_eq!(u.left(), a.left().min(b.left()));
+        assert_eq!(u.bottom(), a.bottom().max(b.bottom()));
+        assert!(u.area() >= a.area() + b.area() - a.overlap_area(&b));
+    });
+}
+
+#[test]
+fn contains_point_agrees_with_intersection_of_unit_cell() {
+    check_property(500, |rng| {
+        let r = int_rect(rng);
+        let p = Point::new(rng.range(-60, 60), rng.range(-60, 60));
+        let cell = Rect::new(p.x, p.y, 1, 1);
+        assert_eq!(r.contains_point(p), r.intersects(&cell));
+        // 中の点は距離 0、距離が正なら外
+        if r.contains_point(p) {
+            assert_eq!(r.distance_squared_to(p), 0.0);
+        }
+        if r.distance_squared_to(p) > 0.0 {
+            assert!(!r.contains_point(p));
+        }
+    });
+}
+
+#[test]
+fn can_hold_with_rotation_is_symmetric_in_orientation() {
+    check_property(500, |rng| {
+        let (a, b) = (int_rect(rng), int_rect(rng));
+        assert_eq!(
+            a.can_hold(&b, Rotation::Allowed),
+            a.can_hold(&b.rotated(), Rotation::Allowed)
+        );
+        if a.can_hold(&b, Rotation::Fixed) {
+            assert!(a.can_hold(&b, Rotation::Allowed));
+            assert!(a.fits(&b, Rotation::Fixed));
+            assert!(a.area() > b.area() || b.is_empty());
+        }
+        // 収まるなら重ねたとき中に入る
+        if a.fits(&b, Rotation::Fixed) {
+            assert!(a.contains(&b.moved_to(a.left(), a.top())));
+        }
+    });
+}
+
+#[test]
+fn float_overlap_is_bounded_and_commutative() {
+    check_property(500, |rng| {
+        let (a, b) = (float_rect(rng), float_rect(rng));
+        let o = a.overlap_area(&b);
+        assert_eq!(o, b.overlap_area(&a));
+        assert!(o >= 0.0 && o <= a.area().min(b.area()) + 1e-9);
+        // 浮動小数点数では left + (right - left) が right に戻らないことがあるので少し緩める
+        let u = a.union(&b);
+        for r in &[a, b] {
+            assert!(u.left() <= r.left() && u.top() <= r.top());
+            assert!(u.right() >= r.right() - 1e-9 && u.bottom() >= r.bottom() - 1e-9);
+        }
+        assert!(u.perimeter() >= a.perimeter().max(b.perimeter()) - 1e-9);
+    });
+}
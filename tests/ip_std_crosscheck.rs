@@ -0,0 +1,126 @@
+// ip のアドレス型を std::net と突き合わせる
+// 乱数のアドレスで文字列化と分類が一致すること、
+// 崩した文字列を読ませたときに受け付けるかどうかが std と一致することを確かめる
+
+mod property;
+
+use property::{check_property, Rng};
+use rust_etude_dev::ip::{IpAddress, Ipv4, Ipv6};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+fn v4(rng: &mut Rng) -> Ipv4 {
+    // 分類の境目に当たりやすいように最初の数はよく出るものから選ぶ
+    let first = [0, 10, 127, 169, 172, 192, 198, 203, 224, 239, 255];
+    let a = if rng.bool() {
+        first[rng.range(0, first.len() as i64) as usize]
+    } else {
+        rng.range(0, 256) as u8
+    };
+    let mut octet = || {
+        if rng.range(0, 4) == 0 {
+            0
+        } else {
+            rng.range(0, 256) as u8
+        }
+    };
+    Ipv4::new(a, octet(), octet(), octet())
+}
+
+fn v6(rng: &mut Rng) -> Ipv6 {
+    // 0 の並びがよく出るようにする
+    let first = [
+        0, 0xfc00, 0xfd12, 0xfe80, 0xfebf, 0xfec0, 0xff02, 0x2001, 0x64,
+    ];
+    let mut segments = [0u16; 8];
+    for s in segments.iter_mut() {
+        *s = match rng.range(0, 3) {
+            0 => 0,
+            1 => rng.range(0, 16) as u16,
+            _ => rng.range(0, 0x1_0000) as u16,
+        };
+    }
+    if rng.bool() {
+        segments[0] = first[rng.range(0, first.len() as i64) as usize];
+    }
+    if rng.range(0, 8) == 0 {
+        // IPv4 射影アドレス
+        segments = [0, 0, 0, 0, 0, 0xffff, segments[6], segments[7]];
+    }
+    Ipv6(segments)
+}
+
+// 文字列を1か所だけ崩す
+fn mutate(rng: &mut Rng, s: &str) -> String {
+    let alphabet = ['0', '1', '9', 'a', 'f', 'g', ':', '.', 'F', ' '];
+    let mut chars: Vec<char> = s.chars().collect();
+    let i = rng.range(0, chars.len() as i64 + 1) as usize;
+    match rng.range(0, 3) {
+        0 if i < chars.len() => {
+            chars.remove(i);
+        }
+        1 if i < chars.len() => chars[i] = alphabet[rng.range(0, 10) as usize],
+        _ => chars.insert(i, alphabet[rng.range(0, 10) as usize]),
+    }
+    chars.into_iter().collect()
+}
+
+#[test]
+fn v4_matches_std() {
+    check_property(2000, |rng| {
+        let ours = v4(rng);
+        let std_addr = Ipv4Addr::from(ours);
+        assert_eq!(ours.to_string(), std_addr.to_string());
+        assert_eq!(Ipv4::parse(&std_addr.to_string()), Ok(ours));
+        assert_eq!(ours.is_loopback(), std_addr.is_loopback());
+        assert_eq!(ours.is_private(), std_addr.is_private());
+        assert_eq!(ours.is_link_local(), std_addr.is_link_local());
+        assert_eq!(ours.is_multicast(), std_addr.is_multicast());
+        assert_eq!(ours.is_broadcast(), std_addr.is_broadcast());
+        assert_eq!(ours.is_documentation(), std_addr.is_documentation());
+        assert_eq!(ours.is_unspecified(), std_addr.is_unspecified());
+    });
+}
+
+#[test]
+fn v6_matches_std() {
+    check_property(2000, |rng| {
+        let ours = v6(rng);
+        let std_addr = Ipv6Addr::from(ours);
+        assert_eq!(ours.to_string(), std_addr.to_string());
+        assert_eq!(Ipv6::parse(&std_addr.to_string()), Ok(ours));
+        assert_eq!(ours.is_loopback(), std_addr.is_loopback());
+        assert_eq!(ours.is_unspecified(), std_addr.is_unspecified());
+        assert_eq!(ours.is_multicast(), std_addr.is_multicast());
+        assert_eq!(ours.is_unique_local(), std_addr.is_unique_local());
+        assert_eq!(ours.is_link_local(), std_addr.is_unicast_link_local());
+        assert_eq!(
+            ours.to_ipv4_mapped().map(Ipv4Addr::from),
+            std_addr.to_ipv4_mapped()
+        );
+    });
+}
+
+#[test]
+fn mutated_strings_are_accepted_exactly_when_std_accepts() {
+    check_property(5000, |rng| {
+        let base = if rng.bool() {
+            v4(rng).to_string()
+        } else {
+            v6(rng).to_string()
+        };
+        let s = mutate(rng, &base);
+        let ours = IpAddress::parse(&s);
+        let std_result = s.parse::<IpAddr>();
+        assert_eq!(
+            ours.is_ok(),
+            std_result.is_ok(),
+            "`{}`: ours {:?}, std {:?}",
+            s,
+            ours,
+            std_result
+        );
+        if let (Ok(a), Ok(b)) = (ours, std_result) {
+            assert_eq!(IpAddr::from(a), b);
+        }
+    });
+}
@@ -0,0 +1,192 @@
+// maybe の性質テスト
+// 乱数の値と乱数で決めた関数で、Maybe / Outcome の組み合わせ関数が
+// std の Option / Result と同じ答えを出すことを確かめる(std に変換して比べる)
+
+mod property;
+
+use property::{check_property, Rng};
+use rust_etude_dev::maybe::{Maybe, Outcome};
+
+fn option(rng: &mut Rng) -> Option<i32> {
+    if rng.range(0, 3) == 0 {
+        None
+    } else {
+        Some(rng.range(-50, 50) as i32)
+    }
+}
+
+fn result(rng: &mut Rng) -> Result<i32, u8> {
+    if rng.range(0, 3) == 0 {
+        Err(rng.range(0, 5) as u8)
+    } else {
+        Ok(rng.range(-50, 50) as i32)
+    }
+}
+
+fn m(o: Option<i32>) -> Maybe<i32> {
+    Maybe::from(o)
+}
+
+fn o(r: Result<i32, u8>) -> Outcome<i32, u8> {
+    Outcome::from(r)
+}
+
+// Maybe を std に戻して比べる
+fn std_of<T>(x: Maybe<T>) -> Option<T> {
+    x.into()
+}
+
+fn std_res<T, E>(x: Outcome<T, E>) -> Result<T, E> {
+    x.into()
+}
+
+// std 側も遅延版(or_else など)をわざと呼んで比べる
+#[allow(clippy::unnecessary_lazy_evaluations)]
+#[test]
+fn maybe_matches_option() {
+    check_property(3000, |rng| {
+        let (a, b) = (option(rng), option(rng));
+        let r = result(rng);
+        let k = rng.range(-5, 6) as i32;
+        let modulus = rng.range(1, 5) as i32;
+        let f = |x: i32| x.wrapping_mul(k);
+        let pred = |x: &i32| x % modulus == 0;
+        let chain = |x: i32| if x > k { Some(x - k) } else { None };
+        let chain_m = |x: i32| m(chain(x));
+
+        assert_eq!(std_of(m(a).map(f)), a.map(f));
+        assert_eq!(m(a).map_or(k, f), a.map_or(k, f));
+        assert_eq!(m(a).map_or_else(|| k, f), a.map_or_else(|| k, f));
+        assert_eq!(std_of(m(a).and_then(chain_m)), a.and_then(chain));
+        assert_eq!(std_of(m(a).and(m(b))), a.and(b));
+        assert_eq!(std_of(m(a).or(m(b))), a.or(b));
+        assert_eq!(std_of(m(a).or_else(|| m(b))), a.or_else(|| b));
+        assert_eq!(std_of(m(a).xor(m(b))), a.xor(b));
+        assert_eq!(std_of(m(a).filter(pred)), a.filter(pred));
+        assert_eq!(std_of(m(a).zip(m(b))), a.zip(b));
+        assert_eq!(std_res(m(a).ok_or(7u8)), a.ok_or(7u8));
+        assert_eq!(std_res(m(a).ok_or_else(|| 7u8)), a.ok_or_else(|| 7u8));
+        assert_eq!(m(a).unwrap_or(k), a.unwrap_or(k));
+        assert_eq!(m(a).unwrap_or_default(), a.unwrap_or_default());
+        assert_eq!(
+            m(a).is_just_and(pred_owned(modulus)),
+            a.is_some_and(pred_owned(modulus))
+        );
+        assert_eq!(
+            m(a).is_nothing_or(pred_owned(modulus)),
+            a.is_none_or(pred_owned(modulus))
+        );
+        assert_eq!(
+            m(a).iter().collect::<Vec<_>>(),
+            a.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(m(a).into_iter().sum::<i32>(), a.into_iter().sum::<i32>());
+        assert_eq!(m(a) < m(b), a < b);
+        assert_eq!(m(a).cmp(&m(b)), a.cmp(&b));
+
+        // 入れ子
+        let nested = if rng.bool() { Some(a) } else { None };
+        assert_eq!(
+            std_of(Maybe::from(nested.map(m)).flatten()),
+            nested.flatten()
+        );
+        let with_result = if rng.bool() { Some(r) } else { None };
+        assert_eq!(
+            std_res(Maybe::from(with_result.map(o)).transpose().map(std_of)),
+            with_result.transpose()
+        );
+        let pair = a.zip(b);
+        let (x, y) = Maybe::from(pair).unzip();
+        assert_eq!((std_of(x), std_of(y)), pair.unzip());
+
+        // 書き換え
+        let (mut ours, mut theirs) = (m(a), a);
+        assert_eq!(std_of(ours.take()), theirs.take());
+        assert_eq!(std_of(ours), theirs);
+        let (mut ours, mut theirs) = (m(a), a);
+        assert_eq!(std_of(ours.replace(k)), theirs.replace(k));
+        assert_eq!(
+            *ours.get_or_insert_with(|| 0),
+            *theirs.get_or_insert_with(|| 0)
+        );
+        if let Maybe::Just(x) = ours.as_mut() {
+            *x += 1;
+        }
+        if let Some(x) = theirs.as_mut() {
+            *x += 1;
+        }
+        assert_eq!(std_of(ours), theirs);
+    });
+}
+
+fn pred_owned(modulus: i32) -> impl Fn(i32) -> bool {
+    move |x| x % modulus == 0
+}
+
+#[test]
+fn outcome_matches_result() {
+    check_property(3000, |rng| {
+        let (a, b) = (result(rng), result(rng));
+        let k = rng.range(-5, 6) as i32;
+        let f = |x: i32| x.wrapping_mul(k);
+        let g = |e: u8| e as i32 * 10;
+        let chain = |x: i32| if x > k { Ok(x - k) } else { Err(9u8) };
+        let recover = |e: u8| {
+            if e.is_multiple_of(2) {
+                Ok(e as i32)
+            } else {
+                Err(e as u16)
+            }
+        };
+
+        assert_eq!(std_res(o(a).map(f)), a.map(f));
+        assert_eq!(std_res(o(a).map_err(g)), a.map_err(g));
+        assert_eq!(o(a).map_or(k, f), a.map_or(k, f));
+        assert_eq!(o(a).map_or_else(g, f), a.map_or_else(g, f));
+        assert_eq!(std_res(o(a).and_then(|x| o(chain(x)))), a.and_then(chain));
+        assert_eq!(std_res(o(a).and(o(b))), a.and(b));
+        assert_eq!(std_res(o(a).or(o(b))), a.or(b));
+        assert_eq!(
+            std_res(o(a).or_else(|e| Outcome::from(recover(e)))),
+            a.or_else(recover)
+        );
+        assert_eq!(std_of(o(a).success()), a.ok());
+        assert_eq!(std_of(o(a).failure()), a.err());
+        assert_eq!(o(a).unwrap_or(k), a.unwrap_or(k));
+        assert_eq!(o(a).unwrap_or_else(g), a.unwrap_or_else(g));
+        assert_eq!(o(a).unwrap_or_default(), a.unwrap_or_default());
+        assert_eq!(o(a).is_success(), a.is_ok());
+        assert_eq!(o(a).is_failure_and(|e| e > 2), a.is_err_and(|e| e > 2));
+        assert_eq!(
+            o(a).iter().collect::<Vec<_>>(),
+            a.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(o(a).cmp(&o(b)), a.cmp(&b));
+
+        let inner = if rng.bool() { a.ok() } else { None };
+        let wrapped: Result<Option<i32>, u8> = if rng.bool() { Ok(inner) } else { Err(3) };
+        assert_eq!(
+            std_of(
+                Outcome::from(wrapped.map(Maybe::from))
+                    .transpose()
+                    .map(std_res)
+            ),
+            wrapped.transpose()
+        );
+    });
+}
+
+#[test]
+fn collecting_matches_std() {
+    check_property(1000, |rng| {
+        let options: Vec<Option<i32>> = (0..rng.range(0, 6)).map(|_| option(rng)).collect();
+        let ours: Maybe<Vec<i32>> = options.iter().copied().map(m).collect();
+        let theirs: Option<Vec<i32>> = options.iter().copied().collect();
+        assert_eq!(std_of(ours), theirs);
+
+        let results: Vec<Result<i32, u8>> = (0..rng.range(0, 6)).map(|_| result(rng)).collect();
+        let ours: Outcome<Vec<i32>, u8> = results.iter().copied().map(o).collect();
+        let theirs: Result<Vec<i32>, u8> = results.iter().copied().collect();
+        assert_eq!(std_res(ours), theirs);
+    });
+}
@@ -0,0 +1,47 @@
+// ./src/others/collections.rs の statistics をそのまま写したもの(比べる相手)
+// 板書のモジュールは main.rs からしか読めないので、1文字も変えずにここに置く(clippy の指摘は allow で黙らせる)
+// tests/statistics_regression.rs と benches/statistics.rs から読む
+#![allow(unused_variables, clippy::needless_return, clippy::manual_is_multiple_of)]
+
+//平均値
+pub fn mean(i_l:&[i32]) -> f64 {
+	use std::collections::HashMap;
+	let mut material_mean:HashMap<&str,i32> = HashMap::new();
+	material_mean.entry("sum").or_insert(0);
+	for e in i_l.iter() {
+		let total = material_mean.entry("sum").or_insert(*e);
+		*total += *e;
+	}
+	material_mean.entry("num of elem").or_insert(i_l.len() as i32);
+	let result = *material_mean.get("sum").unwrap() as f64 / *material_mean.get("num of elem").unwrap() as f64;
+	return result
+}
+// 中央値
+pub fn median(i_l:&mut [i32]) -> f64 {
+	i_l.sort();
+	let mid = i_l.len()/2;
+	if i_l.len()%2 == 0 { // eve
+		return ( i_l[mid-1] as f64  + i_l[mid] as f64 )/ 2.
+	} else { // odd
+		return i_l[mid] as f64
+	}
+}
+// 最頻値
+pub fn mode(i_l:& [i32]) -> Vec<i32> {
+	use std::collections::HashMap;
+	let mut num_count:HashMap<String,i32> = HashMap::new();
+	for n in i_l.iter() {
+		let ct = num_count.entry(n.to_string()).or_insert(0);
+		*ct += 1;
+	}
+	let mut ct_vec:Vec<&i32> = Vec::new();
+	for (k, v) in num_count.iter() { ct_vec.push(v); }
+	let mut result:Vec<i32> = Vec::new();
+	for (k, v) in  num_count.iter() {
+		if v == ct_vec.iter().max().unwrap() as &i32 { 
+			result.push( k.parse().unwrap() ); 
+		}
+	}
+	result.sort();
+	return result
+}
@@ -0,0 +1,95 @@
+// numerals の往復テスト
+// 乱数の数をどの書き方で書いても parse で元に戻ること、
+// 書き方どうしを混ぜた文字列(1億2345万 と 一億二千三百四十五万)も同じ数になることを確かめる
+
+mod property;
+
+use property::{check_property, Rng};
+use rust_etude_dev::numerals::{self, Style};
+
+// 0 の多い数や桁の境目も出るようにする
+fn number(rng: &mut Rng) -> i128 {
+    let digits = rng.range(1, 39) as u32;
+    let mut n: i128 = 0;
+    for _ in 0..digits {
+        let d = if rng.range(0, 3) == 0 {
+            0
+        } else {
+            rng.range(0, 10)
+        };
+        n = n * 10 + d as i128;
+    }
+    if rng.range(0, 10) == 0 {
+        n = 10i128.pow(rng.range(0, 38) as u32);
+    }
+    if rng.bool() {
+        -n
+    } else {
+        n
+    }
+}
+
+#[test]
+fn every_style_round_trips() {
+    check_property(3000, |rng| {
+        let n = number(rng);
+        for &style in Style::ALL.iter() {
+            let s = numerals::format(n, style);
+            assert_eq!(numerals::parse(&s), Ok(n), "{:?} {}", style, s);
+        }
+    });
+}
+
+#[test]
+fn extremes_round_trip() {
+    for &n in &[0, 1, 9, 10, 11, 9999, 10_000, 10_001, i128::MAX, i128::MIN] {
+        for &style in Style::ALL.iter() {
+            let s = numerals::format(n, style);
+            assert_eq!(numerals::parse(&s), Ok(n), "{:?} {}", style, s);
+        }
+    }
+    for &style in Style::ALL.iter() {
+        let s = numerals::format_unsigned(u128::MAX, style);
+        assert_eq!(numerals::parse_unsigned(&s), Ok(u128::MAX), "{}", s);
+        // i128 には入らない
+        assert!(numerals::parse(&s).is_err());
+    }
+}
+
+// 万 の区切りごとに Man と Kanji を入れ替えても同じ数になる
+#[test]
+fn mixed_groups_read_the_same() {
+    check_property(1000, |rng| {
+        let n = number(rng).unsigned_abs();
+        let man = numerals::format_unsigned(n, Style::Man);
+        let units = ['万', '億', '兆', '京', '垓', '秭', '穣', '溝', '澗'];
+        let mut mixed = String::new();
+        let mut part = String::new();
+        let last = man.chars().count() - 1;
+        for (i, c) in man.chars().enumerate() {
+            part.push(c);
+            if units.contains(&c) || i == last {
+                let (digits, unit) = match part.pop() {
+                    Some(u) if units.contains(&u) => (part.clone(), Some(u)),
+                    Some(d) => (format!("{}{}", part, d), None),
+                    None => unreachable!(),
+                };
+                if rng.bool() {
+                    let g: u128 = digits.parse().unwrap();
+                    mixed.push_str(&numerals::format_unsigned(g, Style::Kanji));
+                } else {
+                    mixed.push_str(&digits);
+                }
+                mixed.extend(unit);
+                part.clear();
+            }
+        }
+        assert_eq!(
+            numerals::parse_unsigned(&mixed),
+            Ok(n),
+            "{} → {}",
+            man,
+            mixed
+        );
+    });
+}
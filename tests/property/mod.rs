@@ -0,0 +1,49 @@
+// 性質テストの道具(common と同じく property/mod.rs に置くのでテスト出力に区域は出ない)
+#![allow(dead_code)] // テストファイルごとに使う道具が違う
+
+/// 性質テスト用の小さな疑似乱数(xorshift64*)
+/// 外部クレートを入れずに済ませるため。種が同じなら同じ列になるので失敗も再現できる
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // 種が 0 だと 0 しか出なくなるのでずらす
+        Rng(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// lo 以上 hi 未満の整数
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo) as u64) as i64
+    }
+
+    /// 0.0 以上 1.0 未満
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+}
+
+/// 種を変えながら cases 回 f を呼ぶ。失敗したら種を出して止まる
+#[track_caller]
+pub fn check_property(cases: u64, mut f: impl FnMut(&mut Rng)) {
+    for seed in 0..cases {
+        let mut rng = Rng::new(seed);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut rng)));
+        if let Err(e) = result {
+            eprintln!("性質テストが 種 {} で失敗しました", seed);
+            std::panic::resume_unwind(e);
+        }
+    }
+}
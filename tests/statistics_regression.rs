@@ -0,0 +1,78 @@
+// statistics の mean, median, mode が ./src/others/collections.rs の statistics と同じ答えを出すことを確かめる
+// 板書の方は notebook_statistics に写してある(文字列にして数える mode と、並べ替える median)
+
+mod notebook_statistics;
+mod property;
+
+use notebook_statistics as notebook;
+use property::{check_property, Rng};
+use rust_etude_dev::statistics;
+use std::collections::HashMap;
+
+// 板書の mean は i32 で足すので、あふれない大きさにしておく
+fn values(rng: &mut Rng) -> Vec<i32> {
+    let spread = [3, 50, 100_000][rng.range(0, 3) as usize];
+    (0..rng.range(1, 200))
+        .map(|_| rng.range(-spread, spread + 1) as i32)
+        .collect()
+}
+
+fn check_same(xs: &[i32]) {
+    assert_eq!(statistics::mean(xs), Some(notebook::mean(xs)), "{:?}", xs);
+    assert_eq!(
+        statistics::median(xs),
+        Some(notebook::median(&mut xs.to_vec())),
+        "{:?}",
+        xs
+    );
+    assert_eq!(statistics::mode(xs), notebook::mode(xs), "{:?}", xs);
+}
+
+#[test]
+fn same_answers_as_notebook() {
+    check_property(3000, |rng| check_same(&values(rng)));
+}
+
+#[test]
+fn notebook_examples() {
+    check_same(&[43, 57, 57, 63, 66, 82, 98]);
+    check_same(&[57, 43, 63, 57, 98, 66, 82, 100]);
+    check_same(&[43, 57, 57, 63, 66, 66, 98]);
+    check_same(&[0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn large_inputs() {
+    let mut rng = Rng::new(45);
+    let xs: Vec<i32> = (0..200_000).map(|_| rng.range(-500, 500) as i32).collect();
+    check_same(&xs);
+    // 偏った並び(整列済み, 逆順, 全部同じ)でも同じ
+    let mut sorted = xs.clone();
+    sorted.sort();
+    check_same(&sorted);
+    sorted.reverse();
+    check_same(&sorted);
+    check_same(&[7; 10_001]);
+}
+
+// 鍵がぶつかりやすそうな値(2 の累乗の倍数, 負の数, 端の値)も別々に数える
+#[test]
+fn keys_do_not_collide() {
+    let xs: Vec<i64> = (0..64)
+        .flat_map(|i| vec![1i64 << i, (1i64 << i).wrapping_neg(), (1 << (i / 2)) * 1024])
+        .chain(vec![i64::MIN, i64::MAX, 0, -1])
+        .collect();
+    let mut expected: HashMap<i64, usize> = HashMap::new();
+    for &x in &xs {
+        *expected.entry(x).or_insert(0) += 1;
+    }
+    let best = *expected.values().max().unwrap();
+    let mut want: Vec<i64> = expected
+        .into_iter()
+        .filter(|&(_, n)| n == best)
+        .map(|(x, _)| x)
+        .collect();
+    want.sort();
+    assert_eq!(statistics::mode(&xs), want);
+    assert_eq!(statistics::mode(&[0.0, -0.0, 1.5]), vec![0.0]);
+}
@@ -0,0 +1,46 @@
+// text モジュールの関数が呼び出し1回あたり何回 String を確保するかの検査
+// 変える必要のない入力では 0回、変えたときでも 1回で済んでいることを確かめる
+
+mod common;
+
+use common::assert_allocations;
+use rust_etude_dev::text;
+
+#[test]
+fn unchanged_input_does_not_allocate() {
+    assert_allocations(0, || text::first_word("hello world"));
+    assert_allocations(0, || text::lowercase("hello world"));
+    assert_allocations(0, || text::uppercase("HELLO"));
+    assert_allocations(0, || text::capitalize("Hello"));
+    assert_allocations(0, || text::collapse_whitespace(" hello world "));
+    assert_allocations(0, || text::pig_latin_word("贵樣"));
+    assert_allocations(0, || text::pig_latin_word(""));
+    assert_allocations(0, || text::greeting(""));
+}
+
+#[test]
+fn changed_input_allocates_once() {
+    assert_allocations(1, || text::lowercase("Hello"));
+    assert_allocations(1, || text::capitalize("hello"));
+    assert_allocations(1, || text::collapse_whitespace("hello \t world"));
+    assert_allocations(1, || text::pig_latin_word("hello"));
+    assert_allocations(1, || text::pig_latin_word("enum"));
+    assert_allocations(1, || text::greeting("もるか"));
+}
+
+#[test]
+fn batch_of_words_allocates_only_for_translated_ones() {
+    let words = ["hello", "123", "japan", "贵樣", "", "open"];
+    let translated = assert_allocations(3 + 1, || {
+        // 結果を入れる Vec の確保で1回
+        let mut out = Vec::with_capacity(words.len());
+        for w in &words {
+            out.push(text::pig_latin_word(w));
+        }
+        out
+    });
+    assert_eq!(
+        translated,
+        ["ellohay", "123", "apanjay", "贵樣", "", "openhay"]
+    );
+}
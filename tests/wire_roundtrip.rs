@@ -0,0 +1,201 @@
+// wire の往復テスト
+// 乱数の Message を書いて、好きな所で切って読ませても元に戻ること、
+// 壊したバイト列を読ませてもパニックせず、壊れていないフレームは読めることを確かめる
+// 最後に 127.0.0.1 で実際にやりとりする
+
+mod property;
+
+use property::{check_property, Rng};
+use rust_etude_dev::message::Message;
+use rust_etude_dev::wire::{self, BinaryDecoder, TextDecoder};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+fn int(rng: &mut Rng) -> i32 {
+    match rng.range(0, 4) {
+        0 => [0, -1, i32::MIN, i32::MAX][rng.range(0, 4) as usize],
+        1 => rng.range(-300, 300) as i32,
+        _ => rng.next_u64() as i32,
+    }
+}
+
+fn text(rng: &mut Rng) -> String {
+    // エスケープが要る文字と多バイト文字を混ぜる
+    let alphabet = [
+        'a', 'Z', ' ', '"', '\\', '\n', '\r', '\t', '\u{0}', '\u{7f}', '#', 'ぷ', '臭', '🐄', '}',
+    ];
+    let n = rng.range(0, 20);
+    (0..n)
+        .map(|_| alphabet[rng.range(0, alphabet.len() as i64) as usize])
+        .collect()
+}
+
+fn message(rng: &mut Rng) -> Message {
+    match rng.range(0, 4) {
+        0 => Message::Quit,
+        1 => Message::Move {
+            x: int(rng),
+            y: int(rng),
+        },
+        2 => Message::Write(text(rng)),
+        _ => Message::ChangeColor(int(rng), int(rng), int(rng)),
+    }
+}
+
+// bytes を乱数の長さに切って渡していく
+fn chunks<'a>(rng: &mut Rng, mut bytes: &'a [u8]) -> Vec<&'a [u8]> {
+    let mut out = Vec::new();
+    while !bytes.is_empty() {
+        let n = (rng.range(1, 12) as usize).min(bytes.len());
+        out.push(&bytes[..n]);
+        bytes = &bytes[n..];
+    }
+    out
+}
+
+#[test]
+fn binary_round_trip_in_random_pieces() {
+    check_property(500, |rng| {
+        let messages: Vec<Message> = (0..rng.range(0, 20)).map(|_| message(rng)).collect();
+        let mut bytes = Vec::new();
+        for m in &messages {
+            wire::encode_binary_into(m, &mut bytes).unwrap();
+        }
+        let mut d = BinaryDecoder::new();
+        let mut got = Vec::new();
+        for piece in chunks(rng, &bytes) {
+            d.feed(piece);
+            while let Some(m) = d.next_message().unwrap() {
+                got.push(m);
+            }
+        }
+        assert_eq!(got, messages);
+        assert_eq!(d.finish(), Ok(()));
+    });
+}
+
+#[test]
+fn text_round_trip_in_random_pieces() {
+    check_property(500, |rng| {
+        let messages: Vec<Message> = (0..rng.range(0, 20)).map(|_| message(rng)).collect();
+        let mut src = wire::text_header();
+        for m in &messages {
+            let line = wire::encode_text(m);
+            assert_eq!(line.matches('\n').count(), 1);
+            src.push_str(&line);
+        }
+        let mut d = TextDecoder::new();
+        let mut got = Vec::new();
+        for piece in chunks(rng, src.as_bytes()) {
+            d.feed(piece);
+            while let Some(m) = d.next_message().unwrap() {
+                got.push(m);
+            }
+        }
+        got.extend(d.finish().unwrap());
+        assert_eq!(got, messages);
+    });
+}
+
+#[test]
+fn corrupted_binary_never_panics_and_later_frames_survive() {
+    check_property(500, |rng| {
+        let mut bytes = Vec::new();
+        for _ in 0..rng.range(1, 8) {
+            wire::encode_binary_into(&message(rng), &mut bytes).unwrap();
+        }
+        // 本体の中だけを壊す(長さが壊れると区切りが分からなくなるので)
+        let len = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        for _ in 0..rng.range(1, 4) {
+            let i = rng.range(4, 4 + len as i64) as usize;
+            bytes[i] = rng.next_u64() as u8;
+        }
+        let tail = Message::Write(String::from("最後"));
+        wire::encode_binary_into(&tail, &mut bytes).unwrap();
+
+        let mut d = BinaryDecoder::new();
+        let mut last = None;
+        for piece in chunks(rng, &bytes) {
+            d.feed(piece);
+            loop {
+                match d.next_message() {
+                    Ok(Some(m)) => last = Some(m),
+                    Ok(None) => break,
+                    Err(_) => {}
+                }
+            }
+        }
+        assert_eq!(last, Some(tail));
+        assert_eq!(d.finish(), Ok(()));
+    });
+}
+
+#[test]
+fn garbage_text_never_panics() {
+    check_property(500, |rng| {
+        let mut src = Vec::new();
+        for _ in 0..rng.range(1, 8) {
+            src.extend_from_slice(wire::encode_text(&message(rng)).as_bytes());
+        }
+        for _ in 0..rng.range(1, 6) {
+            let i = rng.range(0, src.len() as i64) as usize;
+            src[i] = rng.next_u64() as u8;
+        }
+        let mut d = TextDecoder::new();
+        for piece in chunks(rng, &src) {
+            d.feed(piece);
+            while d.next_message().transpose().is_some() {}
+        }
+        let _ = d.finish();
+    });
+}
+
+#[test]
+fn loopback_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let sent = vec![
+        Message::Move { x: 3, y: -4 },
+        Message::Write(String::from("こんにちは")),
+        Message::ChangeColor(255, 128, 0),
+        Message::Quit,
+    ];
+    // 受け手は届いたものをテキストにして送り返す
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut d = BinaryDecoder::new();
+        let mut buf = [0; 3]; // わざと小さく読む
+        loop {
+            let n = stream.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            d.feed(&buf[..n]);
+            while let Some(m) = d.next_message().unwrap() {
+                stream.write_all(wire::encode_text(&m).as_bytes()).unwrap();
+                if m == Message::Quit {
+                    return;
+                }
+            }
+        }
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    for m in &sent {
+        stream.write_all(&wire::encode_binary(m).unwrap()).unwrap();
+    }
+    let mut d = TextDecoder::new();
+    let mut got = Vec::new();
+    let mut buf = [0; 5];
+    while got.len() < sent.len() {
+        let n = stream.read(&mut buf).unwrap();
+        assert!(n > 0, "途中で切れた");
+        d.feed(&buf[..n]);
+        while let Some(m) = d.next_message().unwrap() {
+            got.push(m);
+        }
+    }
+    server.join().unwrap();
+    assert_eq!(got, sent);
+}